@@ -0,0 +1,55 @@
+use bsv::{Interpreter, Script, ScriptFlags, ScriptLimits};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+
+/// Pushes `count` elements of `size` bytes then drops them all, so the stack and the script both grow with `count`.
+fn push_and_drop(count: usize, size: usize) -> Script {
+    let push = "ab".repeat(size);
+    let asm = format!("{}{}", format!("{} ", push).repeat(count), "OP_DROP ".repeat(count));
+    Script::from_asm_string(&format!("{}OP_1", asm)).unwrap()
+}
+
+/// Hashes a value `count` times, a long script over a small stack.
+fn hash_chain(count: usize) -> Script {
+    Script::from_asm_string(&format!("00{} OP_DROP OP_1", " OP_SHA256".repeat(count))).unwrap()
+}
+
+fn run(script: &Script) {
+    let mut interpreter = Interpreter::from_script(script, ScriptFlags::default());
+    assert!(interpreter.verify().is_success());
+}
+
+/// Runs scripts larger than the default policy allows.
+fn run_unlimited(script: &Script) {
+    let mut interpreter = Interpreter::from_script(script, ScriptFlags::default());
+    interpreter.set_limits(ScriptLimits {
+        max_script_size: usize::MAX,
+        ..ScriptLimits::default()
+    });
+    assert!(interpreter.verify().is_success());
+}
+
+fn large_scripts(c: &mut Criterion) {
+    let mut group = c.benchmark_group("interpreter");
+    group.sample_size(10);
+
+    for count in [1_000, 4_000] {
+        let script = push_and_drop(count, 100);
+        group.bench_with_input(BenchmarkId::new("push_and_drop", count), &script, |b, script| b.iter(|| run(script)));
+    }
+
+    // Every step checks the limits, the time per element should stay flat however deep the stack gets
+    for count in [100_000, 200_000, 400_000] {
+        let script = push_and_drop(count, 1);
+        group.bench_with_input(BenchmarkId::new("deep_stack", count), &script, |b, script| b.iter(|| run_unlimited(script)));
+    }
+
+    for count in [1_000, 10_000] {
+        let script = hash_chain(count);
+        group.bench_with_input(BenchmarkId::new("hash_chain", count), &script, |b, script| b.iter(|| run(script)));
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, large_scripts);
+criterion_main!(benches);
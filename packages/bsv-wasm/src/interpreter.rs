@@ -1,5 +1,9 @@
 use crate::{script::Script, transaction::Transaction};
-use bsv::{Interpreter as BSVInterpreter, State as BSVState};
+use bsv::{
+    Breakpoint, DebuggerStop, ExecutionFailure as BSVExecutionFailure, ExecutionPhase, Interpreter as BSVInterpreter, OpCodes, ScriptDebugger as BSVScriptDebugger, ScriptFlags, ScriptVerdict,
+    State as BSVState,
+};
+use std::str::FromStr;
 use wasm_bindgen::{prelude::*, JsError};
 
 #[wasm_bindgen]
@@ -17,20 +21,70 @@ impl From<Interpreter> for BSVInterpreter {
     }
 }
 
+/// Script verification flags, OR them together to pass to the Interpreter.
+#[wasm_bindgen]
+#[allow(non_camel_case_types)]
+pub enum ScriptFlag {
+    NONE = 0,
+    STRICTENC = 0x02,
+    DERSIG = 0x04,
+    LOW_S = 0x08,
+    NULLDUMMY = 0x10,
+    SIGPUSHONLY = 0x20,
+    MINIMALDATA = 0x40,
+    DISCOURAGE_UPGRADABLE_NOPS = 0x80,
+    CLEANSTACK = 0x100,
+    CHECKLOCKTIMEVERIFY = 0x200,
+    CHECKSEQUENCEVERIFY = 0x400,
+    MINIMALIF = 0x2000,
+    NULLFAIL = 0x4000,
+    SIGHASH_FORKID = 0x10000,
+    UTXO_AFTER_GENESIS = 0x80000,
+    UTXO_AFTER_CHRONICLE = 0x100000,
+}
+
+pub(crate) fn to_script_flags(flags: Option<u32>) -> ScriptFlags {
+    flags.map(ScriptFlags::from_bits).unwrap_or_default()
+}
+
+/// How a verification that succeeded finished, failed verifications throw instead.
+#[wasm_bindgen]
+pub enum ScriptVerdictKind {
+    Success,
+    EarlyReturn,
+    Coinbase,
+}
+
+pub(crate) fn to_verdict_kind(verdict: ScriptVerdict) -> Result<ScriptVerdictKind, JsError> {
+    match verdict {
+        ScriptVerdict::Success => Ok(ScriptVerdictKind::Success),
+        ScriptVerdict::EarlyReturn => Ok(ScriptVerdictKind::EarlyReturn),
+        ScriptVerdict::Coinbase { .. } => Ok(ScriptVerdictKind::Coinbase),
+        ScriptVerdict::Failure(e) => Err(e.into()),
+    }
+}
+
 #[wasm_bindgen]
 impl Interpreter {
-    pub fn from_transaction(tx: Transaction, txin_idx: usize) -> Result<Interpreter, JsError> {
-        Ok(Interpreter(BSVInterpreter::from_transaction(&tx.0, txin_idx)?))
+    /// Flags default to post-Genesis rules without any policy checks.
+    pub fn from_transaction(tx: Transaction, txin_idx: usize, flags: Option<u32>) -> Result<Interpreter, JsError> {
+        Ok(Interpreter(BSVInterpreter::from_transaction(&tx.0, txin_idx, to_script_flags(flags))?))
     }
 
-    pub fn from_script(script: Script) -> Interpreter {
-        Interpreter(BSVInterpreter::from_script(&script.0))
+    /// Flags default to post-Genesis rules without any policy checks.
+    pub fn from_script(script: Script, flags: Option<u32>) -> Interpreter {
+        Interpreter(BSVInterpreter::from_script(&script.0, to_script_flags(flags)))
     }
 
     pub fn run(&mut self) -> Result<(), JsError> {
         Ok(self.0.run()?)
     }
 
+    /// Runs the scripts to completion, throwing unless they finish with a true value on top of the stack.
+    pub fn verify(&mut self) -> Result<ScriptVerdictKind, JsError> {
+        to_verdict_kind(self.0.verify())
+    }
+
     #[wasm_bindgen(js_name = "next")]
     pub fn step(&mut self) -> Result<Option<State>, JsError> {
         let state = match self.0.next() {
@@ -45,6 +99,167 @@ impl Interpreter {
     pub fn get_state(&self) -> State {
         State(self.0.state())
     }
+
+    /// Where and why the script failed, if it has.
+    pub fn get_failure(&self) -> Option<ExecutionFailure> {
+        self.0.failure().map(ExecutionFailure)
+    }
+
+    /// Longest number in bytes opcodes may pop off the stack post-Genesis, pre-Genesis is always 4 bytes.
+    pub fn set_max_script_num_length(&mut self, length: usize) {
+        self.0.set_max_script_num_length(length)
+    }
+
+    /// Sets the post-Genesis policy limits from an object with the fields of `ScriptLimits`, eg. `max_stack_memory`.
+    pub fn set_limits(&mut self, limits: JsValue) -> Result<(), JsError> {
+        self.0.set_limits(serde_wasm_bindgen::from_value(limits)?);
+        Ok(())
+    }
+
+    pub fn get_limits(&self) -> Result<JsValue, JsError> {
+        Ok(serde_wasm_bindgen::to_value(&self.0.limits())?)
+    }
+
+    /// Records the executed opcodes returned by `State.get_executed_script`, off by default.
+    pub fn set_record_executed_opcodes(&mut self, enabled: bool) {
+        self.0.set_record_executed_opcodes(enabled)
+    }
+
+    /// Coinbase inputs are not executed, `run` and `verify` succeed straight away.
+    pub fn is_coinbase(&self) -> bool {
+        self.0.is_coinbase()
+    }
+
+    pub fn get_coinbase_height(&self) -> Option<u32> {
+        self.0.coinbase_height()
+    }
+
+    /// Answers every signature and locktime check with `is_valid`, so scripts can be run without a spending transaction.
+    pub fn set_mock_signature_checker(&mut self, is_valid: bool) {
+        match is_valid {
+            true => self.0.set_signature_checker(bsv::MockSignatureChecker::always_valid()),
+            false => self.0.set_signature_checker(bsv::MockSignatureChecker::always_invalid()),
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub struct ScriptDebugger(pub(crate) BSVScriptDebugger);
+
+#[wasm_bindgen]
+impl ScriptDebugger {
+    #[wasm_bindgen(constructor)]
+    pub fn new(interpreter: Interpreter) -> ScriptDebugger {
+        ScriptDebugger(BSVScriptDebugger::new(interpreter.0))
+    }
+
+    /// Stops before the opcode at this index of the unlocking or locking script, counting every opcode and push. Returns the breakpoint id.
+    pub fn add_index_breakpoint(&mut self, index: usize, locking_script: bool) -> usize {
+        let phase = match locking_script {
+            true => ExecutionPhase::Locking,
+            false => ExecutionPhase::Unlocking,
+        };
+        self.0.add_breakpoint(Breakpoint::Index { phase, index })
+    }
+
+    /// Stops before any instance of the opcode, eg. "OP_CHECKSIG". Returns the breakpoint id.
+    pub fn add_opcode_breakpoint(&mut self, opcode: &str) -> Result<usize, JsError> {
+        let opcode = OpCodes::from_str(opcode).map_err(|_| JsError::new(&format!("Unknown OpCode {}", opcode)))?;
+        Ok(self.0.add_breakpoint(Breakpoint::OpCode(opcode)))
+    }
+
+    pub fn remove_breakpoint(&mut self, id: usize) -> bool {
+        self.0.remove_breakpoint(id)
+    }
+
+    pub fn clear_breakpoints(&mut self) {
+        self.0.clear_breakpoints()
+    }
+
+    pub fn step(&mut self) -> Result<Option<State>, JsError> {
+        match self.0.step() {
+            Some(v) => Ok(Some(State(v?))),
+            None => Ok(None),
+        }
+    }
+
+    pub fn step_back(&mut self) -> bool {
+        self.0.step_back()
+    }
+
+    /// Returns the id of the breakpoint that was hit, or undefined if execution finished.
+    pub fn resume(&mut self) -> Result<Option<usize>, JsError> {
+        match self.0.resume()? {
+            DebuggerStop::Breakpoint(id) => Ok(Some(id)),
+            DebuggerStop::Finished => Ok(None),
+        }
+    }
+
+    /// Position of the next opcode in the original script, undefined once execution has finished.
+    pub fn get_position(&self) -> Result<JsValue, JsError> {
+        Ok(serde_wasm_bindgen::to_value(&self.0.position())?)
+    }
+
+    pub fn get_steps_taken(&self) -> usize {
+        self.0.steps_taken()
+    }
+
+    pub fn get_state(&self) -> State {
+        State(self.0.state())
+    }
+
+    pub fn get_failure(&self) -> Option<ExecutionFailure> {
+        self.0.failure().map(ExecutionFailure)
+    }
+}
+
+#[wasm_bindgen]
+pub struct ExecutionFailure(pub(crate) BSVExecutionFailure);
+
+impl From<BSVExecutionFailure> for ExecutionFailure {
+    fn from(v: BSVExecutionFailure) -> ExecutionFailure {
+        ExecutionFailure(v)
+    }
+}
+
+#[wasm_bindgen]
+impl ExecutionFailure {
+    pub fn get_script_index(&self) -> usize {
+        self.0.script_index
+    }
+
+    pub fn get_byte_offset(&self) -> usize {
+        self.0.byte_offset
+    }
+
+    /// True if the failure happened in the locking script.
+    pub fn is_locking_script(&self) -> bool {
+        self.0.phase == bsv::ExecutionPhase::Locking
+    }
+
+    pub fn get_opcode(&self) -> Option<String> {
+        self.0.opcode.map(|x| x.to_string())
+    }
+
+    pub fn get_stack(&self) -> Result<JsValue, JsError> {
+        Ok(serde_wasm_bindgen::to_value(&self.0.stack)?)
+    }
+
+    pub fn get_alt_stack(&self) -> Result<JsValue, JsError> {
+        Ok(serde_wasm_bindgen::to_value(&self.0.alt_stack)?)
+    }
+
+    pub fn get_code(&self) -> String {
+        self.0.code.clone()
+    }
+
+    pub fn get_message(&self) -> String {
+        self.0.message.clone()
+    }
+
+    pub fn to_json(&self) -> Result<JsValue, JsError> {
+        Ok(serde_wasm_bindgen::to_value(&self.0)?)
+    }
 }
 
 #[wasm_bindgen]
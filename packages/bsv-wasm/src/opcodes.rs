@@ -248,11 +248,11 @@ pub enum JsOpCodes {
     // --------------------------------------------------------------------------------------------
     /// Transaction is invalid unless occuring in an unexecuted OP_IF branch
     OP_RESERVED = 80,
-    /// Transaction is invalid unless occuring in an unexecuted OP_IF branch
+    /// Transaction is invalid unless occuring in an unexecuted OP_IF branch. After Chronicle, pushes the transaction's version.
     OP_VER = 98,
-    /// Transaction is invalid even when occuring in an unexecuted OP_IF branch
+    /// Transaction is invalid even when occuring in an unexecuted OP_IF branch. After Chronicle, `OP_VER OP_EQUAL OP_IF`.
     OP_VERIF = 101,
-    /// Transaction is invalid even when occuring in an unexecuted OP_IF branch
+    /// Transaction is invalid even when occuring in an unexecuted OP_IF branch. After Chronicle, `OP_VER OP_EQUAL OP_NOTIF`.
     OP_VERNOTIF = 102,
     /// Transaction is invalid unless occuring in an unexecuted OP_IF branch
     OP_RESERVED1 = 137,
@@ -260,15 +260,15 @@ pub enum JsOpCodes {
     OP_RESERVED2 = 138,
     /// The word is ignored. Does not mark transaction as invalid.
     OP_NOP1 = 176,
-    /// The word is ignored. Does not mark transaction as invalid.
+    /// The word is ignored. Does not mark transaction as invalid. OP_SUBSTR after Chronicle, see `ChronicleOpCodes`.
     OP_NOP4 = 179,
-    /// The word is ignored. Does not mark transaction as invalid.
+    /// The word is ignored. Does not mark transaction as invalid. OP_LEFT after Chronicle, see `ChronicleOpCodes`.
     OP_NOP5 = 180,
-    /// The word is ignored. Does not mark transaction as invalid.
+    /// The word is ignored. Does not mark transaction as invalid. OP_RIGHT after Chronicle, see `ChronicleOpCodes`.
     OP_NOP6 = 181,
-    /// The word is ignored. Does not mark transaction as invalid.
+    /// The word is ignored. Does not mark transaction as invalid. OP_LSHIFTNUM after Chronicle, see `ChronicleOpCodes`.
     OP_NOP7 = 182,
-    /// The word is ignored. Does not mark transaction as invalid.
+    /// The word is ignored. Does not mark transaction as invalid. OP_RSHIFTNUM after Chronicle, see `ChronicleOpCodes`.
     OP_NOP8 = 183,
     /// The word is ignored. Does not mark transaction as invalid.
     OP_NOP9 = 184,
@@ -281,8 +281,26 @@ pub enum JsOpCodes {
     // --------------------------------------------------------------------------------------------
     // Disabled words
     // --------------------------------------------------------------------------------------------
-    /// The input is multiplied by 2
+    /// The input is multiplied by 2. Re-enabled by Chronicle.
     OP_2MUL = 141,
-    /// The input is divided by 2
+    /// The input is divided by 2. Re-enabled by Chronicle.
     OP_2DIV = 142,
 }
+
+/// The opcodes Chronicle gives to OP_NOP4 - OP_NOP8, which keep their names in `OpCodes`.
+///
+/// An enum cannot hold two names for the same value, so they are exported separately.
+#[wasm_bindgen(js_name = ChronicleOpCodes)]
+#[allow(non_camel_case_types)]
+pub enum JsChronicleOpCodes {
+    /// Keeps `size` bytes from position `begin`: `data begin size OP_SUBSTR`. OP_NOP4 before Chronicle.
+    OP_SUBSTR = 179,
+    /// Keeps the leftmost `size` bytes: `data size OP_LEFT`. OP_NOP5 before Chronicle.
+    OP_LEFT = 180,
+    /// Keeps the rightmost `size` bytes: `data size OP_RIGHT`. OP_NOP6 before Chronicle.
+    OP_RIGHT = 181,
+    /// Shifts a number left by `n` bits, keeping its sign: `a n OP_LSHIFTNUM`. OP_NOP7 before Chronicle.
+    OP_LSHIFTNUM = 182,
+    /// Shifts a number right by `n` bits, keeping its sign: `a n OP_RSHIFTNUM`. OP_NOP8 before Chronicle.
+    OP_RSHIFTNUM = 183,
+}
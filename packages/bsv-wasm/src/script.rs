@@ -1,6 +1,8 @@
 use bsv::Script as BSVScript;
 use wasm_bindgen::prelude::*;
 
+use crate::interpreter::to_script_flags;
+
 #[wasm_bindgen]
 pub struct Script(pub(crate) BSVScript);
 
@@ -37,6 +39,13 @@ impl Script {
         Ok(Script(BSVScript::from_bytes(bytes)?))
     }
 
+    /**
+     * Parses any script so it serialises back to the same bytes, keeping what cannot be parsed as a raw tail
+     */
+    pub fn from_bytes_lenient(bytes: &[u8]) -> Script {
+        Script(BSVScript::from_bytes_lenient(bytes).script)
+    }
+
     pub fn from_asm_string(asm_string: &str) -> Result<Script, wasm_bindgen::JsError> {
         Ok(Script(BSVScript::from_asm_string(asm_string)?))
     }
@@ -71,4 +80,22 @@ impl Script {
     pub fn remove_codeseparators(&mut self) {
         BSVScript::remove_codeseparators(&mut self.0)
     }
+
+    pub fn count_sigops(&self, accurate: bool) -> usize {
+        self.0.count_sigops(accurate)
+    }
+
+    /**
+     * Works out the stack effect of the script without executing it, defaulting to post-Genesis flags
+     */
+    pub fn analyze_stack(&self, flags: Option<u32>) -> Result<wasm_bindgen::JsValue, wasm_bindgen::JsError> {
+        Ok(serde_wasm_bindgen::to_value(&self.0.analyze_stack(to_script_flags(flags)))?)
+    }
+
+    /**
+     * Recognises the standard output type of the script, eg. P2PKH, with the fields extracted from it
+     */
+    pub fn classify(&self) -> Result<wasm_bindgen::JsValue, wasm_bindgen::JsError> {
+        Ok(serde_wasm_bindgen::to_value(&self.0.classify())?)
+    }
 }
@@ -63,6 +63,35 @@ pub enum SigHash {
      * SINGLE | ANYONECANPAY
      */
     Legacy_InputOutput = 0x83,
+
+    /**
+     * Set with FORKID from Chronicle to sign with the Original Transaction Digest Algorithm (OTDA) instead of BIP143
+     */
+    CHRONICLE = 0x20,
+    /**
+     * ALL | FORKID | CHRONICLE
+     */
+    OTDA_InputsOutputs = 0x61,
+    /**
+     * NONE | FORKID | CHRONICLE
+     */
+    OTDA_Inputs = 0x62,
+    /**
+     * SINGLE | FORKID | CHRONICLE
+     */
+    OTDA_InputsOutput = 0x63,
+    /**
+     * ALL | ANYONECANPAY | FORKID | CHRONICLE
+     */
+    OTDA_InputOutputs = 0xe1,
+    /**
+     * NONE | ANYONECANPAY | FORKID | CHRONICLE
+     */
+    OTDA_Input = 0xe2,
+    /**
+     * SINGLE | ANYONECANPAY | FORKID | CHRONICLE
+     */
+    OTDA_InputOutput = 0xe3,
 }
 
 impl From<SigHash> for bsv::SigHash {
@@ -82,6 +111,13 @@ impl From<SigHash> for bsv::SigHash {
             SigHash::Legacy_InputOutputs => bsv::SigHash::Legacy_InputOutputs,
             SigHash::Legacy_Input => bsv::SigHash::Legacy_Input,
             SigHash::Legacy_InputOutput => bsv::SigHash::Legacy_InputOutput,
+            SigHash::CHRONICLE => bsv::SigHash::CHRONICLE,
+            SigHash::OTDA_InputsOutputs => bsv::SigHash::OTDA_InputsOutputs,
+            SigHash::OTDA_Inputs => bsv::SigHash::OTDA_Inputs,
+            SigHash::OTDA_InputsOutput => bsv::SigHash::OTDA_InputsOutput,
+            SigHash::OTDA_InputOutputs => bsv::SigHash::OTDA_InputOutputs,
+            SigHash::OTDA_Input => bsv::SigHash::OTDA_Input,
+            SigHash::OTDA_InputOutput => bsv::SigHash::OTDA_InputOutput,
         }
     }
 }
@@ -103,6 +139,13 @@ impl From<bsv::SigHash> for SigHash {
             bsv::SigHash::Legacy_InputOutputs => SigHash::Legacy_InputOutputs,
             bsv::SigHash::Legacy_Input => SigHash::Legacy_Input,
             bsv::SigHash::Legacy_InputOutput => SigHash::Legacy_InputOutput,
+            bsv::SigHash::CHRONICLE => SigHash::CHRONICLE,
+            bsv::SigHash::OTDA_InputsOutputs => SigHash::OTDA_InputsOutputs,
+            bsv::SigHash::OTDA_Inputs => SigHash::OTDA_Inputs,
+            bsv::SigHash::OTDA_InputsOutput => SigHash::OTDA_InputsOutput,
+            bsv::SigHash::OTDA_InputOutputs => SigHash::OTDA_InputOutputs,
+            bsv::SigHash::OTDA_Input => SigHash::OTDA_Input,
+            bsv::SigHash::OTDA_InputOutput => SigHash::OTDA_InputOutput,
         }
     }
 }
@@ -7,6 +7,7 @@ pub use txin::*;
 pub use txout::*;
 
 use crate::{
+    interpreter::{to_script_flags, to_verdict_kind, ScriptVerdictKind},
     keypair::private_key::PrivateKey,
     script::Script,
     sighash::{SigHash, SighashSignature},
@@ -38,6 +39,10 @@ impl Transaction {
         self.0.get_ninputs()
     }
 
+    pub fn count_sigops(&self) -> usize {
+        self.0.count_sigops()
+    }
+
     pub fn get_noutputs(&self) -> usize {
         self.0.get_noutputs()
     }
@@ -280,4 +285,10 @@ impl Transaction {
     pub fn _verify(&self, pub_key: &PublicKey, sig: &SighashSignature, reverse_digest: bool) -> bool {
         self.0._verify(&pub_key.0, &sig.0, reverse_digest)
     }
+
+    /// Throws if the unlocking script of the input does not satisfy the locking script set on the TxIn, otherwise returns how it passed.
+    /// Flags default to post-Genesis rules without any policy checks.
+    pub fn verify_input(&self, index: usize, flags: Option<u32>) -> Result<ScriptVerdictKind, wasm_bindgen::JsError> {
+        to_verdict_kind(self.0.verify_input(index, to_script_flags(flags)))
+    }
 }
@@ -164,4 +164,9 @@ impl TxIn {
     pub fn is_coinbase(&self) -> bool {
         self.0.is_coinbase()
     }
+
+    /// Block height from the start of a coinbase script, undefined if this is not a coinbase input.
+    pub fn get_bip34_height(&self) -> Option<u32> {
+        self.0.get_bip34_height()
+    }
 }
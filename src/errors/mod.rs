@@ -146,6 +146,9 @@ pub enum BSVErrors {
     #[error("Error when deserialising Script: {0}")]
     DeserialiseScript(String),
 
+    #[error("Error when parsing Script: {}", crate::script::display_parse_errors(.0))]
+    ParseScript(Vec<crate::ScriptParseError>),
+
     #[error("Error when Serialising Script: {0} {1:?}")]
     SerialiseScript(String, #[source] Option<std::io::Error>),
 
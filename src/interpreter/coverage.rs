@@ -0,0 +1,241 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{BSVErrors, OpCodes, Script, ScriptBit};
+
+use super::{
+    debugger::ScriptPosition,
+    observer::{BranchEvent, ExecutionObserver, StepEvent},
+    opcode_stream::OpcodeStream,
+    state::State,
+    ExecutionPhase,
+};
+
+/// How many times each outcome of an OP_IF, OP_NOTIF, OP_VERIF or OP_VERNOTIF was taken.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BranchCoverage {
+    /// Times the statements following the conditional were executed.
+    pub taken: usize,
+    /// Times the OP_ELSE statements, or nothing if there is no OP_ELSE, were executed instead.
+    pub not_taken: usize,
+}
+
+impl BranchCoverage {
+    /// Both outcomes have been taken at least once.
+    pub fn is_covered(&self) -> bool {
+        self.taken > 0 && self.not_taken > 0
+    }
+}
+
+/// Hit counts and branch outcomes for one script, keyed by the byte offset of each ScriptBit.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScriptCoverage {
+    pub hits: BTreeMap<usize, usize>,
+    pub branches: BTreeMap<usize, BranchCoverage>,
+}
+
+impl ScriptCoverage {
+    fn merge(&mut self, other: &ScriptCoverage) {
+        for (offset, hits) in &other.hits {
+            *self.hits.entry(*offset).or_default() += hits;
+        }
+
+        for (offset, branch) in &other.branches {
+            let entry = self.branches.entry(*offset).or_default();
+            entry.taken += branch.taken;
+            entry.not_taken += branch.not_taken;
+        }
+    }
+}
+
+/// Records which opcodes and branch outcomes of the unlocking and locking scripts are executed, across any number of runs.
+///
+/// Attach the same collector to every Interpreter spending a locking script, or merge collectors from separate runs,
+/// then build a `CoverageReport` against the locking script to find what was never exercised.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CoverageCollector {
+    pub unlocking: ScriptCoverage,
+    pub locking: ScriptCoverage,
+}
+
+impl CoverageCollector {
+    pub fn new() -> CoverageCollector {
+        CoverageCollector::default()
+    }
+
+    pub fn script(&self, phase: ExecutionPhase) -> &ScriptCoverage {
+        match phase {
+            ExecutionPhase::Unlocking => &self.unlocking,
+            ExecutionPhase::Locking => &self.locking,
+        }
+    }
+
+    fn script_mut(&mut self, phase: ExecutionPhase) -> &mut ScriptCoverage {
+        match phase {
+            ExecutionPhase::Unlocking => &mut self.unlocking,
+            ExecutionPhase::Locking => &mut self.locking,
+        }
+    }
+
+    /// Adds the hits and branch outcomes of another collector, eg. one saved with `to_json_string` by another test run.
+    pub fn merge(&mut self, other: &CoverageCollector) {
+        self.unlocking.merge(&other.unlocking);
+        self.locking.merge(&other.locking);
+    }
+
+    /// Lists every ScriptBit of `script` with what was recorded for it when run as the given phase, including those never executed.
+    pub fn report(&self, script: &Script, phase: ExecutionPhase) -> CoverageReport {
+        let coverage = self.script(phase);
+        let stream = OpcodeStream::decode(&script.to_bytes());
+
+        let entries = stream
+            .bits
+            .iter()
+            .zip(stream.offsets.iter())
+            .enumerate()
+            .map(|(index, (bit, byte_offset))| {
+                let opcode = match bit {
+                    ScriptBit::OpCode(code) | ScriptBit::If { code, .. } | ScriptBit::PushData(code, _) => Some(*code),
+                    ScriptBit::Push(_) | ScriptBit::Coinbase(_) | ScriptBit::Unparsed(_) => None,
+                };
+
+                let branch = match opcode {
+                    Some(OpCodes::OP_IF | OpCodes::OP_NOTIF | OpCodes::OP_VERIF | OpCodes::OP_VERNOTIF) => Some(coverage.branches.get(byte_offset).copied().unwrap_or_default()),
+                    _ => None,
+                };
+
+                CoverageEntry {
+                    position: ScriptPosition {
+                        phase,
+                        index,
+                        byte_offset: *byte_offset,
+                    },
+                    opcode,
+                    hits: coverage.hits.get(byte_offset).copied().unwrap_or_default(),
+                    branch,
+                }
+            })
+            .collect();
+
+        CoverageReport { entries }
+    }
+
+    pub fn to_json(&self) -> Result<serde_json::Value, BSVErrors> {
+        Ok(serde_json::to_value(self)?)
+    }
+
+    pub fn to_json_string(&self) -> Result<String, BSVErrors> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn from_json_string(json: &str) -> Result<CoverageCollector, BSVErrors> {
+        Ok(serde_json::from_str(json)?)
+    }
+}
+
+impl ExecutionObserver for CoverageCollector {
+    fn before_opcode(&mut self, step: &StepEvent, _state: &State) {
+        if step.executed {
+            *self.script_mut(step.phase).hits.entry(step.byte_offset).or_default() += 1;
+        }
+    }
+
+    fn branch(&mut self, event: &BranchEvent) {
+        let branch = self.script_mut(event.phase).branches.entry(event.byte_offset).or_default();
+        match event.executed {
+            true => branch.taken += 1,
+            false => branch.not_taken += 1,
+        }
+    }
+}
+
+/// Coverage of a single ScriptBit.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CoverageEntry {
+    pub position: ScriptPosition,
+    /// `None` for direct pushes.
+    pub opcode: Option<OpCodes>,
+    /// Times the ScriptBit was executed, not counting unexecuted branches.
+    pub hits: usize,
+    /// Outcomes of conditionals, `None` for every other ScriptBit.
+    pub branch: Option<BranchCoverage>,
+}
+
+impl CoverageEntry {
+    /// Executed at least once, and if it is a conditional, both of its outcomes have been taken.
+    pub fn is_covered(&self) -> bool {
+        self.hits > 0 && self.branch.is_none_or(|x| x.is_covered())
+    }
+}
+
+/// Coverage of every ScriptBit in a script, see `CoverageCollector::report`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CoverageReport {
+    pub entries: Vec<CoverageEntry>,
+}
+
+impl CoverageReport {
+    /// Entries that were never executed, or are conditionals with an outcome that was never taken.
+    pub fn uncovered(&self) -> Vec<&CoverageEntry> {
+        self.entries.iter().filter(|x| !x.is_covered()).collect()
+    }
+
+    /// Fraction of the ScriptBits executed at least once, 1.0 for an empty script.
+    pub fn opcode_coverage(&self) -> f64 {
+        let hit = self.entries.iter().filter(|x| x.hits > 0).count();
+        fraction(hit, self.entries.len())
+    }
+
+    /// Fraction of conditional outcomes taken at least once, counting two outcomes for each conditional. 1.0 if there are none.
+    pub fn branch_coverage(&self) -> f64 {
+        let branches: Vec<BranchCoverage> = self.entries.iter().filter_map(|x| x.branch).collect();
+        let taken = branches.iter().map(|x| (x.taken > 0) as usize + (x.not_taken > 0) as usize).sum();
+        fraction(taken, branches.len() * 2)
+    }
+
+    pub fn to_json(&self) -> Result<serde_json::Value, BSVErrors> {
+        Ok(serde_json::to_value(self)?)
+    }
+
+    pub fn to_json_string(&self) -> Result<String, BSVErrors> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Formats the report as an lcov tracefile for `source_name`, with each ScriptBit as a line numbered from its index plus one.
+    pub fn to_lcov(&self, source_name: &str) -> String {
+        let mut lcov = format!("TN:\nSF:{}\n", source_name);
+        let (mut branches_found, mut branches_hit) = (0, 0);
+
+        for entry in &self.entries {
+            let line = entry.position.index + 1;
+            if let Some(branch) = entry.branch {
+                for (block, count) in [branch.taken, branch.not_taken].into_iter().enumerate() {
+                    branches_found += 1;
+                    branches_hit += (count > 0) as usize;
+                    // lcov marks the outcomes of a conditional that was never reached with a dash
+                    match entry.hits {
+                        0 => lcov.push_str(&format!("BRDA:{},0,{},-\n", line, block)),
+                        _ => lcov.push_str(&format!("BRDA:{},0,{},{}\n", line, block, count)),
+                    }
+                }
+            }
+        }
+        lcov.push_str(&format!("BRF:{}\nBRH:{}\n", branches_found, branches_hit));
+
+        for entry in &self.entries {
+            lcov.push_str(&format!("DA:{},{}\n", entry.position.index + 1, entry.hits));
+        }
+        let lines_hit = self.entries.iter().filter(|x| x.hits > 0).count();
+        lcov.push_str(&format!("LF:{}\nLH:{}\nend_of_record\n", self.entries.len(), lines_hit));
+
+        lcov
+    }
+}
+
+fn fraction(count: usize, total: usize) -> f64 {
+    match total {
+        0 => 1.0,
+        _ => count as f64 / total as f64,
+    }
+}
@@ -0,0 +1,237 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{OpCodes, ScriptBit};
+
+use super::{errors::InterpreterError, failure::ExecutionFailure, opcode_stream::OpcodeStream, state::State, Condition, ExecutionPhase, Interpreter, Status};
+
+/// Position of an opcode or push in the serialised script.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScriptPosition {
+    pub phase: ExecutionPhase,
+    /// Index of the opcode in the script, counting every opcode and push including OP_ELSE and OP_ENDIF.
+    pub index: usize,
+    pub byte_offset: usize,
+}
+
+/// Checked against the stack after every step.
+pub type StackPredicate = Box<dyn Fn(&[Vec<u8>]) -> bool + Send>;
+
+pub enum Breakpoint {
+    /// Stops before executing the opcode at this index of the unlocking or locking script, see `ScriptPosition::index`.
+    Index { phase: ExecutionPhase, index: usize },
+    /// Stops before executing any instance of the opcode.
+    OpCode(OpCodes),
+    /// Stops after a step leaves the stack matching the predicate.
+    Stack(StackPredicate),
+}
+
+/// Why `ScriptDebugger::resume` stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DebuggerStop {
+    /// Stopped on the breakpoint with this id.
+    Breakpoint(usize),
+    Finished,
+}
+
+/// The parts of the Interpreter a step changes, enough for `step_back` to restore it without copying the scripts.
+struct Snapshot {
+    state: State,
+    phase: ExecutionPhase,
+    script_index: usize,
+    op_count: usize,
+    conditions: Vec<Condition>,
+    non_top_level_return: bool,
+    returned_early: bool,
+    failure: Option<ExecutionFailure>,
+}
+
+impl Snapshot {
+    fn take(interpreter: &Interpreter) -> Snapshot {
+        Snapshot {
+            state: interpreter.state.clone(),
+            phase: interpreter.phase,
+            script_index: interpreter.script_index,
+            op_count: interpreter.op_count,
+            conditions: interpreter.conditions.clone(),
+            non_top_level_return: interpreter.non_top_level_return,
+            returned_early: interpreter.returned_early,
+            failure: interpreter.failure.clone(),
+        }
+    }
+}
+
+/// Steps an Interpreter forwards and backwards, stopping on breakpoints.
+///
+/// Every step keeps a snapshot of the execution state so `step_back` can restore it exactly.
+pub struct ScriptDebugger {
+    interpreter: Interpreter,
+    history: Vec<Snapshot>,
+    /// The unlocking script, loaded again when stepping back from the locking script.
+    unlocking_script: Option<Vec<u8>>,
+    breakpoints: Vec<(usize, Breakpoint)>,
+    next_breakpoint_id: usize,
+    /// The first opcode of the locking script, which runs next once the unlocking script has finished.
+    locking_start: Option<ScriptBit>,
+}
+
+impl ScriptDebugger {
+    pub fn new(interpreter: Interpreter) -> ScriptDebugger {
+        let locking_start = OpcodeStream::decode(&interpreter.locking_script.to_bytes()).bits.into_iter().next();
+        let unlocking_script = (interpreter.phase == ExecutionPhase::Unlocking).then(|| interpreter.script_bytes.clone());
+
+        ScriptDebugger {
+            interpreter,
+            history: vec![],
+            unlocking_script,
+            breakpoints: vec![],
+            next_breakpoint_id: 0,
+            locking_start,
+        }
+    }
+
+    /// Returns the id of the breakpoint, used to remove it again.
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) -> usize {
+        let id = self.next_breakpoint_id;
+        self.next_breakpoint_id += 1;
+        self.breakpoints.push((id, breakpoint));
+        id
+    }
+
+    /// Returns `false` if there was no breakpoint with the given id.
+    pub fn remove_breakpoint(&mut self, id: usize) -> bool {
+        let len = self.breakpoints.len();
+        self.breakpoints.retain(|(x, _)| *x != id);
+        self.breakpoints.len() != len
+    }
+
+    pub fn clear_breakpoints(&mut self) {
+        self.breakpoints.clear();
+    }
+
+    /// Position of the next opcode to be executed, `None` once execution has finished.
+    pub fn position(&self) -> Option<ScriptPosition> {
+        let interpreter = &self.interpreter;
+        if interpreter.state.status == Status::Finished {
+            return None;
+        }
+
+        match interpreter.script_offsets.get(interpreter.script_index) {
+            Some(byte_offset) => Some(ScriptPosition {
+                phase: interpreter.phase,
+                index: interpreter.script_index,
+                byte_offset: *byte_offset,
+            }),
+            None if interpreter.phase == ExecutionPhase::Unlocking && self.locking_start.is_some() => Some(ScriptPosition {
+                phase: ExecutionPhase::Locking,
+                index: 0,
+                byte_offset: 0,
+            }),
+            None => None,
+        }
+    }
+
+    /// The opcode that will be executed next, `None` for direct pushes.
+    fn next_opcode(&self) -> Option<OpCodes> {
+        let interpreter = &self.interpreter;
+        let bit = match interpreter.script_bits.get(interpreter.script_index) {
+            Some(v) => v,
+            None if interpreter.phase == ExecutionPhase::Unlocking => self.locking_start.as_ref()?,
+            None => return None,
+        };
+
+        match bit {
+            ScriptBit::OpCode(code) | ScriptBit::If { code, .. } | ScriptBit::PushData(code, _) => Some(*code),
+            _ => None,
+        }
+    }
+
+    /// Executes the next opcode, keeping a snapshot to step back to.
+    pub fn step(&mut self) -> Option<Result<State, InterpreterError>> {
+        let snapshot = Snapshot::take(&self.interpreter);
+        let result = self.interpreter.next_impl();
+        if result.is_some() {
+            self.history.push(snapshot);
+        }
+        result
+    }
+
+    /// Restores the Interpreter to how it was before the last step. Returns `false` if there are no steps to go back.
+    pub fn step_back(&mut self) -> bool {
+        let snapshot = match self.history.pop() {
+            Some(v) => v,
+            None => return false,
+        };
+
+        let interpreter = &mut self.interpreter;
+        if snapshot.phase != interpreter.phase {
+            // Only a step from the end of the unlocking script moves to the locking script
+            interpreter.load_script_bytes(self.unlocking_script.clone().unwrap_or_default());
+        }
+
+        interpreter.state = snapshot.state;
+        interpreter.phase = snapshot.phase;
+        interpreter.script_index = snapshot.script_index;
+        interpreter.op_count = snapshot.op_count;
+        interpreter.conditions = snapshot.conditions;
+        interpreter.non_top_level_return = snapshot.non_top_level_return;
+        interpreter.returned_early = snapshot.returned_early;
+        interpreter.failure = snapshot.failure;
+        true
+    }
+
+    /// Executes at least one step, then continues until a breakpoint is hit or execution finishes.
+    pub fn resume(&mut self) -> Result<DebuggerStop, InterpreterError> {
+        loop {
+            let state = match self.step() {
+                Some(v) => v?,
+                None => return Ok(DebuggerStop::Finished),
+            };
+
+            if let Some(id) = self.hit_breakpoint(&state) {
+                return Ok(DebuggerStop::Breakpoint(id));
+            }
+        }
+    }
+
+    /// Steps until the predicate holds for the state after a step. Returns `false` if execution finished first.
+    pub fn run_until<F: Fn(&State) -> bool>(&mut self, predicate: F) -> Result<bool, InterpreterError> {
+        while let Some(state) = self.step() {
+            if predicate(&state?) {
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+
+    fn hit_breakpoint(&self, state: &State) -> Option<usize> {
+        let position = self.position();
+        let opcode = self.next_opcode();
+
+        self.breakpoints.iter().find_map(|(id, breakpoint)| {
+            let is_hit = match breakpoint {
+                Breakpoint::Index { phase, index } => position.map(|x| x.phase == *phase && x.index == *index).unwrap_or(false),
+                Breakpoint::OpCode(code) => position.is_some() && opcode == Some(*code),
+                Breakpoint::Stack(predicate) => predicate(&state.stack),
+            };
+            is_hit.then_some(*id)
+        })
+    }
+
+    /// Number of steps that can be undone with `step_back`.
+    pub fn steps_taken(&self) -> usize {
+        self.history.len()
+    }
+
+    pub fn state(&self) -> State {
+        self.interpreter.state()
+    }
+
+    pub fn interpreter(&self) -> &Interpreter {
+        &self.interpreter
+    }
+
+    pub fn failure(&self) -> Option<ExecutionFailure> {
+        self.interpreter.failure()
+    }
+}
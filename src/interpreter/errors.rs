@@ -1,10 +1,22 @@
-use crate::{BSVErrors, OpCodes};
+use crate::{BSVErrors, OpCodes, ScriptNumError};
 use thiserror::*;
 
 #[derive(Debug, Error)]
 pub enum InterpreterError {
-    #[error("Number provided is out of range of an i32")]
+    #[error("Number is longer than the maximum script number length")]
     NumberOutOfRange,
+
+    #[error("Number is outside the range allowed by the opcode")]
+    InvalidNumberRange,
+
+    #[error("Division by zero")]
+    DivByZero,
+
+    #[error("Modulo by zero")]
+    ModByZero,
+
+    #[error("Split or substring position is beyond the end of the data")]
+    InvalidSplitRange,
     #[error("Stack is empty")]
     EmptyStack,
 
@@ -32,9 +44,158 @@ pub enum InterpreterError {
     #[error("The TxIn was not provided for this transaction")]
     NoTxInProvided,
 
+    #[error("The TxIn does not have the locking script it spends")]
+    MissingLockingScript,
+
     #[error("Could not calculate SigHash preimage {0}")]
     SighashPreimageCalculation(String),
 
+    #[error("Signature is not strict DER encoded")]
+    SigDer,
+
+    #[error("Signature S value is not low")]
+    SigHighS,
+
+    #[error("Signature has an undefined SigHash type")]
+    SigHashType,
+
+    #[error("Signature must use SIGHASH_FORKID")]
+    MustUseForkId,
+
+    #[error("Signature must not use SIGHASH_FORKID")]
+    IllegalForkId,
+
+    #[error("Public key is neither compressed or uncompressed")]
+    PubKeyType,
+
+    #[error("Dummy element consumed by OP_CHECKMULTISIG must be empty")]
+    SigNullDummy,
+
+    #[error("Signature must be empty if the signature check fails")]
+    SigNullFail,
+
+    #[error("Unlocking script may only contain pushes")]
+    SigPushOnly,
+
+    #[error("Data was not pushed with the smallest possible encoding")]
+    MinimalData,
+
+    #[error("Argument to OP_IF or OP_NOTIF must be empty or 0x01")]
+    MinimalIf,
+
+    #[error("Stack must contain exactly one element after execution")]
+    CleanStack,
+
+    #[error("Reserved NOP {0} is discouraged")]
+    DiscourageUpgradableNops(OpCodes),
+
+    #[error("Locktime is negative")]
+    NegativeLockTime,
+
+    #[error("Locktime requirement not satisfied")]
+    UnsatisfiedLockTime,
+
+    #[error("OP_RETURN was executed")]
+    OpReturn,
+
+    #[error("Script finished with an empty stack or a false value on top of the stack")]
+    EvalFalse,
+
+    #[error("OP_IF, OP_NOTIF, OP_ELSE and OP_ENDIF are not balanced")]
+    UnbalancedConditional,
+
+    #[error("Script ends part way through a push")]
+    TruncatedPush,
+
+    #[error("Script is larger than the maximum script size")]
+    ScriptSize,
+
+    #[error("Script contains more opcodes than the maximum ops per script")]
+    OpCount,
+
+    #[error("Element is larger than the maximum element size")]
+    PushSize,
+
+    #[error("Stack and alt stack contain more elements than allowed")]
+    StackSize,
+
+    #[error("Stack and alt stack use more memory than allowed")]
+    StackMemory,
+
+    #[error("OP_CHECKMULTISIG was given a negative public key count or more public keys than allowed")]
+    PubKeyCount,
+
+    #[error("OP_CHECKMULTISIG was given a negative signature count or more signatures than public keys")]
+    SigCount,
+
+    #[error("OP_NUM2BIN was given a size too small to hold the number")]
+    ImpossibleEncoding,
+
+    #[error("Coinbase scripts are not executed")]
+    CoinbaseScript,
+
     #[error("{0}")]
     BSVErrors(#[from] BSVErrors),
 }
+
+impl InterpreterError {
+    /// Machine readable code for the error, named after the node's `SCRIPT_ERR_*` codes where there is an equivalent.
+    pub fn code(&self) -> &'static str {
+        match self {
+            InterpreterError::NumberOutOfRange => "SCRIPTNUM_OVERFLOW",
+            InterpreterError::InvalidNumberRange => "INVALID_NUMBER_RANGE",
+            InterpreterError::DivByZero => "DIV_BY_ZERO",
+            InterpreterError::ModByZero => "MOD_BY_ZERO",
+            InterpreterError::InvalidSplitRange => "SPLIT_RANGE",
+            InterpreterError::EmptyStack => "INVALID_STACK_OPERATION",
+            InterpreterError::InvalidOpcode(_) => "BAD_OPCODE",
+            InterpreterError::TooLongForBool => "INVALID_BOOL",
+            InterpreterError::VerifyFailed => "VERIFY",
+            InterpreterError::DisabledOpCode(_) => "DISABLED_OPCODE",
+            InterpreterError::RequiresTransaction(_) => "REQUIRES_TRANSACTION",
+            InterpreterError::FailedToConvertSighash => "SIG_HASHTYPE",
+            InterpreterError::InvalidStackOperation(_) => "INVALID_STACK_OPERATION",
+            InterpreterError::NoTxInProvided => "NO_TXIN",
+            InterpreterError::MissingLockingScript => "MISSING_LOCKING_SCRIPT",
+            InterpreterError::SighashPreimageCalculation(_) => "SIGHASH_PREIMAGE",
+            InterpreterError::SigDer => "SIG_DER",
+            InterpreterError::SigHighS => "SIG_HIGH_S",
+            InterpreterError::SigHashType => "SIG_HASHTYPE",
+            InterpreterError::MustUseForkId => "MUST_USE_FORKID",
+            InterpreterError::IllegalForkId => "ILLEGAL_FORKID",
+            InterpreterError::PubKeyType => "PUBKEYTYPE",
+            InterpreterError::SigNullDummy => "SIG_NULLDUMMY",
+            InterpreterError::SigNullFail => "SIG_NULLFAIL",
+            InterpreterError::SigPushOnly => "SIG_PUSHONLY",
+            InterpreterError::MinimalData => "MINIMALDATA",
+            InterpreterError::MinimalIf => "MINIMALIF",
+            InterpreterError::CleanStack => "CLEANSTACK",
+            InterpreterError::DiscourageUpgradableNops(_) => "DISCOURAGE_UPGRADABLE_NOPS",
+            InterpreterError::NegativeLockTime => "NEGATIVE_LOCKTIME",
+            InterpreterError::UnsatisfiedLockTime => "UNSATISFIED_LOCKTIME",
+            InterpreterError::OpReturn => "OP_RETURN",
+            InterpreterError::EvalFalse => "EVAL_FALSE",
+            InterpreterError::UnbalancedConditional => "UNBALANCED_CONDITIONAL",
+            InterpreterError::TruncatedPush => "BAD_OPCODE",
+            InterpreterError::ScriptSize => "SCRIPT_SIZE",
+            InterpreterError::OpCount => "OP_COUNT",
+            InterpreterError::PushSize => "PUSH_SIZE",
+            InterpreterError::StackSize => "STACK_SIZE",
+            InterpreterError::StackMemory => "STACK_MEMORY_USAGE",
+            InterpreterError::PubKeyCount => "PUBKEY_COUNT",
+            InterpreterError::SigCount => "SIG_COUNT",
+            InterpreterError::ImpossibleEncoding => "IMPOSSIBLE_ENCODING",
+            InterpreterError::CoinbaseScript => "COINBASE",
+            InterpreterError::BSVErrors(_) => "UNKNOWN_ERROR",
+        }
+    }
+}
+
+impl From<ScriptNumError> for InterpreterError {
+    fn from(error: ScriptNumError) -> InterpreterError {
+        match error {
+            ScriptNumError::TooLong { .. } => InterpreterError::NumberOutOfRange,
+            ScriptNumError::NotMinimal => InterpreterError::MinimalData,
+        }
+    }
+}
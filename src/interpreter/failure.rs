@@ -0,0 +1,61 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{OpCodes, ScriptBit};
+
+use super::{errors::InterpreterError, ExecutionPhase, Interpreter, Status};
+
+/// Where and why a script failed, along with the stacks as they were before the failing ScriptBit was executed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExecutionFailure {
+    /// Which of the two scripts failed.
+    pub phase: ExecutionPhase,
+    /// Index of the failing ScriptBit, this is one past the last executed ScriptBit if the script failed after it finished (eg. EVAL_FALSE).
+    pub script_index: usize,
+    /// Byte offset of the failing ScriptBit in the serialised script of the phase.
+    pub byte_offset: usize,
+    /// The failing OpCode, `None` for direct pushes and failures after the script finished.
+    pub opcode: Option<OpCodes>,
+    pub stack: Vec<Vec<u8>>,
+    pub alt_stack: Vec<Vec<u8>>,
+    /// Machine readable error code, see `InterpreterError::code`.
+    pub code: String,
+    pub message: String,
+}
+
+impl Interpreter {
+    /// Records the position and stacks at the point of failure before handing the error back.
+    pub(crate) fn record_failure(&mut self, error: InterpreterError) -> InterpreterError {
+        let script_index = self.script_index;
+        let byte_offset = self.script_offsets.get(script_index).copied().unwrap_or(self.script_bytes.len());
+        let opcode = match (&self.state.status, self.script_bits.get(script_index)) {
+            // Execution may have finished early on an OP_RETURN, the remaining ScriptBits were never run
+            (Status::Finished, _) | (_, None) => None,
+            (_, Some(ScriptBit::OpCode(code) | ScriptBit::If { code, .. } | ScriptBit::PushData(code, _))) => Some(*code),
+            (_, Some(ScriptBit::Push(_) | ScriptBit::Coinbase(_) | ScriptBit::Unparsed(_))) => None,
+        };
+
+        let failure = ExecutionFailure {
+            phase: self.phase,
+            script_index,
+            byte_offset,
+            opcode,
+            stack: self.state.stack.to_vec(),
+            alt_stack: self.state.alt_stack.to_vec(),
+            code: error.code().to_string(),
+            message: error.to_string(),
+        };
+
+        if let Some(observer) = &self.observer {
+            observer.lock().unwrap_or_else(|e| e.into_inner()).failure(&failure);
+        }
+        self.failure = Some(failure);
+
+        error
+    }
+
+    /// Get where and why the script failed, if it has.
+    #[must_use]
+    pub fn failure(&self) -> Option<ExecutionFailure> {
+        self.failure.clone()
+    }
+}
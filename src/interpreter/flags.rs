@@ -0,0 +1,134 @@
+use std::ops::{BitAnd, BitOr, BitOrAssign};
+
+use serde::{Deserialize, Serialize};
+
+/// Script verification flags, mirroring the `SCRIPT_VERIFY_*` flags used by the node.
+///
+/// Flags are combined with `|`, eg. `ScriptFlags::UTXO_AFTER_GENESIS | ScriptFlags::MINIMALDATA`.
+/// `ScriptFlags::MANDATORY` is the consensus rule set, `ScriptFlags::STANDARD` adds the policy rules
+/// miners apply before accepting a transaction into their mempool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ScriptFlags(u32);
+
+impl ScriptFlags {
+    /// No flags, pre-Genesis rules with nothing but the base consensus checks.
+    pub const NONE: ScriptFlags = ScriptFlags(0);
+    /// Signatures must be strict DER, have a defined sighash type and public keys must be compressed or uncompressed.
+    pub const STRICTENC: ScriptFlags = ScriptFlags(1 << 1);
+    /// Signatures must be strict DER (BIP66).
+    pub const DERSIG: ScriptFlags = ScriptFlags(1 << 2);
+    /// Signatures must have an S value no greater than half the curve order.
+    pub const LOW_S: ScriptFlags = ScriptFlags(1 << 3);
+    /// The extra element consumed by OP_CHECKMULTISIG must be empty.
+    pub const NULLDUMMY: ScriptFlags = ScriptFlags(1 << 4);
+    /// The unlocking script may only contain pushes.
+    pub const SIGPUSHONLY: ScriptFlags = ScriptFlags(1 << 5);
+    /// Pushes and numbers must use their smallest possible encoding.
+    pub const MINIMALDATA: ScriptFlags = ScriptFlags(1 << 6);
+    /// Fail on reserved NOPs (OP_NOP1, OP_NOP4 - OP_NOP10) so they can be repurposed in future.
+    pub const DISCOURAGE_UPGRADABLE_NOPS: ScriptFlags = ScriptFlags(1 << 7);
+    /// Exactly one element must be left on the stack once the script has finished.
+    pub const CLEANSTACK: ScriptFlags = ScriptFlags(1 << 8);
+    /// Before Genesis, OP_CHECKLOCKTIMEVERIFY checks the transaction's locktime (BIP65) instead of being OP_NOP2.
+    pub const CHECKLOCKTIMEVERIFY: ScriptFlags = ScriptFlags(1 << 9);
+    /// Before Genesis, OP_CHECKSEQUENCEVERIFY checks the input's relative locktime (BIP112) instead of being OP_NOP3.
+    pub const CHECKSEQUENCEVERIFY: ScriptFlags = ScriptFlags(1 << 10);
+    /// The argument to OP_IF and OP_NOTIF must be empty or exactly `0x01`.
+    pub const MINIMALIF: ScriptFlags = ScriptFlags(1 << 13);
+    /// A failed signature check must have been given an empty signature.
+    pub const NULLFAIL: ScriptFlags = ScriptFlags(1 << 14);
+    /// Signatures must commit to the FORKID sighash flag.
+    pub const SIGHASH_FORKID: ScriptFlags = ScriptFlags(1 << 16);
+    /// The UTXO being spent was created after Genesis activation and is executed under post-Genesis rules.
+    pub const UTXO_AFTER_GENESIS: ScriptFlags = ScriptFlags(1 << 19);
+    /// The UTXO being spent is executed under the Chronicle rules, which restore OP_VER, OP_VERIF, OP_VERNOTIF, OP_2MUL and OP_2DIV
+    /// and add OP_SUBSTR, OP_LEFT, OP_RIGHT, OP_LSHIFTNUM and OP_RSHIFTNUM. Only meaningful with `UTXO_AFTER_GENESIS`.
+    pub const UTXO_AFTER_CHRONICLE: ScriptFlags = ScriptFlags(1 << 20);
+
+    /// Rules every transaction on the network must follow.
+    pub const MANDATORY: ScriptFlags = ScriptFlags(Self::STRICTENC.0 | Self::SIGHASH_FORKID.0 | Self::LOW_S.0 | Self::NULLFAIL.0);
+
+    /// Rules miners apply by default before accepting a transaction.
+    pub const STANDARD: ScriptFlags = ScriptFlags(
+        Self::MANDATORY.0
+            | Self::DERSIG.0
+            | Self::MINIMALDATA.0
+            | Self::NULLDUMMY.0
+            | Self::DISCOURAGE_UPGRADABLE_NOPS.0
+            | Self::CLEANSTACK.0
+            | Self::SIGPUSHONLY.0
+            | Self::CHECKLOCKTIMEVERIFY.0
+            | Self::CHECKSEQUENCEVERIFY.0,
+    );
+
+    pub fn from_bits(bits: u32) -> ScriptFlags {
+        ScriptFlags(bits)
+    }
+
+    pub fn bits(&self) -> u32 {
+        self.0
+    }
+
+    /// Returns `true` if every flag in `other` is also set on `self`.
+    pub fn contains(&self, other: ScriptFlags) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Returns `true` if any flag in `other` is also set on `self`.
+    pub fn intersects(&self, other: ScriptFlags) -> bool {
+        self.0 & other.0 != 0
+    }
+
+    pub fn insert(&mut self, other: ScriptFlags) {
+        self.0 |= other.0;
+    }
+
+    pub fn remove(&mut self, other: ScriptFlags) {
+        self.0 &= !other.0;
+    }
+
+    /// Consensus rules for a UTXO created either side of Genesis.
+    pub fn consensus(utxo_after_genesis: bool) -> ScriptFlags {
+        match utxo_after_genesis {
+            true => ScriptFlags::MANDATORY | ScriptFlags::UTXO_AFTER_GENESIS,
+            false => ScriptFlags::MANDATORY,
+        }
+    }
+
+    /// Policy rules for a UTXO created either side of Genesis.
+    pub fn standard(utxo_after_genesis: bool) -> ScriptFlags {
+        match utxo_after_genesis {
+            true => ScriptFlags::STANDARD | ScriptFlags::UTXO_AFTER_GENESIS,
+            false => ScriptFlags::STANDARD,
+        }
+    }
+}
+
+/// Post-Genesis rules without any additional policy checks.
+impl Default for ScriptFlags {
+    fn default() -> Self {
+        ScriptFlags::UTXO_AFTER_GENESIS
+    }
+}
+
+impl BitOr for ScriptFlags {
+    type Output = ScriptFlags;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        ScriptFlags(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for ScriptFlags {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+impl BitAnd for ScriptFlags {
+    type Output = ScriptFlags;
+
+    fn bitand(self, rhs: Self) -> Self::Output {
+        ScriptFlags(self.0 & rhs.0)
+    }
+}
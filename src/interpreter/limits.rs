@@ -0,0 +1,93 @@
+use serde::{Deserialize, Serialize};
+
+use super::{errors::InterpreterError, state::State};
+
+/// Numbers popped off the stack may be at most 4 bytes long before Genesis.
+pub const MAX_SCRIPT_NUM_LENGTH_BEFORE_GENESIS: usize = 4;
+/// Default policy limit on the length of numbers popped off the stack after Genesis.
+pub const MAX_SCRIPT_NUM_LENGTH_AFTER_GENESIS: usize = 750_000;
+
+/// Bytes counted against `max_stack_memory` for every element on the stack on top of its data, the same as the node.
+pub const STACK_ELEMENT_OVERHEAD: usize = 32;
+
+/// Resource limits enforced while executing a script.
+///
+/// Before Genesis these are consensus rules and always `ScriptLimits::BEFORE_GENESIS`.
+/// After Genesis they are policy, set by each miner, and default to `ScriptLimits::AFTER_GENESIS`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScriptLimits {
+    /// Opcodes above OP_16 in each script, whether they are executed or not.
+    pub max_ops_per_script: usize,
+    /// Combined size of the stack and alt stack, with `STACK_ELEMENT_OVERHEAD` added for each element.
+    pub max_stack_memory: usize,
+    /// Combined number of elements on the stack and alt stack.
+    pub max_stack_elements: usize,
+    /// Length of any single pushed or computed element.
+    pub max_element_size: usize,
+    /// Public keys given to a single OP_CHECKMULTISIG.
+    pub max_pubkeys_per_multisig: usize,
+    /// Length of each serialised script.
+    pub max_script_size: usize,
+    /// Length of numbers popped off the stack.
+    pub max_script_num_length: usize,
+}
+
+impl ScriptLimits {
+    pub const BEFORE_GENESIS: ScriptLimits = ScriptLimits {
+        max_ops_per_script: 500,
+        max_stack_memory: usize::MAX,
+        max_stack_elements: 1000,
+        max_element_size: 520,
+        max_pubkeys_per_multisig: 20,
+        max_script_size: 10_000,
+        max_script_num_length: MAX_SCRIPT_NUM_LENGTH_BEFORE_GENESIS,
+    };
+
+    pub const AFTER_GENESIS: ScriptLimits = ScriptLimits {
+        max_ops_per_script: u32::MAX as usize,
+        max_stack_memory: 100_000_000,
+        max_stack_elements: usize::MAX,
+        max_element_size: u32::MAX as usize,
+        max_pubkeys_per_multisig: u32::MAX as usize,
+        max_script_size: 500_000,
+        max_script_num_length: MAX_SCRIPT_NUM_LENGTH_AFTER_GENESIS,
+    };
+
+    /// Checks the stack left by an opcode, the element it has just pushed or computed is on top.
+    pub(crate) fn check_stack(&self, state: &State) -> Result<(), InterpreterError> {
+        if state.stack.last().map(|x| x.len()).unwrap_or_default() > self.max_element_size {
+            return Err(InterpreterError::PushSize);
+        }
+
+        if state.stack.len() + state.alt_stack.len() > self.max_stack_elements {
+            return Err(InterpreterError::StackSize);
+        }
+
+        let memory = state.stack.memory().saturating_add(state.alt_stack.memory());
+        if memory > self.max_stack_memory {
+            return Err(InterpreterError::StackMemory);
+        }
+
+        Ok(())
+    }
+
+    /// Checks an element of `size` bytes could be pushed without going over `max_stack_memory`, before it is allocated.
+    pub(crate) fn check_push_memory(&self, state: &State, size: usize) -> Result<(), InterpreterError> {
+        if size.saturating_add(STACK_ELEMENT_OVERHEAD) > self.stack_memory_headroom(state) {
+            return Err(InterpreterError::StackMemory);
+        }
+
+        Ok(())
+    }
+
+    /// Get the memory left before `max_stack_memory` is reached.
+    fn stack_memory_headroom(&self, state: &State) -> usize {
+        self.max_stack_memory.saturating_sub(state.stack.memory().saturating_add(state.alt_stack.memory()))
+    }
+}
+
+impl Default for ScriptLimits {
+    fn default() -> Self {
+        ScriptLimits::AFTER_GENESIS
+    }
+}
@@ -3,17 +3,45 @@
 //ops::{Neg, Not, Shl, Shr},
 //};
 
-use crate::{Script, ScriptBit, Transaction};
-use errors::InterpreterError;
+use crate::{OpCodes, Script, ScriptBit, Transaction};
+pub use errors::*;
 //use num_bigint::{BigInt, Sign};
 use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
 //use stack_trait::ScriptStack;
 
+pub mod debugger;
+pub use debugger::*;
 mod errors;
+mod failure;
+pub use failure::*;
+pub mod observer;
+pub use observer::*;
+pub mod coverage;
+pub use coverage::*;
+pub mod stack_analysis;
+pub use stack_analysis::*;
+pub mod flags;
+pub use flags::*;
+pub mod limits;
+pub use limits::*;
+pub mod signature_checker;
+pub use signature_checker::*;
+mod signature_checks;
+pub mod stack;
+pub use stack::*;
 mod stack_trait;
+pub use stack_trait::cast_to_bool;
+mod verdict;
+pub use verdict::*;
 pub mod state;
 pub use state::*;
+pub mod synthetic_spend;
+pub use synthetic_spend::*;
+mod opcode_stream;
+use opcode_stream::OpcodeStream;
 mod script_matching;
+mod sigops;
 
 #[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
 pub enum Status {
@@ -22,71 +50,275 @@ pub enum Status {
     Finished,
 }
 
+/// Which of the two scripts of an input is currently being executed.
+///
+/// The unlocking script runs first on an empty stack, its resulting stack is then handed to a fresh run of the locking script.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum ExecutionPhase {
+    #[default]
+    Unlocking,
+    Locking,
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct TxScript {
     pub(crate) tx: Transaction,
     pub(crate) input_index: usize,
 }
 
+/// An open OP_IF or OP_NOTIF, the equivalent of an entry in the node's `vfExec` stack.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Condition {
+    pub(crate) is_executing: bool,
+    pub(crate) has_else: bool,
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct Interpreter {
+    /// Flat stream of the opcodes and pushes in the current phase, conditionals are not nested.
     pub(crate) script_bits: Vec<ScriptBit>,
+    /// Byte offset of each of the `script_bits` in the serialised script of the current phase.
+    pub(crate) script_offsets: Vec<usize>,
+    /// Serialised script of the current phase, the scriptCode for signature checks is sliced from this.
+    pub(crate) script_bytes: Vec<u8>,
+    /// The current script ends part way through a push.
+    pub(crate) script_truncated: bool,
     pub(crate) script_index: usize,
+    /// Opcodes counted against `ScriptLimits::max_ops_per_script` in the current script.
+    pub(crate) op_count: usize,
+    pub(crate) conditions: Vec<Condition>,
+    /// A post-Genesis OP_RETURN was executed inside an OP_IF, the rest of the script is only checked for balanced conditionals.
+    pub(crate) non_top_level_return: bool,
+    /// A post-Genesis OP_RETURN has been executed.
+    pub(crate) returned_early: bool,
     pub(crate) state: State,
     pub(crate) tx_script: Option<TxScript>,
+    pub(crate) flags: ScriptFlags,
+    /// Limits applied post-Genesis, pre-Genesis limits are fixed by consensus.
+    pub(crate) limits: ScriptLimits,
+    pub(crate) phase: ExecutionPhase,
+    pub(crate) locking_script: Script,
+    pub(crate) failure: Option<ExecutionFailure>,
+    /// The input is a coinbase, which spends nothing so neither script is executed.
+    pub(crate) is_coinbase: bool,
+    /// Block height from the start of the coinbase script, see `TxIn::get_bip34_height`.
+    pub(crate) coinbase_height: Option<u32>,
+    /// Append every executed opcode to `State::executed_opcodes`, off by default.
+    pub(crate) record_executed_opcodes: bool,
+    #[serde(skip)]
+    pub(crate) signature_checker: Option<Arc<dyn SignatureChecker + Send + Sync>>,
+    #[serde(skip)]
+    pub(crate) observer: Option<Arc<Mutex<dyn ExecutionObserver + Send>>>,
 }
 
 impl Interpreter {
-    pub fn from_transaction_and_script_bits(tx: Transaction, txin: usize, script_bits: Vec<ScriptBit>) -> Interpreter {
-        Interpreter {
-            script_bits,
+    fn new(unlocking_script: &Script, locking_script: &Script, phase: ExecutionPhase, tx_script: Option<TxScript>, flags: ScriptFlags) -> Interpreter {
+        let mut interpreter = Interpreter {
+            script_bits: vec![],
+            script_offsets: vec![],
+            script_bytes: vec![],
+            script_truncated: false,
             script_index: 0,
+            op_count: 0,
+            conditions: vec![],
+            non_top_level_return: false,
+            returned_early: false,
             state: State::default(),
-            tx_script: Some(TxScript { tx, input_index: txin }),
+            tx_script,
+            flags,
+            limits: ScriptLimits::default(),
+            phase,
+            locking_script: locking_script.clone(),
+            failure: None,
+            is_coinbase: false,
+            coinbase_height: None,
+            record_executed_opcodes: false,
+            signature_checker: None,
+            observer: None,
+        };
+
+        match phase {
+            ExecutionPhase::Unlocking => interpreter.load_script(unlocking_script),
+            ExecutionPhase::Locking => interpreter.load_script(locking_script),
+        }
+        interpreter
+    }
+
+    /// Treats the given script bits as the locking script, the scriptCode for signature checks is taken from them.
+    pub fn from_transaction_and_script_bits(tx: Transaction, txin: usize, script_bits: Vec<ScriptBit>, flags: ScriptFlags) -> Interpreter {
+        let tx_script = Some(TxScript { tx, input_index: txin });
+        Interpreter::new(&Script::default(), &Script::from_script_bits(script_bits), ExecutionPhase::Locking, tx_script, flags)
+    }
+
+    fn load_script(&mut self, script: &Script) {
+        self.load_script_bytes(script.to_bytes());
+    }
+
+    fn load_script_bytes(&mut self, script_bytes: Vec<u8>) {
+        self.script_bytes = script_bytes;
+        let stream = OpcodeStream::decode(&self.script_bytes);
+        self.script_bits = stream.bits;
+        self.script_offsets = stream.offsets;
+        self.script_truncated = stream.is_truncated;
+        self.script_index = 0;
+        self.op_count = 0;
+        self.conditions.clear();
+        self.non_top_level_return = false;
+    }
+
+    /// Every open conditional is executing and no OP_RETURN has been executed inside one.
+    pub(crate) fn is_executing(&self) -> bool {
+        !self.non_top_level_return && self.conditions.iter().all(|x| x.is_executing)
+    }
+
+    /// The ScriptBit at the script index will be executed, for OP_ELSE and OP_ENDIF the branch containing their OP_IF is.
+    fn is_step_executed(&self) -> bool {
+        match self.script_bits.get(self.script_index) {
+            Some(ScriptBit::OpCode(OpCodes::OP_ELSE | OpCodes::OP_ENDIF)) => {
+                let enclosing = self.conditions.len().saturating_sub(1);
+                !self.non_top_level_return && self.conditions[..enclosing].iter().all(|x| x.is_executing)
+            }
+            _ => self.is_executing(),
         }
     }
 
+    /// Hands the stack left by the unlocking script to a fresh run of the locking script.
+    fn start_locking_phase(&mut self) {
+        let locking_script = self.locking_script.clone();
+        self.load_script(&locking_script);
+        self.phase = ExecutionPhase::Locking;
+        self.state.alt_stack.clear();
+        self.state.codeseparator_offset = 0;
+    }
+
+    /// Steps through both scripts until they finish or fail
+    pub(crate) fn execute(&mut self) -> Result<(), InterpreterError> {
+        while let Some(result) = self.step_impl() {
+            result?;
+        }
+
+        Ok(())
+    }
+
     /// Runs the script to completion
     pub(crate) fn run_impl(&mut self) -> Result<(), InterpreterError> {
-        while let Some(state) = self.next_impl() {
-            let state = state?;
-            println!("=============NEXT==============");
-            println!("{}", state);
+        if self.is_coinbase {
+            return Ok(());
+        }
+
+        self.execute()?;
+
+        if self.flags.contains(ScriptFlags::CLEANSTACK) && self.state.stack.len() != 1 {
+            return Err(self.record_failure(InterpreterError::CleanStack));
         }
 
         Ok(())
     }
 
-    pub(crate) fn next_impl(&mut self) -> Option<Result<State, InterpreterError>> {
-        let script_bits = &self.script_bits.clone();
-        let index = self.script_index;
-        let new_state = match script_bits.get(index) {
-            Some(v) => match Interpreter::match_script_bit(self, v) {
-                Ok(v) => v,
-                Err(e) => return Some(Err(e)),
-            },
-            None => {
-                self.state.status = Status::Finished;
-                return None;
+    /// Executes the next ScriptBit, modifying the state in place. Returns `None` once both scripts have finished.
+    pub(crate) fn step_impl(&mut self) -> Option<Result<(), InterpreterError>> {
+        // A post-Genesis OP_RETURN finishes execution early
+        if self.state.status == Status::Finished {
+            return None;
+        }
+
+        if self.script_index == 0 && self.script_bytes.len() > self.limits().max_script_size {
+            return Some(Err(self.record_failure(InterpreterError::ScriptSize)));
+        }
+
+        if self.script_index >= self.script_bits.len() {
+            return match self.phase {
+                // Like the node, a truncated push is only an error once it is reached
+                _ if self.script_truncated => Some(Err(self.record_failure(InterpreterError::TruncatedPush))),
+                _ if !self.conditions.is_empty() => Some(Err(self.record_failure(InterpreterError::UnbalancedConditional))),
+                ExecutionPhase::Unlocking => {
+                    self.start_locking_phase();
+                    self.step_impl()
+                }
+                ExecutionPhase::Locking => {
+                    self.state.status = Status::Finished;
+                    None
+                }
+            };
+        }
+
+        let result = match self.observer.clone() {
+            Some(observer) => {
+                let mut observer = observer.lock().unwrap_or_else(|e| e.into_inner());
+                let step = StepEvent {
+                    phase: self.phase,
+                    script_index: self.script_index,
+                    byte_offset: self.script_offsets[self.script_index],
+                    script_bit: self.script_bits[self.script_index].clone(),
+                    executed: self.is_step_executed(),
+                };
+
+                observer.before_opcode(&step, &self.state);
+                let result = self.execute_script_bit(&mut *observer);
+                if result.is_ok() {
+                    observer.after_opcode(&step, &self.state);
+                }
+                // The observer is released before recording a failure, which notifies it again
+                result
             }
+            None => self.execute_script_bit(&mut NoopObserver),
         };
 
+        match result {
+            Ok(()) => Some(Ok(())),
+            Err(e) => Some(Err(self.record_failure(e))),
+        }
+    }
+
+    /// Executes the ScriptBit at the script index and checks the stack it leaves against the limits.
+    ///
+    /// Opcodes change the stacks in place, so a failed step may have left them part way through. The step is undone
+    /// from the stacks' journals, leaving the stacks as they were before it for the failure to report.
+    fn execute_script_bit(&mut self, observer: &mut dyn ExecutionObserver) -> Result<(), InterpreterError> {
+        self.state.stack.checkpoint();
+        self.state.alt_stack.checkpoint();
+        let codeseparator_offset = self.state.codeseparator_offset;
+
+        // Moved out while the ScriptBit is borrowed, instead of cloning the whole script every step
+        let script_bits = std::mem::take(&mut self.script_bits);
+        let result = self.match_script_bit(&script_bits[self.script_index], observer).and_then(|_| self.limits().check_stack(&self.state));
+        self.script_bits = script_bits;
+
+        match result {
+            Ok(_) => {
+                self.state.stack.commit();
+                self.state.alt_stack.commit();
+            }
+            Err(_) => {
+                self.state.stack.rewind();
+                self.state.alt_stack.rewind();
+                self.state.codeseparator_offset = codeseparator_offset;
+            }
+        }
+
+        result?;
         self.script_index += 1;
-        self.state = new_state;
+        Ok(())
+    }
+
+    /// Appends the opcode to `State::executed_opcodes` if they are being recorded.
+    pub(crate) fn record_executed(&mut self, opcode: OpCodes) {
+        if self.record_executed_opcodes {
+            self.state.executed_opcodes.push(opcode);
+        }
+    }
 
-        Some(Ok(self.state.clone()))
+    /// Executes the next ScriptBit and returns a snapshot of the state it left.
+    pub(crate) fn next_impl(&mut self) -> Option<Result<State, InterpreterError>> {
+        self.step_impl().map(|result| result.map(|_| self.state.clone()))
     }
 }
 
 /// Both WASM and Rust functionality
 impl Interpreter {
-    pub fn from_script(script: &Script) -> Interpreter {
-        Interpreter {
-            script_bits: script.to_script_bits(),
-            script_index: 0,
-            state: State::default(),
-            tx_script: None,
-        }
+    /// Runs a single script on its own, as if it were a locking script with an empty unlocking script.
+    pub fn from_script(script: &Script, flags: ScriptFlags) -> Interpreter {
+        Interpreter::new(&Script::default(), script, ExecutionPhase::Locking, None, flags)
     }
 
     pub fn script(&self) -> Script {
@@ -98,27 +330,126 @@ impl Interpreter {
         self.script_index
     }
 
-    /// Get a reference to the interpreter's state.
+    /// Get a snapshot of the interpreter's state.
     #[must_use]
     pub fn state(&self) -> State {
         self.state.clone()
     }
 
+    /// Get the stack without taking a snapshot of the whole state.
+    #[must_use]
+    pub fn stack(&self) -> &[Vec<u8>] {
+        &self.state.stack
+    }
+
+    /// Get the alt stack without taking a snapshot of the whole state.
+    #[must_use]
+    pub fn alt_stack(&self) -> &[Vec<u8>] {
+        &self.state.alt_stack
+    }
+
+    /// Get which script the interpreter is currently executing.
+    #[must_use]
+    pub fn phase(&self) -> ExecutionPhase {
+        self.phase
+    }
+
+    /// Get the flags the interpreter is verifying the script with.
+    #[must_use]
+    pub fn flags(&self) -> ScriptFlags {
+        self.flags
+    }
+
+    /// Get the resource limits the script is executed with, `ScriptLimits::BEFORE_GENESIS` unless `ScriptFlags::UTXO_AFTER_GENESIS` is set.
+    #[must_use]
+    pub fn limits(&self) -> ScriptLimits {
+        match self.flags.contains(ScriptFlags::UTXO_AFTER_GENESIS) {
+            true => self.limits,
+            false => ScriptLimits::BEFORE_GENESIS,
+        }
+    }
+
+    /// Sets the policy limits used post-Genesis. Defaults to `ScriptLimits::AFTER_GENESIS`, pre-Genesis limits cannot be changed.
+    pub fn set_limits(&mut self, limits: ScriptLimits) {
+        self.limits = limits;
+    }
+
+    /// Get the longest number, in bytes, that opcodes may pop off the stack.
+    #[must_use]
+    pub fn max_script_num_length(&self) -> usize {
+        self.limits().max_script_num_length
+    }
+
+    /// Sets the longest number, in bytes, that opcodes may pop off the stack post-Genesis.
+    /// Defaults to `MAX_SCRIPT_NUM_LENGTH_AFTER_GENESIS`, pre-Genesis numbers are always limited to 4 bytes.
+    pub fn set_max_script_num_length(&mut self, length: usize) {
+        self.limits.max_script_num_length = length;
+    }
+
+    /// Sends execution events to the given observer, eg. a `TraceCollector`. Keep a clone of the `Arc` to read it back after the run.
+    pub fn set_observer<T: ExecutionObserver + Send + 'static>(&mut self, observer: Arc<Mutex<T>>) {
+        self.observer = Some(observer);
+    }
+
+    /// Checks signatures and locktimes with the given checker instead of the transaction, if there is one.
+    /// Lets scripts using OP_CHECKSIG be run without a spending transaction, eg. with a `MockSignatureChecker`.
+    pub fn set_signature_checker<T: SignatureChecker + Send + Sync + 'static>(&mut self, checker: T) {
+        self.signature_checker = Some(Arc::new(checker));
+    }
+
+    /// Records every executed opcode in `State::executed_opcodes`. Off by default, as the list grows with every step.
+    pub fn set_record_executed_opcodes(&mut self, enabled: bool) {
+        self.record_executed_opcodes = enabled;
+    }
+
     /// Get a reference to the interpreter's tx script.
     #[must_use]
     pub fn tx_script(&self) -> Option<TxScript> {
         self.tx_script.clone()
     }
 
-    pub fn from_transaction(tx: &Transaction, txin: usize) -> Result<Interpreter, InterpreterError> {
-        let script_bits = tx.get_input(txin).unwrap().get_finalised_script_impl()?.to_script_bits();
-        Ok(Interpreter::from_transaction_and_script_bits(tx.clone(), txin, script_bits))
+    /// Runs the unlocking script of the given input, followed by the locking script set on the TxIn, which must have one.
+    ///
+    /// Post-Genesis, or with `ScriptFlags::SIGPUSHONLY`, the unlocking script may only contain pushes.
+    /// Like the node, a coinbase input is not executed, the interpreter starts finished and verifies as `ScriptVerdict::Coinbase`.
+    pub fn from_transaction(tx: &Transaction, txin: usize, flags: ScriptFlags) -> Result<Interpreter, InterpreterError> {
+        let input = tx.get_input(txin).ok_or(InterpreterError::NoTxInProvided)?;
+        if input.is_coinbase() {
+            let tx_script = Some(TxScript { tx: tx.clone(), input_index: txin });
+            let mut interpreter = Interpreter::new(&Script::default(), &Script::default(), ExecutionPhase::Locking, tx_script, flags);
+            interpreter.is_coinbase = true;
+            interpreter.coinbase_height = input.get_bip34_height();
+            interpreter.state.status = Status::Finished;
+            return Ok(interpreter);
+        }
+
+        let unlocking_script = input.get_unlocking_script();
+        if flags.intersects(ScriptFlags::SIGPUSHONLY | ScriptFlags::UTXO_AFTER_GENESIS) && !unlocking_script.is_push_only() {
+            return Err(InterpreterError::SigPushOnly);
+        }
+
+        let locking_script = input.get_locking_script().ok_or(InterpreterError::MissingLockingScript)?;
+        let tx_script = Some(TxScript { tx: tx.clone(), input_index: txin });
+        Ok(Interpreter::new(&unlocking_script, &locking_script, ExecutionPhase::Unlocking, tx_script, flags))
     }
 
+    /// Runs the scripts to completion without checking the result, see `verify` for consensus-style success.
     pub fn run(&mut self) -> Result<(), InterpreterError> {
         self.run_impl()
     }
 
+    /// Get whether the input is a coinbase, which is not executed.
+    #[must_use]
+    pub fn is_coinbase(&self) -> bool {
+        self.is_coinbase
+    }
+
+    /// Get the block height at the start of the coinbase script, if the input is a coinbase that has one.
+    #[must_use]
+    pub fn coinbase_height(&self) -> Option<u32> {
+        self.coinbase_height
+    }
+
     /// Get a reference to the interpreter's script bits.
     #[must_use]
     pub fn script_bits(&self) -> Vec<ScriptBit> {
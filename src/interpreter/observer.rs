@@ -0,0 +1,137 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    utils::{from_hex, to_hex},
+    BSVErrors, OpCodes, ScriptBit,
+};
+
+use super::{failure::ExecutionFailure, state::State, ExecutionPhase};
+
+/// The ScriptBit about to be, or just, executed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StepEvent {
+    pub phase: ExecutionPhase,
+    pub script_index: usize,
+    /// Byte offset of the ScriptBit in the serialised script of the phase.
+    pub byte_offset: usize,
+    pub script_bit: ScriptBit,
+    /// `false` inside a branch that is not taken, where only conditionals are evaluated.
+    /// OP_ELSE and OP_ENDIF count as executed when the branch containing their OP_IF is.
+    pub executed: bool,
+}
+
+/// A signature checked by OP_CHECKSIG, OP_CHECKMULTISIG or their VERIFY variants.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignatureCheckEvent {
+    #[serde(serialize_with = "to_hex", deserialize_with = "from_hex")]
+    pub public_key: Vec<u8>,
+    #[serde(serialize_with = "to_hex", deserialize_with = "from_hex")]
+    pub signature: Vec<u8>,
+    /// The sighash byte on the end of the signature.
+    pub sighash_type: u8,
+    #[serde(serialize_with = "to_hex", deserialize_with = "from_hex")]
+    pub preimage: Vec<u8>,
+    pub is_valid: bool,
+}
+
+/// Which way an OP_IF or OP_NOTIF went.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BranchEvent {
+    pub phase: ExecutionPhase,
+    pub script_index: usize,
+    pub byte_offset: usize,
+    pub opcode: OpCodes,
+    /// `true` if the statements following the OP_IF or OP_NOTIF are executed, `false` if the OP_ELSE statements are.
+    pub executed: bool,
+}
+
+/// Receives events from the Interpreter as it executes a script. Every method defaults to doing nothing.
+///
+/// Attach one with `Interpreter::set_observer`.
+pub trait ExecutionObserver {
+    /// Called with the state before the ScriptBit is executed.
+    fn before_opcode(&mut self, _step: &StepEvent, _state: &State) {}
+
+    /// Called with the state after the ScriptBit was successfully executed.
+    fn after_opcode(&mut self, _step: &StepEvent, _state: &State) {}
+
+    fn signature_check(&mut self, _event: &SignatureCheckEvent) {}
+
+    fn branch(&mut self, _event: &BranchEvent) {}
+
+    fn failure(&mut self, _failure: &ExecutionFailure) {}
+}
+
+/// Ignores every event, used when no observer has been attached.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoopObserver;
+
+impl ExecutionObserver for NoopObserver {}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum TraceEvent {
+    BeforeOpcode { step: StepEvent, stack: Vec<String>, alt_stack: Vec<String> },
+    AfterOpcode { step: StepEvent, stack: Vec<String>, alt_stack: Vec<String> },
+    SignatureCheck(SignatureCheckEvent),
+    Branch(BranchEvent),
+    Failure(ExecutionFailure),
+}
+
+/// Records every event of a run so it can be exported as a JSON trace. Stack items are recorded as hex.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TraceCollector {
+    pub events: Vec<TraceEvent>,
+}
+
+impl TraceCollector {
+    pub fn new() -> TraceCollector {
+        TraceCollector::default()
+    }
+
+    pub fn events(&self) -> &[TraceEvent] {
+        &self.events
+    }
+
+    pub fn to_json(&self) -> Result<serde_json::Value, BSVErrors> {
+        Ok(serde_json::to_value(self)?)
+    }
+
+    pub fn to_json_string(&self) -> Result<String, BSVErrors> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+fn to_hex_stack(stack: &[Vec<u8>]) -> Vec<String> {
+    stack.iter().map(hex::encode).collect()
+}
+
+impl ExecutionObserver for TraceCollector {
+    fn before_opcode(&mut self, step: &StepEvent, state: &State) {
+        self.events.push(TraceEvent::BeforeOpcode {
+            step: step.clone(),
+            stack: to_hex_stack(&state.stack),
+            alt_stack: to_hex_stack(&state.alt_stack),
+        });
+    }
+
+    fn after_opcode(&mut self, step: &StepEvent, state: &State) {
+        self.events.push(TraceEvent::AfterOpcode {
+            step: step.clone(),
+            stack: to_hex_stack(&state.stack),
+            alt_stack: to_hex_stack(&state.alt_stack),
+        });
+    }
+
+    fn signature_check(&mut self, event: &SignatureCheckEvent) {
+        self.events.push(TraceEvent::SignatureCheck(event.clone()));
+    }
+
+    fn branch(&mut self, event: &BranchEvent) {
+        self.events.push(TraceEvent::Branch(event.clone()));
+    }
+
+    fn failure(&mut self, failure: &ExecutionFailure) {
+        self.events.push(TraceEvent::Failure(failure.clone()));
+    }
+}
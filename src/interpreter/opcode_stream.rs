@@ -0,0 +1,63 @@
+use num_traits::FromPrimitive;
+
+use crate::{OpCodes, ScriptBit};
+
+/// A serialised script decoded the way the node reads it during execution, one opcode or push at a time.
+///
+/// OP_IF, OP_NOTIF, OP_ELSE and OP_ENDIF are kept as plain opcodes rather than nested into `ScriptBit::If`,
+/// so scripts with unbalanced conditionals can still be executed up to the point they fail.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct OpcodeStream {
+    pub(crate) bits: Vec<ScriptBit>,
+    /// Byte offset of each of the `bits` in the serialised script.
+    pub(crate) offsets: Vec<usize>,
+    /// The script ends part way through a push, which fails once execution reaches it.
+    pub(crate) is_truncated: bool,
+}
+
+impl OpcodeStream {
+    pub(crate) fn decode(script: &[u8]) -> OpcodeStream {
+        let mut stream = OpcodeStream::default();
+        let mut offset = 0;
+
+        while let Some(byte) = script.get(offset).copied() {
+            let (length_size, data_length) = match byte {
+                0x01..=0x4b => (0, byte as usize),
+                0x4c => (1, script.get(offset + 1).map(|x| *x as usize).unwrap_or(usize::MAX)),
+                0x4d => (2, script.get(offset + 1..offset + 3).map(|x| u16::from_le_bytes([x[0], x[1]]) as usize).unwrap_or(usize::MAX)),
+                0x4e => (
+                    4,
+                    script.get(offset + 1..offset + 5).map(|x| u32::from_le_bytes([x[0], x[1], x[2], x[3]]) as usize).unwrap_or(usize::MAX),
+                ),
+                _ => {
+                    // Bytes without an assigned opcode only fail if they are executed
+                    let opcode = OpCodes::from_u8(byte).unwrap_or(OpCodes::OP_INVALIDOPCODE);
+                    stream.bits.push(ScriptBit::OpCode(opcode));
+                    stream.offsets.push(offset);
+                    offset += 1;
+                    continue;
+                }
+            };
+
+            let data_start = offset + 1 + length_size;
+            let data = match data_start.checked_add(data_length).and_then(|end| script.get(data_start..end)) {
+                Some(v) => v.to_vec(),
+                None => {
+                    stream.is_truncated = true;
+                    break;
+                }
+            };
+
+            stream.bits.push(match byte {
+                0x4c => ScriptBit::PushData(OpCodes::OP_PUSHDATA1, data),
+                0x4d => ScriptBit::PushData(OpCodes::OP_PUSHDATA2, data),
+                0x4e => ScriptBit::PushData(OpCodes::OP_PUSHDATA4, data),
+                _ => ScriptBit::Push(data),
+            });
+            stream.offsets.push(offset);
+            offset = data_start + data_length;
+        }
+
+        stream
+    }
+}
@@ -1,68 +1,179 @@
 use std::ops::Neg;
 
-use crate::{Hash, OpCodes, PublicKey, Script, ScriptBit, SigHash, SighashSignature, ToHex};
-use num_bigint::{BigInt, Sign};
+use crate::{Hash, OpCodes, Script, ScriptBit, ScriptNum};
+use num_bigint::{BigInt, BigUint, Sign};
+use num_traits::{ToPrimitive, Zero};
 
 use super::{
     errors::InterpreterError,
+    flags::ScriptFlags,
+    limits::ScriptLimits,
+    observer::{BranchEvent, ExecutionObserver, SignatureCheckEvent},
+    signature_checker::{SignatureChecker, SEQUENCE_LOCKTIME_DISABLE_FLAG},
+    signature_checks::{check_pubkey_encoding, check_signature_encoding},
     stack_trait::{self, ScriptStack},
     state::State,
-    Interpreter, TxScript,
+    Condition, Interpreter, Status,
 };
 
 /// Script Matching functions
 impl Interpreter {
-    fn verify(boolean: bool) -> Result<(), InterpreterError> {
+    fn verify_predicate(boolean: bool) -> Result<(), InterpreterError> {
         match boolean {
             true => Ok(()),
             false => Err(InterpreterError::VerifyFailed),
         }
     }
 
-    pub(crate) fn match_script_bit(&mut self, bit: &ScriptBit) -> Result<State, InterpreterError> {
-        Ok(match bit {
-            ScriptBit::OpCode(o) => match Interpreter::match_opcode(self.script_index, o, &mut self.state.clone(), self.tx_script.clone()) {
-                Ok(mut next_state) => {
-                    next_state.executed_opcodes.push(*o);
-                    next_state
+    /// The spending transaction's version as pushed by OP_VER, 4 bytes little endian.
+    fn tx_version_bytes(&self, opcode: &'static OpCodes) -> Result<Vec<u8>, InterpreterError> {
+        match &self.tx_script {
+            Some(tx_script) => Ok(tx_script.tx.get_version().to_le_bytes().to_vec()),
+            None => Err(InterpreterError::RequiresTransaction(opcode)),
+        }
+    }
+
+    pub(crate) fn match_script_bit(&mut self, bit: &ScriptBit, observer: &mut dyn ExecutionObserver) -> Result<(), InterpreterError> {
+        let is_executing = self.is_executing();
+        let limits = self.limits();
+
+        // Like the node, opcodes are counted and push sizes checked in unexecuted branches too
+        match bit {
+            ScriptBit::OpCode(code) if *code as u8 > OpCodes::OP_16 as u8 => {
+                self.op_count += 1;
+                if self.op_count > limits.max_ops_per_script {
+                    return Err(InterpreterError::OpCount);
+                }
+            }
+            ScriptBit::Push(data) | ScriptBit::PushData(_, data) if data.len() > limits.max_element_size => return Err(InterpreterError::PushSize),
+            _ => (),
+        }
+
+        // Conditionals are evaluated inside unexecuted branches too so the nesting is tracked.
+        // Before Chronicle, OP_VERIF and OP_VERNOTIF fail the script and OP_2MUL and OP_2DIV are disabled wherever they appear.
+        let is_chronicle = self.flags.contains(ScriptFlags::UTXO_AFTER_CHRONICLE);
+        match bit {
+            ScriptBit::OpCode(code @ (OpCodes::OP_IF | OpCodes::OP_NOTIF | OpCodes::OP_VERIF | OpCodes::OP_VERNOTIF)) if is_chronicle || matches!(code, OpCodes::OP_IF | OpCodes::OP_NOTIF) => {
+                let mut is_branch_executed = false;
+                if is_executing {
+                    let condition = self.state.stack.last().ok_or(InterpreterError::UnbalancedConditional)?;
+                    is_branch_executed = match code {
+                        // OP_VER OP_EQUAL OP_IF and OP_VER OP_EQUAL OP_NOTIF
+                        OpCodes::OP_VERIF => *condition == self.tx_version_bytes(&OpCodes::OP_VERIF)?,
+                        OpCodes::OP_VERNOTIF => *condition != self.tx_version_bytes(&OpCodes::OP_VERNOTIF)?,
+                        _ => {
+                            if self.flags.contains(ScriptFlags::MINIMALIF) && (condition.len() > 1 || (condition.len() == 1 && condition[0] != 1)) {
+                                return Err(InterpreterError::MinimalIf);
+                            }
+
+                            stack_trait::cast_to_bool(condition) != (*code == OpCodes::OP_NOTIF)
+                        }
+                    };
+                    self.state.stack.discard(self.state.stack.len() - 1);
+                    observer.branch(&BranchEvent {
+                        phase: self.phase,
+                        script_index: self.script_index,
+                        byte_offset: self.script_offsets[self.script_index],
+                        opcode: *code,
+                        executed: is_branch_executed,
+                    });
+                }
+
+                self.conditions.push(Condition {
+                    is_executing: is_branch_executed,
+                    has_else: false,
+                });
+                self.record_executed(*code);
+                return Ok(());
+            }
+            ScriptBit::OpCode(OpCodes::OP_ELSE) => {
+                let is_after_genesis = self.flags.contains(ScriptFlags::UTXO_AFTER_GENESIS);
+                let condition = self.conditions.last_mut().ok_or(InterpreterError::UnbalancedConditional)?;
+                // Any number of OP_ELSE may toggle the branch before Genesis, only one is allowed after
+                if condition.has_else && is_after_genesis {
+                    return Err(InterpreterError::UnbalancedConditional);
+                }
+
+                condition.is_executing = !condition.is_executing;
+                condition.has_else = true;
+                self.record_executed(OpCodes::OP_ELSE);
+                return Ok(());
+            }
+            ScriptBit::OpCode(OpCodes::OP_ENDIF) => {
+                self.conditions.pop().ok_or(InterpreterError::UnbalancedConditional)?;
+                self.record_executed(OpCodes::OP_ENDIF);
+                return Ok(());
+            }
+            ScriptBit::OpCode(code @ (OpCodes::OP_VERIF | OpCodes::OP_VERNOTIF)) => return Err(InterpreterError::InvalidOpcode(*code)),
+            ScriptBit::OpCode(OpCodes::OP_2MUL) if !is_chronicle => return Err(InterpreterError::DisabledOpCode(&OpCodes::OP_2MUL)),
+            ScriptBit::OpCode(OpCodes::OP_2DIV) if !is_chronicle => return Err(InterpreterError::DisabledOpCode(&OpCodes::OP_2DIV)),
+            _ if !is_executing => return Ok(()),
+            ScriptBit::OpCode(OpCodes::OP_RETURN) => {
+                self.record_executed(OpCodes::OP_RETURN);
+                if !self.flags.contains(ScriptFlags::UTXO_AFTER_GENESIS) {
+                    return Err(InterpreterError::OpReturn);
                 }
-                Err(e) => {
-                    self.state.executed_opcodes.push(*o);
-                    return Err(e);
+
+                // Post-Genesis OP_RETURN leaves the stack to be evaluated as is. Outside of any conditional the rest of the
+                // script is never read, inside one the script must still have balanced conditionals.
+                self.returned_early = true;
+                match self.conditions.is_empty() {
+                    true => self.state.status = Status::Finished,
+                    false => self.non_top_level_return = true,
                 }
-            },
+                return Ok(());
+            }
+            _ => (),
+        }
+
+        if self.flags.contains(ScriptFlags::MINIMALDATA) && !is_minimal_push(bit) {
+            return Err(InterpreterError::MinimalData);
+        }
+
+        match bit {
+            ScriptBit::OpCode(o) => {
+                // The state is moved out so opcodes can change it in place while reading the rest of the Interpreter
+                let mut state = std::mem::take(&mut self.state);
+                let result = self.match_opcode(o, &mut state, observer);
+                self.state = state;
+                self.record_executed(*o);
+                result?;
+            }
             ScriptBit::Push(v) => {
                 self.state.stack.push(v.clone());
-                self.state.executed_opcodes.push(OpCodes::OP_DATA);
-                self.state.clone()
+                self.record_executed(OpCodes::OP_DATA);
             }
             ScriptBit::PushData(size, v) => {
                 self.state.stack.push(v.clone());
-                self.state.executed_opcodes.push(*size);
-                self.state.clone()
-            }
-            ScriptBit::If { code, pass, fail } => {
-                let predicate = self.state.stack.pop_bool()?;
-                self.state.executed_opcodes.push(*code);
-
-                if predicate {
-                    let _removed: Vec<ScriptBit> = self.script_bits.splice(self.script_index + 1..self.script_index + 1, pass.clone()).collect();
-                    // println!("Removed items: {:?}", removed);
-                } else {
-                    let _removed: Vec<ScriptBit> = self.script_bits.splice(self.script_index + 1..self.script_index + 1, fail.clone().unwrap_or_default()).collect();
-                    // println!("Removed items: {:?}", removed);
-                }
+                self.record_executed(*size);
+            }
+            // Scripts are executed as a flat stream of opcodes, If statements are never nested
+            ScriptBit::If { code, .. } => return Err(InterpreterError::InvalidOpcode(*code)),
+            // Coinbase inputs are recognised by `Interpreter::from_transaction` and never executed
+            ScriptBit::Coinbase(_) => return Err(InterpreterError::CoinbaseScript),
+            // Never decoded from a serialised script, the bytes of an unparsed tail are executed as whatever they decode to
+            ScriptBit::Unparsed(_) => return Err(InterpreterError::InvalidOpcode(OpCodes::OP_INVALIDOPCODE)),
+        }
 
-                self.state.clone()
-            }
-            ScriptBit::Coinbase(_) => todo!(),
-        })
+        Ok(())
     }
 
-    #[allow(unused_mut)]
-    pub(crate) fn match_opcode(script_index: usize, opcode: &OpCodes, state: &mut State, tx: Option<TxScript>) -> Result<State, InterpreterError> {
-        let mut state: &mut State = state;
-        match opcode {
+    /// Executes the opcode at the current script index, changing the given state in place.
+    pub(crate) fn match_opcode(&mut self, opcode: &OpCodes, state: &mut State, observer: &mut dyn ExecutionObserver) -> Result<(), InterpreterError> {
+        let script_offset = self.script_offsets[self.script_index];
+        // A checker set on the Interpreter takes precedence over the transaction being spent
+        let checker: Option<&dyn SignatureChecker> = match (&self.signature_checker, &self.tx_script) {
+            (Some(v), _) => Some(v.as_ref()),
+            (None, Some(v)) => Some(v),
+            (None, None) => None,
+        };
+        let flags = self.flags;
+        let script = self.script_bytes.as_slice();
+        let require_minimal = flags.contains(ScriptFlags::MINIMALDATA);
+        let limits = self.limits();
+        let max_num_length = limits.max_script_num_length;
+        let is_chronicle = flags.contains(ScriptFlags::UTXO_AFTER_CHRONICLE);
+        match *opcode {
             OpCodes::OP_0 => state.stack.push_number(0)?,
             OpCodes::OP_1NEGATE => state.stack.push_number(-1)?,
             OpCodes::OP_1 => state.stack.push_number(1)?,
@@ -83,23 +194,23 @@ impl Interpreter {
             OpCodes::OP_16 => state.stack.push_number(16)?,
             OpCodes::OP_NOP => {}
             OpCodes::OP_IF => {
-                // NOP - handled by ScriptBit interpreter
+                // NOP - handled by match_script_bit
             }
             OpCodes::OP_NOTIF => {
-                // NOP - handled by ScriptBit interpreter
+                // NOP - handled by match_script_bit
             }
             OpCodes::OP_ELSE => {
-                // NOP - handled by ScriptBit interpreter
+                // NOP - handled by match_script_bit
             }
             OpCodes::OP_ENDIF => {
-                // NOP - handled by ScriptBit interpreter
+                // NOP - handled by match_script_bit
             }
             OpCodes::OP_VERIFY => {
                 let predicate = state.stack.pop_bool()?;
-                Interpreter::verify(predicate)?
+                Interpreter::verify_predicate(predicate)?
             }
             OpCodes::OP_RETURN => {
-                return Ok(state.clone());
+                // NOP - handled by match_script_bit
             }
             OpCodes::OP_TOALTSTACK => {
                 let a = state.stack.pop_bytes()?;
@@ -110,13 +221,10 @@ impl Interpreter {
                 state.stack.push_bytes(a);
             }
             OpCodes::OP_IFDUP => {
-                let predicate = state.stack.pop_bool()?;
-                if predicate {
-                    let top_data = match state.stack.last().cloned() {
-                        Some(v) => v,
-                        None => return Err(InterpreterError::EmptyStack),
-                    };
-
+                // The top element is only duplicated if it is true, it is left on the stack either way
+                let top_data = state.stack.last().ok_or(InterpreterError::EmptyStack)?;
+                if stack_trait::cast_to_bool(top_data) {
+                    let top_data = top_data.clone();
                     state.stack.push(top_data);
                 }
             }
@@ -125,7 +233,7 @@ impl Interpreter {
                 state.stack.push_number(depth as i64)?;
             }
             OpCodes::OP_DROP => {
-                state.stack.pop_bytes()?;
+                state.stack.pop_refs::<1>()?;
             }
             OpCodes::OP_DUP => {
                 let top_data = match state.stack.last().cloned() {
@@ -136,162 +244,145 @@ impl Interpreter {
                 state.stack.push(top_data);
             }
             OpCodes::OP_NIP => {
-                state.stack.remove(state.stack.len() - 2);
+                let len = stack_depth(&state.stack, 2)?;
+                state.stack.discard(len - 2);
             }
             OpCodes::OP_OVER => {
-                let index = state.stack.len() - 2;
-                let second_last = state.stack.get(index).cloned().ok_or(InterpreterError::NumberOutOfRange)?;
+                let len = stack_depth(&state.stack, 2)?;
+                let second_last = state.stack[len - 2].clone();
                 state.stack.push_bytes(second_last);
             }
             OpCodes::OP_PICK => {
-                let index = state.stack.pop_number()?;
-                let selected_item = state.stack.get((state.stack.len() - 1) - index as usize).cloned().ok_or(InterpreterError::NumberOutOfRange)?;
+                let index = state.stack.pop_index(require_minimal, max_num_length)?;
+                let position = stack_position(&state.stack, index)?;
+                let selected_item = state.stack[position].clone();
                 state.stack.push_bytes(selected_item);
             }
             OpCodes::OP_ROLL => {
-                let index = state.stack.pop_number()?;
-                let selected_item = state.stack.remove((state.stack.len() - 1) - index as usize);
-                state.stack.push_bytes(selected_item);
+                let index = state.stack.pop_index(require_minimal, max_num_length)?;
+                let position = stack_position(&state.stack, index)?;
+                state.stack.roll(position);
             }
             OpCodes::OP_ROT => {
-                let len = state.stack.len();
-                let third = state.stack.remove(len - 3);
-
-                state.stack.push_bytes(third);
+                let len = stack_depth(&state.stack, 3)?;
+                state.stack.roll(len - 3);
             }
             OpCodes::OP_SWAP => {
-                let len = state.stack.len();
+                let len = stack_depth(&state.stack, 2)?;
                 state.stack.swap(len - 1, len - 2);
             }
             OpCodes::OP_TUCK => {
-                let selected_item = state.stack.last().cloned().ok_or(InterpreterError::NumberOutOfRange)?;
-                state.stack.insert(state.stack.len() - 2, selected_item);
+                let len = stack_depth(&state.stack, 2)?;
+                let selected_item = state.stack[len - 1].clone();
+                state.stack.insert(len - 2, selected_item);
             }
             OpCodes::OP_2DROP => {
-                state.stack.pop_bytes()?;
-                state.stack.pop_bytes()?;
+                state.stack.pop_refs::<2>()?;
             }
             OpCodes::OP_2DUP => {
-                let first = state.stack.last().cloned().ok_or(InterpreterError::NumberOutOfRange)?;
-                let second = state.stack.get(state.stack.len() - 2).cloned().ok_or(InterpreterError::NumberOutOfRange)?;
-
-                state.stack.push_bytes(first);
-                state.stack.push_bytes(second);
+                let len = stack_depth(&state.stack, 2)?;
+                state.stack.extend_from_within(len - 2..);
             }
             OpCodes::OP_3DUP => {
-                let first = state.stack.last().cloned().ok_or(InterpreterError::NumberOutOfRange)?;
-                let second = state.stack.get(state.stack.len() - 2).cloned().ok_or(InterpreterError::NumberOutOfRange)?;
-                let third = state.stack.get(state.stack.len() - 3).cloned().ok_or(InterpreterError::NumberOutOfRange)?;
-
-                state.stack.push_bytes(first);
-                state.stack.push_bytes(second);
-                state.stack.push_bytes(third);
+                let len = stack_depth(&state.stack, 3)?;
+                state.stack.extend_from_within(len - 3..);
             }
             OpCodes::OP_2OVER => {
-                let len = state.stack.len();
+                let len = stack_depth(&state.stack, 4)?;
                 let third = state.stack[len - 3].clone();
                 let fourth = state.stack[len - 4].clone();
                 state.stack.push_bytes(fourth);
                 state.stack.push_bytes(third);
             }
             OpCodes::OP_2ROT => {
-                let index = state.stack.len() - 6;
-                let sixth = state.stack.remove(index);
-                let fifth = state.stack.remove(index);
-                state.stack.push_bytes(sixth);
-                state.stack.push_bytes(fifth);
+                let index = stack_depth(&state.stack, 6)? - 6;
+                state.stack.roll(index);
+                state.stack.roll(index);
             }
 
             OpCodes::OP_2SWAP => {
-                let x1 = state.stack.pop_bytes()?;
-                let x2 = state.stack.pop_bytes()?;
-                let x3 = state.stack.pop_bytes()?;
-                let x4 = state.stack.pop_bytes()?;
-
-                state.stack.push_bytes(x3);
-                state.stack.push_bytes(x4);
-                state.stack.push_bytes(x1);
-                state.stack.push_bytes(x2)
+                let index = stack_depth(&state.stack, 4)? - 4;
+                state.stack.roll(index);
+                state.stack.roll(index);
             }
             OpCodes::OP_CAT => {
-                let mut x1 = state.stack.pop_bytes()?;
-                let x2 = state.stack.pop_bytes()?;
+                // x1 x2 OP_CAT gives x1 followed by x2, x2 being on top
+                let [x2, x1] = state.stack.pop_refs()?;
+                let joined = [x1, x2].concat();
 
-                x1.extend_from_slice(&x2);
-
-                state.stack.push_bytes(x1)
+                state.stack.push_bytes(joined)
             }
             OpCodes::OP_SPLIT => {
-                let x = state.stack.pop_bytes()?;
-                let n = state.stack.pop_number()?;
+                let n = state.stack.pop_index(require_minimal, max_num_length)?;
+                let [x] = state.stack.pop_refs()?;
+                if n > x.len() {
+                    return Err(InterpreterError::InvalidSplitRange);
+                }
 
-                let (x1, x2) = x.split_at(n as usize);
-                state.stack.push_bytes(x1.to_vec());
-                state.stack.push_bytes(x2.to_vec());
+                let (x1, x2) = (x[..n].to_vec(), x[n..].to_vec());
+                state.stack.push_bytes(x1);
+                state.stack.push_bytes(x2);
             }
 
             OpCodes::OP_SIZE => {
-                let len = state.stack.last().unwrap().len();
+                let len = state.stack.last().ok_or(InterpreterError::EmptyStack)?.len();
                 state.stack.push_number(len as i64)?;
             }
             OpCodes::OP_INVERT => {
-                let inverted_bytes = state.stack.pop_bytes()?.iter().map(|x| !x).collect();
+                let [a] = state.stack.pop_refs()?;
+                let inverted_bytes = a.iter().map(|x| !x).collect();
 
                 state.stack.push(inverted_bytes);
             }
             OpCodes::OP_AND => {
-                let a = state.stack.pop_bytes()?;
-                let b = state.stack.pop_bytes()?;
+                let [a, b] = state.stack.pop_refs()?;
 
                 let and_array = b.iter().zip(a.iter()).map(|(&x1, &x2)| x1 & x2).collect();
 
                 state.stack.push_bytes(and_array);
             }
             OpCodes::OP_OR => {
-                let a = state.stack.pop_bytes()?;
-                let b = state.stack.pop_bytes()?;
+                let [a, b] = state.stack.pop_refs()?;
 
                 let or_array = b.iter().zip(a.iter()).map(|(&x1, &x2)| x1 | x2).collect();
 
                 state.stack.push_bytes(or_array);
             }
             OpCodes::OP_XOR => {
-                let a = state.stack.pop_bytes()?;
-                let b = state.stack.pop_bytes()?;
+                let [a, b] = state.stack.pop_refs()?;
 
                 let xor_array = b.iter().zip(a.iter()).map(|(&x1, &x2)| x1 ^ x2).collect();
 
                 state.stack.push_bytes(xor_array);
             }
             OpCodes::OP_EQUAL => {
-                let a = state.stack.pop_bytes()?;
-                let b = state.stack.pop_bytes()?;
+                let [a, b] = state.stack.pop_refs()?;
+                let is_equal = a == b;
 
-                state.stack.push_bool(a.eq(&b))?;
+                state.stack.push_bool(is_equal)?;
             }
             OpCodes::OP_EQUALVERIFY => {
-                let a = state.stack.pop_bytes()?;
-                let b = state.stack.pop_bytes()?;
+                let [a, b] = state.stack.pop_refs()?;
 
-                Interpreter::verify(a.eq(&b))?;
+                Interpreter::verify_predicate(a == b)?;
             }
             OpCodes::OP_1ADD => {
-                let a = state.stack.pop_bigint()?;
+                let a = state.stack.pop_bigint(require_minimal, max_num_length)?;
 
                 state.stack.push_bigint(a + 1)?;
             }
             OpCodes::OP_1SUB => {
-                let a = state.stack.pop_bigint()?;
+                let a = state.stack.pop_bigint(require_minimal, max_num_length)?;
 
                 state.stack.push_bigint(a - 1)?;
             }
             OpCodes::OP_NEGATE => {
-                let a = state.stack.pop_bigint()?;
+                let a = state.stack.pop_bigint(require_minimal, max_num_length)?;
 
                 state.stack.push_bigint(-a)?;
             }
             OpCodes::OP_ABS => {
-                let a = state.stack.pop_bigint()?;
+                let a = state.stack.pop_bigint(require_minimal, max_num_length)?;
 
                 let pos = match a < BigInt::from(0) {
                     true => a.neg(),
@@ -301,67 +392,66 @@ impl Interpreter {
                 state.stack.push_bigint(pos)?;
             }
             OpCodes::OP_NOT => {
-                let a = state.stack.pop_number()?;
-
-                let notted = match a {
-                    0 => 1,
-                    _ => 0,
-                };
+                let a = state.stack.pop_bigint(require_minimal, max_num_length)?;
 
-                state.stack.push_number(notted)?;
+                state.stack.push_bool(a.is_zero())?;
             }
             OpCodes::OP_0NOTEQUAL => {
-                let a = state.stack.pop_number()?;
+                let a = state.stack.pop_bigint(require_minimal, max_num_length)?;
 
-                let notted = match a {
-                    0 => 0,
-                    _ => 1,
-                };
-
-                state.stack.push_number(notted)?;
+                state.stack.push_bool(!a.is_zero())?;
             }
             OpCodes::OP_ADD => {
-                let a = state.stack.pop_bigint()?;
-                let b = state.stack.pop_bigint()?;
+                let b = state.stack.pop_bigint(require_minimal, max_num_length)?;
+                let a = state.stack.pop_bigint(require_minimal, max_num_length)?;
 
-                let sum = a + b;
-                state.stack.push(sum.to_signed_bytes_le());
+                state.stack.push_bigint(a + b)?;
             }
             OpCodes::OP_SUB => {
-                let a = state.stack.pop_bigint()?;
-                let b = state.stack.pop_bigint()?;
+                let b = state.stack.pop_bigint(require_minimal, max_num_length)?;
+                let a = state.stack.pop_bigint(require_minimal, max_num_length)?;
 
                 state.stack.push_bigint(a - b)?;
             }
             OpCodes::OP_MUL => {
-                let a = state.stack.pop_bigint()?;
-                let b = state.stack.pop_bigint()?;
+                let b = state.stack.pop_bigint(require_minimal, max_num_length)?;
+                let a = state.stack.pop_bigint(require_minimal, max_num_length)?;
 
                 state.stack.push_bigint(a * b)?;
             }
             OpCodes::OP_DIV => {
-                let a = state.stack.pop_bigint()?;
-                let b = state.stack.pop_bigint()?;
+                let b = state.stack.pop_bigint(require_minimal, max_num_length)?;
+                let a = state.stack.pop_bigint(require_minimal, max_num_length)?;
+
+                if b.is_zero() {
+                    return Err(InterpreterError::DivByZero);
+                }
 
                 state.stack.push_bigint(a / b)?;
             }
             OpCodes::OP_MOD => {
-                let a = state.stack.pop_bigint()?;
-                let b = state.stack.pop_bigint()?;
+                let b = state.stack.pop_bigint(require_minimal, max_num_length)?;
+                let a = state.stack.pop_bigint(require_minimal, max_num_length)?;
+
+                if b.is_zero() {
+                    return Err(InterpreterError::ModByZero);
+                }
 
                 state.stack.push_bigint(a % b)?;
             }
             OpCodes::OP_LSHIFT => {
-                let a = state.stack.pop_bigint()?;
-                let b = state.stack.pop_number()?;
+                let n = state.stack.pop_bigint(require_minimal, max_num_length)?;
+                let [a] = state.stack.pop_refs()?;
+                let shifted = shift_bytes(a, &n, true)?;
 
-                state.stack.push_bigint(a << b)?;
+                state.stack.push_bytes(shifted);
             }
             OpCodes::OP_RSHIFT => {
-                let a = state.stack.pop_bigint()?;
-                let b = state.stack.pop_number()?;
+                let n = state.stack.pop_bigint(require_minimal, max_num_length)?;
+                let [a] = state.stack.pop_refs()?;
+                let shifted = shift_bytes(a, &n, false)?;
 
-                state.stack.push_bigint(a >> b)?;
+                state.stack.push_bytes(shifted);
             }
             OpCodes::OP_BOOLAND => {
                 let a = state.stack.pop_bool()?;
@@ -376,50 +466,50 @@ impl Interpreter {
                 state.stack.push_bool(a || b)?;
             }
             OpCodes::OP_NUMEQUAL => {
-                let a = state.stack.pop_bigint()?;
-                let b = state.stack.pop_bigint()?;
+                let b = state.stack.pop_bigint(require_minimal, max_num_length)?;
+                let a = state.stack.pop_bigint(require_minimal, max_num_length)?;
 
                 state.stack.push_bool(a == b)?;
             }
             OpCodes::OP_NUMEQUALVERIFY => {
-                let a = state.stack.pop_bigint()?;
-                let b = state.stack.pop_bigint()?;
+                let b = state.stack.pop_bigint(require_minimal, max_num_length)?;
+                let a = state.stack.pop_bigint(require_minimal, max_num_length)?;
 
-                Interpreter::verify(a == b)?;
+                Interpreter::verify_predicate(a == b)?;
             }
             OpCodes::OP_NUMNOTEQUAL => {
-                let a = state.stack.pop_bigint()?;
-                let b = state.stack.pop_bigint()?;
+                let b = state.stack.pop_bigint(require_minimal, max_num_length)?;
+                let a = state.stack.pop_bigint(require_minimal, max_num_length)?;
 
                 state.stack.push_bool(a != b)?;
             }
             OpCodes::OP_LESSTHAN => {
-                let a = state.stack.pop_bigint()?;
-                let b = state.stack.pop_bigint()?;
+                let b = state.stack.pop_bigint(require_minimal, max_num_length)?;
+                let a = state.stack.pop_bigint(require_minimal, max_num_length)?;
 
                 state.stack.push_bool(a < b)?;
             }
             OpCodes::OP_LESSTHANOREQUAL => {
-                let a = state.stack.pop_bigint()?;
-                let b = state.stack.pop_bigint()?;
+                let b = state.stack.pop_bigint(require_minimal, max_num_length)?;
+                let a = state.stack.pop_bigint(require_minimal, max_num_length)?;
 
                 state.stack.push_bool(a <= b)?;
             }
             OpCodes::OP_GREATERTHAN => {
-                let a = state.stack.pop_bigint()?;
-                let b = state.stack.pop_bigint()?;
+                let b = state.stack.pop_bigint(require_minimal, max_num_length)?;
+                let a = state.stack.pop_bigint(require_minimal, max_num_length)?;
 
                 state.stack.push_bool(a > b)?;
             }
             OpCodes::OP_GREATERTHANOREQUAL => {
-                let a = state.stack.pop_bigint()?;
-                let b = state.stack.pop_bigint()?;
+                let b = state.stack.pop_bigint(require_minimal, max_num_length)?;
+                let a = state.stack.pop_bigint(require_minimal, max_num_length)?;
 
                 state.stack.push_bool(a >= b)?;
             }
             OpCodes::OP_MIN => {
-                let a = state.stack.pop_bigint()?;
-                let b = state.stack.pop_bigint()?;
+                let b = state.stack.pop_bigint(require_minimal, max_num_length)?;
+                let a = state.stack.pop_bigint(require_minimal, max_num_length)?;
 
                 let smallest = match a > b {
                     true => b,
@@ -429,8 +519,8 @@ impl Interpreter {
                 state.stack.push_bigint(smallest)?;
             }
             OpCodes::OP_MAX => {
-                let a = state.stack.pop_bigint()?;
-                let b = state.stack.pop_bigint()?;
+                let b = state.stack.pop_bigint(require_minimal, max_num_length)?;
+                let a = state.stack.pop_bigint(require_minimal, max_num_length)?;
 
                 let biggest = match a < b {
                     true => b,
@@ -440,135 +530,194 @@ impl Interpreter {
                 state.stack.push_bigint(biggest)?;
             }
             OpCodes::OP_WITHIN => {
-                let x = state.stack.pop_bigint()?;
-                let min = state.stack.pop_bigint()?;
-                let max = state.stack.pop_bigint()?;
+                let max = state.stack.pop_bigint(require_minimal, max_num_length)?;
+                let min = state.stack.pop_bigint(require_minimal, max_num_length)?;
+                let x = state.stack.pop_bigint(require_minimal, max_num_length)?;
 
-                state.stack.push_bool(x >= min && x <= max)?;
+                state.stack.push_bool(min <= x && x < max)?;
             }
             OpCodes::OP_NUM2BIN => {
-                let length = state.stack.pop_number()?;
-                let bytes = state.stack.pop_bytes()?;
-
-                if length < 1 || length < bytes.len() as i32 {
-                    return Err(InterpreterError::InvalidStackOperation("OP_NUM2BIN failed, provide length was out of range"));
+                let length = state.stack.pop_index(require_minimal, max_num_length)?;
+                if length > limits.max_element_size {
+                    return Err(InterpreterError::PushSize);
                 }
 
-                // Fill the data in, extend the buffer to the length of the length parameter
-                let (sign, mut bin_array) = stack_trait::to_bigint(&bytes)?.to_bytes_le();
-                bin_array.resize(length as usize, 0);
-                let bin_array_len = bin_array.len();
-
-                let full = bin_array[bin_array_len - 1] & 0x80;
-                if full > 0 {
-                    bin_array.push(0x00);
+                // Same as the node: the number is minimally encoded, then padded with zeros with its sign bit moved to the new last byte
+                let mut bin_array = ScriptNum::from_bytes_lenient(state.stack.pop_refs::<1>()?[0]).to_bytes();
+                if bin_array.len() > length {
+                    return Err(InterpreterError::ImpossibleEncoding);
+                }
+                limits.check_push_memory(state, length)?;
+
+                if bin_array.len() < length {
+                    let sign_bit = match bin_array.last_mut() {
+                        Some(last) => {
+                            let sign_bit = *last & 0x80;
+                            *last &= 0x7f;
+                            sign_bit
+                        }
+                        None => 0x00,
+                    };
+                    bin_array.resize(length - 1, 0x00);
+                    bin_array.push(sign_bit);
                 }
-
-                // // Add 0x00 to the end if last byte is positive sign
-                match sign {
-                    Sign::Plus => bin_array[bin_array_len - 1] |= 0x00,
-                    Sign::Minus => bin_array[bin_array_len - 1] |= 0x80,
-                    Sign::NoSign => return Err(InterpreterError::InvalidStackOperation("OP_NUM2BIN failed, invalid sign on bigint.")),
-                };
                 state.stack.push_bytes(bin_array);
             }
             OpCodes::OP_BIN2NUM => {
-                let bigint = state.stack.pop_bigint()?;
-                state.stack.push_bigint(bigint)?;
+                // Any byte array is converted to its minimal encoding, which must then be a valid number
+                let number = ScriptNum::from_bytes_lenient(state.stack.pop_refs::<1>()?[0]);
+                state.stack.push_bytes(number.to_bytes());
+                if state.stack.last().map(|x| x.len()).unwrap_or_default() > max_num_length {
+                    return Err(InterpreterError::NumberOutOfRange);
+                }
             }
             OpCodes::OP_RIPEMD160 => {
-                let data = state.stack.pop_bytes()?;
+                let [data] = state.stack.pop_refs()?;
 
-                let result = Hash::ripemd_160(&data);
+                let result = Hash::ripemd_160(data);
                 state.stack.push(result.to_bytes());
             }
             OpCodes::OP_SHA1 => {
-                let data = state.stack.pop_bytes()?;
+                let [data] = state.stack.pop_refs()?;
 
-                let result = Hash::sha_1(&data);
+                let result = Hash::sha_1(data);
                 state.stack.push(result.to_bytes());
             }
             OpCodes::OP_SHA256 => {
-                let data = state.stack.pop_bytes()?;
+                let [data] = state.stack.pop_refs()?;
 
-                let result = Hash::sha_256(&data);
+                let result = Hash::sha_256(data);
                 state.stack.push(result.to_bytes());
             }
             OpCodes::OP_HASH160 => {
-                let data = state.stack.pop_bytes()?;
+                let [data] = state.stack.pop_refs()?;
 
-                let result = Hash::hash_160(&data);
+                let result = Hash::hash_160(data);
                 state.stack.push(result.to_bytes());
             }
             OpCodes::OP_HASH256 => {
-                let data = state.stack.pop_bytes()?;
+                let [data] = state.stack.pop_refs()?;
 
-                let result = Hash::sha_256d(&data);
+                let result = Hash::sha_256d(data);
                 state.stack.push(result.to_bytes());
             }
-            OpCodes::OP_CODESEPARATOR => state.codeseparator_offset = script_index + 1,
+            OpCodes::OP_CODESEPARATOR => state.codeseparator_offset = script_offset + 1,
             OpCodes::OP_CHECKSIG => {
-                let mut txscript = match tx {
-                    Some(x) => x,
-                    None => return Err(InterpreterError::RequiresTransaction(&OpCodes::OP_CHECKSIG)),
-                };
+                let checker = checker.ok_or(InterpreterError::RequiresTransaction(&OpCodes::OP_CHECKSIG))?;
 
-                let is_signature_valid = checksig(state, &mut txscript)?;
+                let is_signature_valid = checksig(state, checker, flags, script, observer)?;
                 state.stack.push_bool(is_signature_valid)?;
             }
             OpCodes::OP_CHECKSIGVERIFY => {
-                let mut txscript = match tx {
-                    Some(x) => x,
-                    None => return Err(InterpreterError::RequiresTransaction(&OpCodes::OP_CHECKSIGVERIFY)),
-                };
+                let checker = checker.ok_or(InterpreterError::RequiresTransaction(&OpCodes::OP_CHECKSIGVERIFY))?;
 
-                let is_signature_valid = checksig(state, &mut txscript)?;
-                Interpreter::verify(is_signature_valid)?
+                let is_signature_valid = checksig(state, checker, flags, script, observer)?;
+                Interpreter::verify_predicate(is_signature_valid)?
             }
             OpCodes::OP_CHECKMULTISIG => {
-                let mut txscript = match tx {
-                    Some(x) => x,
-                    None => return Err(InterpreterError::RequiresTransaction(&OpCodes::OP_CHECKMULTISIG)),
-                };
+                let checker = checker.ok_or(InterpreterError::RequiresTransaction(&OpCodes::OP_CHECKMULTISIG))?;
 
-                let is_multisig_valid = multisig(state, &mut txscript)?;
+                let is_multisig_valid = multisig(state, checker, flags, &limits, &mut self.op_count, script, observer)?;
                 state.stack.push_bool(is_multisig_valid)?
             }
             OpCodes::OP_CHECKMULTISIGVERIFY => {
-                let mut txscript = match tx {
-                    Some(x) => x,
-                    None => return Err(InterpreterError::RequiresTransaction(&OpCodes::OP_CHECKMULTISIGVERIFY)),
-                };
+                let checker = checker.ok_or(InterpreterError::RequiresTransaction(&OpCodes::OP_CHECKMULTISIGVERIFY))?;
 
-                let is_multisig_valid = multisig(state, &mut txscript)?;
-                Interpreter::verify(is_multisig_valid)?
+                let is_multisig_valid = multisig(state, checker, flags, &limits, &mut self.op_count, script, observer)?;
+                Interpreter::verify_predicate(is_multisig_valid)?
             }
 
-            // TODO: Allow enabling of specific opcodes or opcode feature sets
-            OpCodes::OP_CHECKLOCKTIMEVERIFY => return Err(InterpreterError::DisabledOpCode(&OpCodes::OP_CHECKLOCKTIMEVERIFY)),
-            OpCodes::OP_CHECKSEQUENCEVERIFY => return Err(InterpreterError::DisabledOpCode(&OpCodes::OP_CHECKSEQUENCEVERIFY)),
+            // OP_NOP2 and OP_NOP3 unless enabled before Genesis, the operand is left on the stack either way
+            OpCodes::OP_CHECKLOCKTIMEVERIFY => {
+                if !flags.contains(ScriptFlags::CHECKLOCKTIMEVERIFY) || flags.contains(ScriptFlags::UTXO_AFTER_GENESIS) {
+                    if flags.contains(ScriptFlags::DISCOURAGE_UPGRADABLE_NOPS) {
+                        return Err(InterpreterError::DiscourageUpgradableNops(*opcode));
+                    }
+                } else {
+                    let checker = checker.ok_or(InterpreterError::RequiresTransaction(&OpCodes::OP_CHECKLOCKTIMEVERIFY))?;
+                    let lock_time = peek_lock_time(state, require_minimal)?;
+                    if !checker.check_lock_time(lock_time) {
+                        return Err(InterpreterError::UnsatisfiedLockTime);
+                    }
+                }
+            }
+            OpCodes::OP_CHECKSEQUENCEVERIFY => {
+                if !flags.contains(ScriptFlags::CHECKSEQUENCEVERIFY) || flags.contains(ScriptFlags::UTXO_AFTER_GENESIS) {
+                    if flags.contains(ScriptFlags::DISCOURAGE_UPGRADABLE_NOPS) {
+                        return Err(InterpreterError::DiscourageUpgradableNops(*opcode));
+                    }
+                } else {
+                    let checker = checker.ok_or(InterpreterError::RequiresTransaction(&OpCodes::OP_CHECKSEQUENCEVERIFY))?;
+                    let sequence = peek_lock_time(state, require_minimal)?;
+                    // Sequences with the disable flag set are reserved for future soft forks and always pass
+                    if sequence & SEQUENCE_LOCKTIME_DISABLE_FLAG == 0 && !checker.check_sequence(sequence) {
+                        return Err(InterpreterError::UnsatisfiedLockTime);
+                    }
+                }
+            }
 
+            OpCodes::OP_VER if is_chronicle => state.stack.push_bytes(self.tx_version_bytes(&OpCodes::OP_VER)?),
             OpCodes::OP_VER => return Err(InterpreterError::DisabledOpCode(&OpCodes::OP_VER)),
             OpCodes::OP_VERIF => return Err(InterpreterError::DisabledOpCode(&OpCodes::OP_VERIF)),
             OpCodes::OP_VERNOTIF => return Err(InterpreterError::DisabledOpCode(&OpCodes::OP_VERNOTIF)),
             OpCodes::OP_RESERVED => return Err(InterpreterError::DisabledOpCode(&OpCodes::OP_RESERVED)),
             OpCodes::OP_RESERVED1 => return Err(InterpreterError::DisabledOpCode(&OpCodes::OP_RESERVED1)),
             OpCodes::OP_RESERVED2 => return Err(InterpreterError::DisabledOpCode(&OpCodes::OP_RESERVED2)),
-            OpCodes::OP_NOP1 => {}
-            OpCodes::OP_NOP4 => {}
-            OpCodes::OP_NOP5 => {}
-            OpCodes::OP_NOP6 => {}
-            OpCodes::OP_NOP7 => {}
-            OpCodes::OP_NOP8 => {}
-            OpCodes::OP_NOP9 => {}
-            OpCodes::OP_NOP10 => {}
+            OpCodes::OP_SUBSTR if is_chronicle => {
+                let size = state.stack.pop_index(require_minimal, max_num_length)?;
+                let begin = state.stack.pop_index(require_minimal, max_num_length)?;
+                let [data] = state.stack.pop_refs()?;
+
+                let substr = match begin.checked_add(size) {
+                    Some(end) if end <= data.len() => data[begin..end].to_vec(),
+                    _ => return Err(InterpreterError::InvalidSplitRange),
+                };
+                state.stack.push_bytes(substr);
+            }
+            OpCodes::OP_LEFT if is_chronicle => {
+                let size = state.stack.pop_index(require_minimal, max_num_length)?;
+                let [data] = state.stack.pop_refs()?;
+                if size > data.len() {
+                    return Err(InterpreterError::InvalidSplitRange);
+                }
+
+                let left = data[..size].to_vec();
+                state.stack.push_bytes(left);
+            }
+            OpCodes::OP_RIGHT if is_chronicle => {
+                let size = state.stack.pop_index(require_minimal, max_num_length)?;
+                let [data] = state.stack.pop_refs()?;
+                if size > data.len() {
+                    return Err(InterpreterError::InvalidSplitRange);
+                }
+
+                let right = data[data.len() - size..].to_vec();
+                state.stack.push_bytes(right);
+            }
+            OpCodes::OP_LSHIFTNUM if is_chronicle => {
+                let n = state.stack.pop_bigint(require_minimal, max_num_length)?;
+                let a = state.stack.pop_bigint(require_minimal, max_num_length)?;
+
+                state.stack.push_bigint(shift_number(&a, &n, true, max_num_length)?)?;
+            }
+            OpCodes::OP_RSHIFTNUM if is_chronicle => {
+                let n = state.stack.pop_bigint(require_minimal, max_num_length)?;
+                let a = state.stack.pop_bigint(require_minimal, max_num_length)?;
+
+                state.stack.push_bigint(shift_number(&a, &n, false, max_num_length)?)?;
+            }
+            OpCodes::OP_NOP1 | OpCodes::OP_SUBSTR | OpCodes::OP_LEFT | OpCodes::OP_RIGHT | OpCodes::OP_LSHIFTNUM | OpCodes::OP_RSHIFTNUM | OpCodes::OP_NOP9 | OpCodes::OP_NOP10 => {
+                if flags.contains(ScriptFlags::DISCOURAGE_UPGRADABLE_NOPS) {
+                    return Err(InterpreterError::DiscourageUpgradableNops(*opcode));
+                }
+            }
             OpCodes::OP_2MUL => {
-                let a = state.stack.pop_bigint()?;
+                let a = state.stack.pop_bigint(require_minimal, max_num_length)?;
 
                 state.stack.push_bigint(a * 2)?;
             }
             OpCodes::OP_2DIV => {
-                let a = state.stack.pop_bigint()?;
+                let a = state.stack.pop_bigint(require_minimal, max_num_length)?;
 
                 state.stack.push_bigint(a / 2)?;
             }
@@ -576,99 +725,217 @@ impl Interpreter {
             _ => return Err(InterpreterError::InvalidOpcode(*opcode)),
         };
 
-        Ok(state.clone())
+        Ok(())
     }
 }
 
-fn checksig(state: &mut State, txscript: &mut TxScript) -> Result<bool, InterpreterError> {
-    let public_key = state.stack.pop_bytes()?;
-    let signature = state.stack.pop_bytes()?;
-    let sighash_byte = signature.last().cloned();
-    println!("Sighash Byte: {:#?}", sighash_byte);
-    let sighash = match sighash_byte {
-        Some(x) => SigHash::try_from(x).map_err(|_| InterpreterError::FailedToConvertSighash)?,
-        None => return Err(InterpreterError::InvalidStackOperation("could not read Sighash flag from signature")),
-    };
-    let preimage = calculate_sighash_preimage(txscript, sighash, state.codeseparator_offset)?;
-    let is_signature_valid = verify_tx_signature(&preimage, txscript, &signature, &public_key)?;
-    Ok(is_signature_valid)
+/// Reads the locktime or sequence on top of the stack, which may be up to 5 bytes long so it can hold any unsigned 32 bit value.
+fn peek_lock_time(state: &State, require_minimal: bool) -> Result<i64, InterpreterError> {
+    let lock_time = state.stack.peek_bigint(require_minimal, 5)?;
+    if lock_time.sign() == Sign::Minus {
+        return Err(InterpreterError::NegativeLockTime);
+    }
+    // 5 bytes always fit
+    Ok(lock_time.to_i64().unwrap_or_default())
 }
 
-fn multisig(state: &mut State, txscript: &mut TxScript) -> Result<bool, InterpreterError> {
-    let pubkey_count = state.stack.pop_number()?;
-    if pubkey_count < 1 {
-        return Err(InterpreterError::InvalidStackOperation("PubKey count must be a positive number"));
+fn checksig(state: &mut State, checker: &dyn SignatureChecker, flags: ScriptFlags, script: &[u8], observer: &mut dyn ExecutionObserver) -> Result<bool, InterpreterError> {
+    let [public_key, signature] = state.stack.pop_refs()?;
+
+    check_signature_encoding(signature, flags)?;
+    check_pubkey_encoding(public_key, flags)?;
+
+    let script_code = get_script_code(script, state.codeseparator_offset)?;
+    let is_signature_valid = check_tx_signature(checker, signature, public_key, &script_code, flags, observer)?;
+    if !is_signature_valid && !signature.is_empty() && flags.contains(ScriptFlags::NULLFAIL) {
+        return Err(InterpreterError::SigNullFail);
     }
 
-    // Slice the correct amount of pubkeys off the stack in reverse order so we can pop them.
-    let mut pubkeys = state.stack.split_off(state.stack.len() - pubkey_count as usize);
-    pubkeys.reverse();
+    Ok(is_signature_valid)
+}
 
-    println!("Pubkeys: {:?}", pubkeys.iter().map(|x| x.to_hex()).collect::<Vec<String>>());
+/// Implements OP_CHECKMULTISIG the same way as the node: `dummy [sig ...] sig_count [pubkey ...] pubkey_count`.
+///
+/// Signatures must be given in the same order as their public keys. Both are checked from the top of the stack down,
+/// each public key is tried once and checking stops as soon as there are fewer keys left than signatures.
+fn multisig(
+    state: &mut State,
+    checker: &dyn SignatureChecker,
+    flags: ScriptFlags,
+    limits: &ScriptLimits,
+    op_count: &mut usize,
+    script: &[u8],
+    observer: &mut dyn ExecutionObserver,
+) -> Result<bool, InterpreterError> {
+    let require_minimal = flags.contains(ScriptFlags::MINIMALDATA);
+    let pubkey_count = state.stack.pop_bigint(require_minimal, limits.max_script_num_length)?;
+    let pubkey_count = match pubkey_count.to_usize() {
+        Some(v) if v <= limits.max_pubkeys_per_multisig => v,
+        _ => return Err(InterpreterError::PubKeyCount),
+    };
 
-    let sig_count = state.stack.pop_number()?;
-    if sig_count < 1 {
-        return Err(InterpreterError::InvalidStackOperation("Signature count must be a positive number"));
+    // Every public key counts as an opcode towards the limit
+    *op_count += pubkey_count;
+    if *op_count > limits.max_ops_per_script {
+        return Err(InterpreterError::OpCount);
     }
 
-    if pubkey_count < sig_count {
-        return Err(InterpreterError::InvalidStackOperation("PubKey count must be greater than or equal to Signature count"));
+    if state.stack.len() < pubkey_count + 1 {
+        return Err(InterpreterError::InvalidStackOperation("OP_CHECKMULTISIG is missing public keys"));
     }
+    let pubkeys = state.stack.split_off(state.stack.len() - pubkey_count);
+
+    let sig_count = state.stack.pop_bigint(require_minimal, limits.max_script_num_length)?;
+    let sig_count = match sig_count.to_usize() {
+        Some(v) if v <= pubkey_count => v,
+        _ => return Err(InterpreterError::SigCount),
+    };
 
-    let sigs = state.stack.split_off(state.stack.len() - sig_count as usize);
+    // The signatures and the extra dummy element
+    if state.stack.len() < sig_count + 1 {
+        return Err(InterpreterError::InvalidStackOperation("OP_CHECKMULTISIG is missing signatures"));
+    }
+    let sigs = state.stack.split_off(state.stack.len() - sig_count);
 
-    // Implement a bug to accidentally pop another item off the stack :-)
-    state.stack.pop_bytes()?;
+    let script_code = get_script_code(script, state.codeseparator_offset)?;
+    let (mut sigs_left, mut keys_left) = (sig_count, pubkey_count);
+    let mut is_success = true;
+    while is_success && sigs_left > 0 {
+        let sig = &sigs[sigs_left - 1];
+        let public_key = &pubkeys[keys_left - 1];
 
-    let mut successes = 0;
-    // Compare all Signatures against all public keys
-    for sig in sigs {
-        let sighash = match sig.last().cloned() {
-            Some(x) => SigHash::try_from(x).map_err(|_| InterpreterError::FailedToConvertSighash)?,
-            None => return Err(InterpreterError::InvalidStackOperation("could not read Sighash flag from signature")),
-        };
+        // Encodings are only checked for the pairs that are compared, which makes the order observable with STRICTENC
+        check_signature_encoding(sig, flags)?;
+        check_pubkey_encoding(public_key, flags)?;
 
-        let preimage = calculate_sighash_preimage(txscript, sighash, state.codeseparator_offset)?;
+        if check_tx_signature(checker, sig, public_key, &script_code, flags, observer)? {
+            sigs_left -= 1;
+        }
+        keys_left -= 1;
 
-        // Pop each pubkey because they are only to be compared against once.
-        while let Some(public_key) = pubkeys.pop() {
-            let is_signature_valid = verify_tx_signature(&preimage, txscript, &sig, &public_key)?;
-            if is_signature_valid {
-                successes += 1;
-                break;
-            }
+        // Too many signatures have failed for the rest to be matched
+        if sigs_left > keys_left {
+            is_success = false;
         }
     }
-    Ok(successes == sig_count)
+
+    if !is_success && flags.contains(ScriptFlags::NULLFAIL) && sigs.iter().any(|x| !x.is_empty()) {
+        return Err(InterpreterError::SigNullFail);
+    }
+
+    // A bug in the original implementation consumes one extra element, which must be empty with NULLDUMMY
+    let [dummy] = state.stack.pop_refs()?;
+    if flags.contains(ScriptFlags::NULLDUMMY) && !dummy.is_empty() {
+        return Err(InterpreterError::SigNullDummy);
+    }
+
+    Ok(is_success)
 }
 
-fn verify_tx_signature(preimage: &[u8], txscript: &mut TxScript, signature: &[u8], public_key: &[u8]) -> Result<bool, InterpreterError> {
-    let sighash_sig = SighashSignature::from_bytes_impl(signature, preimage)?;
-    let is_signature_valid = txscript.tx._verify(&PublicKey::from_bytes_impl(public_key)?, &sighash_sig, false) | txscript.tx._verify(&PublicKey::from_bytes_impl(public_key)?, &sighash_sig, true);
-    Ok(is_signature_valid)
+/// Checks a signature with its trailing sighash byte using the checker, reporting it to the observer. Empty signatures are never valid.
+fn check_tx_signature(
+    checker: &dyn SignatureChecker,
+    signature: &[u8],
+    public_key: &[u8],
+    script_code: &Script,
+    flags: ScriptFlags,
+    observer: &mut dyn ExecutionObserver,
+) -> Result<bool, InterpreterError> {
+    let sighash_type = match signature.last() {
+        Some(x) => *x,
+        None => return Ok(false),
+    };
+
+    let (is_valid, preimage) = checker.check_sig_with_preimage(signature, public_key, script_code, flags)?;
+    observer.signature_check(&SignatureCheckEvent {
+        public_key: public_key.to_vec(),
+        signature: signature.to_vec(),
+        sighash_type,
+        preimage,
+        is_valid,
+    });
+
+    Ok(is_valid)
+}
+
+/// The scriptCode signed over is the currently executing script from just after the last executed OP_CODESEPARATOR.
+fn get_script_code(script: &[u8], codeseparator_offset: usize) -> Result<Script, InterpreterError> {
+    let script_code = script.get(codeseparator_offset..).unwrap_or_default();
+    // Signed over byte for byte, even if it starts part way through a conditional
+    Ok(Script::from_bytes_lenient(script_code).script)
 }
 
-fn calculate_sighash_preimage(txscript: &mut TxScript, sighash: SigHash, codeseparator_offset: usize) -> Result<Vec<u8>, InterpreterError> {
-    let txin = match txscript.tx.get_input(txscript.input_index) {
-        Some(v) => v,
-        _ => return Err(InterpreterError::InvalidStackOperation("could not get TxIn at the provided index")),
+/// Get the length of the stack, checking it holds at least the number of elements the opcode works on.
+fn stack_depth(stack: &[Vec<u8>], required: usize) -> Result<usize, InterpreterError> {
+    match stack.len() {
+        len if len < required => Err(InterpreterError::EmptyStack),
+        len => Ok(len),
+    }
+}
+
+/// Position in the stack of the item `index` places below the top, as used by OP_PICK and OP_ROLL.
+fn stack_position(stack: &[Vec<u8>], index: usize) -> Result<usize, InterpreterError> {
+    match index < stack.len() {
+        true => Ok(stack.len() - 1 - index),
+        false => Err(InterpreterError::InvalidStackOperation("Index is deeper than the stack")),
+    }
+}
+
+/// Shifts the bits of the byte array as a big-endian bit string, keeping its length, as OP_LSHIFT and OP_RSHIFT do after Genesis.
+fn shift_bytes(data: &[u8], n: &BigInt, is_left: bool) -> Result<Vec<u8>, InterpreterError> {
+    if n.sign() == Sign::Minus {
+        return Err(InterpreterError::InvalidNumberRange);
+    }
+
+    let bit_length = data.len() * 8;
+    let n = match n.to_usize() {
+        Some(v) if v < bit_length => v,
+        // Every bit is shifted out
+        _ => return Ok(vec![0; data.len()]),
     };
 
-    let unlock_script_len = txin.get_unlocking_script().to_script_bits().len();
-    let script_offset = codeseparator_offset.saturating_sub(unlock_script_len);
-    let unsigned_script = match txin.get_locking_script() {
-        Some(v) => Script::from_script_bits(v.to_script_bits()[script_offset..].to_vec()),
-        None => return Err(InterpreterError::InvalidStackOperation("TxIn at given index does not have locking script provided")),
+    let value = BigUint::from_bytes_be(data);
+    let shifted = match is_left {
+        // Bits shifted past the start of the array are dropped
+        true => (value << n) & ((BigUint::from(1_u8) << bit_length) - 1_u8),
+        false => value >> n,
     };
-    println!("Unsigned script: {}", unsigned_script.to_asm_string());
 
-    let satoshis = match txscript.tx.get_input(txscript.input_index).and_then(|x| x.get_satoshis()) {
-        Some(v) => v,
-        _ => return Err(InterpreterError::InvalidStackOperation("TxIn at given index does not have satoshis provided")),
+    // Pad back out to the original length, leading zero bytes are dropped by the conversion
+    let bytes = shifted.to_bytes_be();
+    let mut result = vec![0; data.len() - bytes.len()];
+    result.extend(bytes);
+    Ok(result)
+}
+
+/// Shifts the magnitude of a number by `n` bits, keeping its sign, for OP_LSHIFTNUM and OP_RSHIFTNUM.
+fn shift_number(a: &BigInt, n: &BigInt, is_left: bool, max_length: usize) -> Result<BigInt, InterpreterError> {
+    if n.sign() == Sign::Minus {
+        return Err(InterpreterError::InvalidNumberRange);
+    }
+
+    let n = n.to_u64().unwrap_or(u64::MAX);
+    let magnitude = match is_left {
+        _ if a.is_zero() => BigUint::zero(),
+        // Fail before allocating a number that could never be popped again
+        true if a.bits().saturating_add(n) > (max_length as u64).saturating_mul(8) => return Err(InterpreterError::NumberOutOfRange),
+        true => a.magnitude() << n,
+        false if n >= a.bits() => BigUint::zero(),
+        false => a.magnitude() >> n,
     };
 
-    txscript
-        .tx
-        .sighash_preimage_impl(txscript.input_index, sighash, &unsigned_script, satoshis)
-        .map_err(|e| InterpreterError::SighashPreimageCalculation(e.to_string()))
+    Ok(BigInt::from_biguint(a.sign(), magnitude))
+}
+
+/// Checks a push uses the smallest possible opcode for its data, ie. OP_0 - OP_16, OP_1NEGATE, a direct push or the smallest OP_PUSHDATAX.
+fn is_minimal_push(bit: &ScriptBit) -> bool {
+    match bit {
+        ScriptBit::Push(data) => !matches!(data.as_slice(), [1..=16] | [0x81]),
+        ScriptBit::PushData(code, data) => match code {
+            OpCodes::OP_PUSHDATA1 => data.len() > 0x4b,
+            OpCodes::OP_PUSHDATA2 => data.len() > 0xff,
+            _ => data.len() > 0xffff,
+        },
+        _ => true,
+    }
 }
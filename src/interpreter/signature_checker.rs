@@ -0,0 +1,187 @@
+use std::collections::HashMap;
+
+use crate::{DigestBytes, PublicKey, Script, SigHash, SighashSignature, Signature, ECDSA};
+
+use super::{errors::InterpreterError, flags::ScriptFlags, TxScript};
+
+/// Transaction locktimes below this are block heights, at or above it are unix timestamps.
+const LOCKTIME_THRESHOLD: i64 = 500_000_000;
+/// A TxIn with this sequence number opts out of locktime checks.
+const SEQUENCE_FINAL: u32 = 0xffff_ffff;
+/// Set on a TxIn sequence number to disable its relative locktime.
+pub(crate) const SEQUENCE_LOCKTIME_DISABLE_FLAG: i64 = 1 << 31;
+/// Set on a relative locktime to measure it in units of 512 seconds instead of blocks.
+const SEQUENCE_LOCKTIME_TYPE_FLAG: i64 = 1 << 22;
+const SEQUENCE_LOCKTIME_MASK: i64 = 0x0000_ffff;
+
+/// Checks signatures and locktimes on behalf of the Interpreter, the equivalent of the node's `BaseSignatureChecker`.
+///
+/// Every check fails unless implemented, the Interpreter has already enforced the encoding rules of the script flags.
+pub trait SignatureChecker {
+    /// Checks `signature`, DER encoded with its trailing sighash byte, was made by `public_key` over the sighash of `script_code`.
+    /// `flags` are those of the running script, which decide the sighash algorithm the same way they do for the node.
+    fn check_sig(&self, _signature: &[u8], _public_key: &[u8], _script_code: &Script, _flags: ScriptFlags) -> Result<bool, InterpreterError> {
+        Ok(false)
+    }
+
+    /// Same as `check_sig`, also returning the sighash preimage the signature was checked against so it can be reported to observers.
+    /// Checkers that compute a preimage override this, others report an empty preimage.
+    fn check_sig_with_preimage(&self, signature: &[u8], public_key: &[u8], script_code: &Script, flags: ScriptFlags) -> Result<(bool, Vec<u8>), InterpreterError> {
+        Ok((self.check_sig(signature, public_key, script_code, flags)?, vec![]))
+    }
+
+    /// Checks the spending transaction is locked until at least `lock_time`, for OP_CHECKLOCKTIMEVERIFY.
+    fn check_lock_time(&self, _lock_time: i64) -> bool {
+        false
+    }
+
+    /// Checks the input is locked for at least the relative `sequence`, for OP_CHECKSEQUENCEVERIFY.
+    fn check_sequence(&self, _sequence: i64) -> bool {
+        false
+    }
+}
+
+/// Checks signatures against the sighash of the input being spent, which must have its satoshis set.
+///
+/// The CHRONICLE sighash bit only selects the original algorithm with `ScriptFlags::UTXO_AFTER_CHRONICLE`, before that FORKID signatures use BIP143.
+impl SignatureChecker for TxScript {
+    fn check_sig(&self, signature: &[u8], public_key: &[u8], script_code: &Script, flags: ScriptFlags) -> Result<bool, InterpreterError> {
+        Ok(self.check_sig_with_preimage(signature, public_key, script_code, flags)?.0)
+    }
+
+    fn check_sig_with_preimage(&self, signature: &[u8], public_key: &[u8], script_code: &Script, flags: ScriptFlags) -> Result<(bool, Vec<u8>), InterpreterError> {
+        let sighash = match signature.last() {
+            Some(x) => SigHash::try_from(*x).map_err(|_| InterpreterError::FailedToConvertSighash)?,
+            None => return Ok((false, vec![])),
+        };
+
+        let satoshis = match self.tx.get_input(self.input_index).and_then(|x| x.get_satoshis()) {
+            Some(v) => v,
+            _ => return Err(InterpreterError::InvalidStackOperation("TxIn at given index does not have satoshis provided")),
+        };
+
+        let preimage = self
+            .tx
+            .clone()
+            .sighash_preimage_for_era(self.input_index, sighash, script_code, satoshis, flags.contains(ScriptFlags::UTXO_AFTER_CHRONICLE))
+            .map_err(|e| InterpreterError::SighashPreimageCalculation(e.to_string()))?;
+
+        // Signatures and public keys that cannot be parsed fail verification, encoding rules are enforced by the flags.
+        let (sighash_sig, public_key) = match (SighashSignature::from_bytes_impl(signature, &preimage), PublicKey::from_bytes_impl(public_key)) {
+            (Ok(sig), Ok(pubkey)) => (sig, pubkey),
+            _ => return Ok((false, preimage)),
+        };
+
+        let is_valid = self.tx._verify(&public_key, &sighash_sig, false) | self.tx._verify(&public_key, &sighash_sig, true);
+        Ok((is_valid, preimage))
+    }
+
+    fn check_lock_time(&self, lock_time: i64) -> bool {
+        let tx_lock_time = self.tx.get_n_locktime() as i64;
+
+        // Both must be block heights or both timestamps
+        if (tx_lock_time < LOCKTIME_THRESHOLD) != (lock_time < LOCKTIME_THRESHOLD) {
+            return false;
+        }
+
+        if lock_time > tx_lock_time {
+            return false;
+        }
+
+        // The transaction's locktime is ignored if the input is final
+        match self.tx.get_input(self.input_index) {
+            Some(input) => input.get_sequence() != SEQUENCE_FINAL,
+            None => false,
+        }
+    }
+
+    fn check_sequence(&self, sequence: i64) -> bool {
+        let tx_sequence = match self.tx.get_input(self.input_index) {
+            Some(input) => input.get_sequence() as i64,
+            None => return false,
+        };
+
+        // Relative locktimes are only enforced from version 2 transactions, unless the input disables them
+        if self.tx.get_version() < 2 || tx_sequence & SEQUENCE_LOCKTIME_DISABLE_FLAG != 0 {
+            return false;
+        }
+
+        let mask = SEQUENCE_LOCKTIME_TYPE_FLAG | SEQUENCE_LOCKTIME_MASK;
+        let (tx_sequence, sequence) = (tx_sequence & mask, sequence & mask);
+
+        // Both must be in blocks or both in time
+        if (tx_sequence < SEQUENCE_LOCKTIME_TYPE_FLAG) != (sequence < SEQUENCE_LOCKTIME_TYPE_FLAG) {
+            return false;
+        }
+
+        sequence <= tx_sequence
+    }
+}
+
+/// Checks signatures against sighashes computed elsewhere, one for each sighash type, without needing the transaction.
+///
+/// The sighashes are the double SHA256 of the preimage and are assumed to be for the scriptCode being executed.
+#[derive(Debug, Clone, Default)]
+pub struct PrecomputedSighashChecker {
+    sighashes: HashMap<u8, [u8; 32]>,
+}
+
+impl PrecomputedSighashChecker {
+    pub fn new() -> PrecomputedSighashChecker {
+        PrecomputedSighashChecker::default()
+    }
+
+    /// Sets the sighash that signatures ending in the given sighash type byte are checked against.
+    ///
+    /// The sighash is the double SHA256 of the preimage in the byte order the hash outputs it, eg. `Hash::sha_256d(&preimage).to_bytes()`,
+    /// not reversed the way txids are displayed. Signatures are only checked against it in that order.
+    pub fn add_sighash(&mut self, sighash_type: u8, sighash: [u8; 32]) {
+        self.sighashes.insert(sighash_type, sighash);
+    }
+}
+
+impl SignatureChecker for PrecomputedSighashChecker {
+    fn check_sig(&self, signature: &[u8], public_key: &[u8], _script_code: &Script, _flags: ScriptFlags) -> Result<bool, InterpreterError> {
+        let (sighash, der) = match signature.split_last().and_then(|(x, der)| self.sighashes.get(x).map(|sighash| (sighash, der))) {
+            Some(v) => v,
+            None => return Ok(false),
+        };
+
+        let (signature, public_key) = match (Signature::from_der_impl(der), PublicKey::from_bytes_impl(public_key)) {
+            (Ok(sig), Ok(pubkey)) => (sig, pubkey),
+            _ => return Ok(false),
+        };
+
+        Ok(ECDSA::verify_hashbuf_impl(DigestBytes::from(*sighash), &public_key, &signature).unwrap_or(false))
+    }
+}
+
+/// Gives the same answer to every signature and locktime check, for testing the rest of a script's logic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MockSignatureChecker {
+    is_valid: bool,
+}
+
+impl MockSignatureChecker {
+    pub fn always_valid() -> MockSignatureChecker {
+        MockSignatureChecker { is_valid: true }
+    }
+
+    pub fn always_invalid() -> MockSignatureChecker {
+        MockSignatureChecker { is_valid: false }
+    }
+}
+
+impl SignatureChecker for MockSignatureChecker {
+    fn check_sig(&self, _signature: &[u8], _public_key: &[u8], _script_code: &Script, _flags: ScriptFlags) -> Result<bool, InterpreterError> {
+        Ok(self.is_valid)
+    }
+
+    fn check_lock_time(&self, _lock_time: i64) -> bool {
+        self.is_valid
+    }
+
+    fn check_sequence(&self, _sequence: i64) -> bool {
+        self.is_valid
+    }
+}
@@ -0,0 +1,134 @@
+use crate::SigHash;
+
+use super::{errors::InterpreterError, flags::ScriptFlags};
+
+/// Half of the secp256k1 curve order, the largest S value a low S signature may have.
+const HALF_CURVE_ORDER: [u8; 32] = [
+    0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x5d, 0x57, 0x6e, 0x73, 0x57, 0xa4, 0x50, 0x1d, 0xdf, 0xe9, 0x2f, 0x46, 0x68, 0x1b, 0x20, 0xa0,
+];
+
+/// Checks a signature with its trailing sighash byte is strict DER, as defined in BIP66.
+///
+/// Format: 0x30 [total-length] 0x02 [R-length] [R] 0x02 [S-length] [S] [sighash]
+pub(crate) fn is_valid_signature_encoding(sig: &[u8]) -> bool {
+    if sig.len() < 9 || sig.len() > 73 {
+        return false;
+    }
+
+    if sig[0] != 0x30 || sig[1] as usize != sig.len() - 3 {
+        return false;
+    }
+
+    let len_r = sig[3] as usize;
+    if 5 + len_r >= sig.len() {
+        return false;
+    }
+
+    let len_s = sig[5 + len_r] as usize;
+    if len_r + len_s + 7 != sig.len() {
+        return false;
+    }
+
+    // R must be a positive integer with no unnecessary padding
+    if sig[2] != 0x02 || len_r == 0 || sig[4] & 0x80 != 0 {
+        return false;
+    }
+    if len_r > 1 && sig[4] == 0x00 && sig[5] & 0x80 == 0 {
+        return false;
+    }
+
+    // S must be a positive integer with no unnecessary padding
+    if sig[len_r + 4] != 0x02 || len_s == 0 || sig[len_r + 6] & 0x80 != 0 {
+        return false;
+    }
+    if len_s > 1 && sig[len_r + 6] == 0x00 && sig[len_r + 7] & 0x80 == 0 {
+        return false;
+    }
+
+    true
+}
+
+/// Checks the S value of a strict DER signature is no greater than half the curve order.
+pub(crate) fn is_low_der_signature(sig: &[u8]) -> bool {
+    if !is_valid_signature_encoding(sig) {
+        return false;
+    }
+
+    let len_r = sig[3] as usize;
+    let len_s = sig[5 + len_r] as usize;
+    let s = &sig[len_r + 6..len_r + 6 + len_s];
+    let s = match s.iter().position(|x| *x != 0) {
+        Some(v) => &s[v..],
+        None => return true,
+    };
+
+    match s.len() {
+        0..=31 => true,
+        32 => s <= &HALF_CURVE_ORDER[..],
+        _ => false,
+    }
+}
+
+/// Checks the sighash byte on the end of a signature is one of ALL, NONE or SINGLE, optionally with ANYONECANPAY and FORKID,
+/// and from Chronicle, CHRONICLE.
+pub(crate) fn is_defined_hashtype_signature(sig: &[u8], is_chronicle: bool) -> bool {
+    let mut modifiers = SigHash::ANYONECANPAY as u8 | SigHash::FORKID as u8;
+    if is_chronicle {
+        modifiers |= SigHash::CHRONICLE as u8;
+    }
+
+    let hashtype = match sig.last() {
+        Some(v) => v & !modifiers,
+        None => return false,
+    };
+
+    (SigHash::ALL as u8..=SigHash::SINGLE as u8).contains(&hashtype)
+}
+
+/// Applies the signature encoding rules selected by the flags. Empty signatures are always allowed, they simply fail verification.
+pub(crate) fn check_signature_encoding(sig: &[u8], flags: ScriptFlags) -> Result<(), InterpreterError> {
+    if sig.is_empty() {
+        return Ok(());
+    }
+
+    if flags.intersects(ScriptFlags::DERSIG | ScriptFlags::LOW_S | ScriptFlags::STRICTENC) && !is_valid_signature_encoding(sig) {
+        return Err(InterpreterError::SigDer);
+    }
+
+    if flags.contains(ScriptFlags::LOW_S) && !is_low_der_signature(sig) {
+        return Err(InterpreterError::SigHighS);
+    }
+
+    if flags.contains(ScriptFlags::STRICTENC) {
+        if !is_defined_hashtype_signature(sig, flags.contains(ScriptFlags::UTXO_AFTER_CHRONICLE)) {
+            return Err(InterpreterError::SigHashType);
+        }
+
+        let uses_forkid = sig.last().map(|x| x & SigHash::FORKID as u8 != 0).unwrap_or(false);
+        match (uses_forkid, flags.contains(ScriptFlags::SIGHASH_FORKID)) {
+            (true, false) => return Err(InterpreterError::IllegalForkId),
+            (false, true) => return Err(InterpreterError::MustUseForkId),
+            _ => (),
+        }
+    }
+
+    Ok(())
+}
+
+/// Under STRICTENC public keys must be either 33 byte compressed or 65 byte uncompressed SEC1 points.
+pub(crate) fn check_pubkey_encoding(pubkey: &[u8], flags: ScriptFlags) -> Result<(), InterpreterError> {
+    if !flags.contains(ScriptFlags::STRICTENC) {
+        return Ok(());
+    }
+
+    let is_valid = match pubkey.first() {
+        Some(0x04) => pubkey.len() == 65,
+        Some(0x02 | 0x03) => pubkey.len() == 33,
+        _ => false,
+    };
+
+    match is_valid {
+        true => Ok(()),
+        false => Err(InterpreterError::PubKeyType),
+    }
+}
@@ -0,0 +1,47 @@
+use crate::{OpCodes, Script, ScriptBit, Transaction};
+
+use super::{limits::ScriptLimits, opcode_stream::OpcodeStream};
+
+impl Script {
+    /// Counts the signature operations in the script, whether they are executed or not, the same as the node's `GetSigOpCount`.
+    ///
+    /// OP_CHECKSIG and OP_CHECKSIGVERIFY count as one. OP_CHECKMULTISIG and OP_CHECKMULTISIGVERIFY count as the number of public keys
+    /// when `accurate` is set and it is pushed by the OP_1 - OP_16 immediately before, otherwise as the pre-Genesis maximum of 20.
+    /// Counting stops at a push that runs past the end of the script.
+    pub fn count_sigops(&self, accurate: bool) -> usize {
+        let stream = OpcodeStream::decode(&self.to_bytes());
+        let mut previous = None;
+        let mut count = 0;
+
+        for bit in &stream.bits {
+            let opcode = match bit {
+                ScriptBit::OpCode(code) => Some(*code),
+                _ => None,
+            };
+
+            count += match opcode {
+                Some(OpCodes::OP_CHECKSIG | OpCodes::OP_CHECKSIGVERIFY) => 1,
+                Some(OpCodes::OP_CHECKMULTISIG | OpCodes::OP_CHECKMULTISIGVERIFY) => match previous {
+                    Some(code) if accurate && (OpCodes::OP_1 as u8..=OpCodes::OP_16 as u8).contains(&(code as u8)) => (code as u8 - OpCodes::OP_1 as u8 + 1) as usize,
+                    _ => ScriptLimits::BEFORE_GENESIS.max_pubkeys_per_multisig,
+                },
+                _ => 0,
+            };
+            previous = opcode;
+        }
+
+        count
+    }
+}
+
+impl Transaction {
+    /// Counts the signature operations in the unlocking script of every input and, where it is set, the locking script it spends.
+    ///
+    /// Multisig public keys are counted accurately where the script pushes their number with OP_1 - OP_16, see `Script::count_sigops`.
+    pub fn count_sigops(&self) -> usize {
+        (0..self.get_ninputs())
+            .filter_map(|index| self.get_input(index))
+            .map(|txin| txin.get_unlocking_script().count_sigops(true) + txin.get_locking_script().map(|x| x.count_sigops(true)).unwrap_or_default())
+            .sum()
+    }
+}
@@ -0,0 +1,225 @@
+use std::ops::{Deref, RangeFrom};
+
+use serde::{Deserialize, Serialize};
+
+use super::limits::STACK_ELEMENT_OVERHEAD;
+
+/// The stack or alt stack of a running script.
+///
+/// Keeps a running total of the memory its elements use, counted the same way as `ScriptLimits::max_stack_memory`,
+/// so the limit can be checked after every opcode without walking the whole stack.
+/// Reads go through `Deref`, every change to the elements goes through the methods below.
+///
+/// While a step is in progress, from `checkpoint` until `commit` or `rewind`, its changes are journaled so a step that
+/// fails part way through can be undone without keeping a copy of the whole stack. Removed elements are moved into the
+/// journal, `lend_top` lends them out from there so opcodes can read them without copying.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(from = "Vec<Vec<u8>>", into = "Vec<Vec<u8>>")]
+pub struct Stack {
+    items: Vec<Vec<u8>>,
+    memory: usize,
+    journal: Vec<StackChange>,
+    is_journaling: bool,
+}
+
+/// A change made since the last checkpoint, undone in reverse order by `Stack::rewind`.
+#[derive(Debug, Clone)]
+enum StackChange {
+    Pushed,
+    Removed(usize, Vec<u8>),
+    Inserted(usize),
+    Swapped(usize, usize),
+    Rolled(usize),
+}
+
+fn element_memory(data: &[u8]) -> usize {
+    data.len() + STACK_ELEMENT_OVERHEAD
+}
+
+impl Stack {
+    /// Get the memory used by the elements, their lengths with `STACK_ELEMENT_OVERHEAD` added for each.
+    #[must_use]
+    pub fn memory(&self) -> usize {
+        self.memory
+    }
+
+    pub fn push(&mut self, data: Vec<u8>) {
+        self.memory += element_memory(&data);
+        self.items.push(data);
+        self.record(StackChange::Pushed);
+    }
+
+    /// Removes the top element. While a step is in progress the journal keeps the original, so the element is copied,
+    /// use `lend_top` to read it without copying.
+    pub fn pop(&mut self) -> Option<Vec<u8>> {
+        match self.items.is_empty() {
+            true => None,
+            false => Some(self.remove(self.items.len() - 1)),
+        }
+    }
+
+    /// Removes the element at `index`, counting from the bottom of the stack. Panics if it is out of bounds.
+    /// Copied the same way as `pop` while a step is in progress.
+    pub fn remove(&mut self, index: usize) -> Vec<u8> {
+        let data = self.take(index);
+        if self.is_journaling {
+            self.journal.push(StackChange::Removed(index, data.clone()));
+        }
+        data
+    }
+
+    /// Removes the element at `index` without handing it back, moving it into the journal instead of copying it.
+    /// Panics if it is out of bounds.
+    pub(crate) fn discard(&mut self, index: usize) {
+        let data = self.take(index);
+        self.record(StackChange::Removed(index, data));
+    }
+
+    /// Removes the top `N` elements, lending them out top first. They are moved into the journal rather than copied and
+    /// kept there until the step finishes.
+    pub(crate) fn lend_top<const N: usize>(&mut self) -> Option<[&[u8]; N]> {
+        if self.items.len() < N {
+            return None;
+        }
+
+        for _ in 0..N {
+            let index = self.items.len() - 1;
+            let data = self.take(index);
+            // Journaled even outside a step, the borrows are lent out of the journal
+            self.journal.push(StackChange::Removed(index, data));
+        }
+
+        // Journaled top first
+        let mut removed = self.journal[self.journal.len() - N..].iter().map(|x| match x {
+            StackChange::Removed(_, data) => data.as_slice(),
+            _ => &[],
+        });
+        Some(std::array::from_fn(|_| removed.next().unwrap_or_default()))
+    }
+
+    /// Inserts an element at `index`, counting from the bottom of the stack. Panics if it is out of bounds.
+    pub fn insert(&mut self, index: usize, data: Vec<u8>) {
+        self.memory += element_memory(&data);
+        self.items.insert(index, data);
+        self.record(StackChange::Inserted(index));
+    }
+
+    pub fn swap(&mut self, a: usize, b: usize) {
+        self.items.swap(a, b);
+        self.record(StackChange::Swapped(a, b));
+    }
+
+    /// Moves the element at `index`, counting from the bottom of the stack, to the top. Panics if it is out of bounds.
+    pub fn roll(&mut self, index: usize) {
+        let data = self.items.remove(index);
+        self.items.push(data);
+        self.record(StackChange::Rolled(index));
+    }
+
+    /// Removes every element from `at` to the top, returning them bottom first.
+    pub fn split_off(&mut self, at: usize) -> Vec<Vec<u8>> {
+        let mut removed: Vec<Vec<u8>> = (at..self.items.len()).map(|_| self.pop().unwrap_or_default()).collect();
+        removed.reverse();
+        removed
+    }
+
+    /// Pushes copies of the elements in `src` onto the top, keeping their order.
+    pub fn extend_from_within(&mut self, src: RangeFrom<usize>) {
+        for index in src.start..self.items.len() {
+            self.push(self.items[index].clone());
+        }
+    }
+
+    /// Removes every element, they cannot be brought back by `rewind`.
+    pub fn clear(&mut self) {
+        self.items.clear();
+        self.memory = 0;
+        self.journal.clear();
+    }
+
+    /// Starts journaling the changes made by a step, `rewind` undoes them.
+    pub(crate) fn checkpoint(&mut self) {
+        self.journal.clear();
+        self.is_journaling = true;
+    }
+
+    /// Keeps the changes made by the step, dropping the journal and the elements it removed.
+    pub(crate) fn commit(&mut self) {
+        self.journal.clear();
+        self.is_journaling = false;
+    }
+
+    /// Puts the elements back as they were at the last checkpoint.
+    pub(crate) fn rewind(&mut self) {
+        while let Some(change) = self.journal.pop() {
+            match change {
+                StackChange::Pushed => {
+                    let data = self.items.pop().unwrap_or_default();
+                    self.memory -= element_memory(&data);
+                }
+                StackChange::Removed(index, data) => {
+                    self.memory += element_memory(&data);
+                    self.items.insert(index, data);
+                }
+                StackChange::Inserted(index) => {
+                    let data = self.items.remove(index);
+                    self.memory -= element_memory(&data);
+                }
+                StackChange::Swapped(a, b) => self.items.swap(a, b),
+                StackChange::Rolled(index) => {
+                    let data = self.items.pop().unwrap_or_default();
+                    self.items.insert(index, data);
+                }
+            }
+        }
+        self.is_journaling = false;
+    }
+
+    /// Removes the element at `index` without journaling it.
+    fn take(&mut self, index: usize) -> Vec<u8> {
+        let data = self.items.remove(index);
+        self.memory -= element_memory(&data);
+        data
+    }
+
+    fn record(&mut self, change: StackChange) {
+        if self.is_journaling {
+            self.journal.push(change);
+        }
+    }
+}
+
+/// Stacks are equal when their elements are, whatever has been journaled.
+impl PartialEq for Stack {
+    fn eq(&self, other: &Self) -> bool {
+        self.items == other.items
+    }
+}
+
+impl Eq for Stack {}
+
+impl Deref for Stack {
+    type Target = Vec<Vec<u8>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.items
+    }
+}
+
+impl From<Vec<Vec<u8>>> for Stack {
+    fn from(items: Vec<Vec<u8>>) -> Stack {
+        let memory = items.iter().map(|x| element_memory(x)).sum();
+        Stack {
+            items,
+            memory,
+            journal: vec![],
+            is_journaling: false,
+        }
+    }
+}
+
+impl From<Stack> for Vec<Vec<u8>> {
+    fn from(stack: Stack) -> Vec<Vec<u8>> {
+        stack.items
+    }
+}
@@ -0,0 +1,529 @@
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{OpCodes, Script, ScriptBit, ScriptNum, StackArity};
+
+use super::{opcode_stream::OpcodeStream, ScriptFlags};
+
+/// Paths are merged by forgetting the values of their elements once there are more than this many at one point in the script.
+const MAX_PATHS: usize = 256;
+/// Counts above this taken from the stack by OP_PICK, OP_ROLL and OP_CHECKMULTISIG are treated as unknown.
+const MAX_TRACKED_COUNT: i64 = 1 << 16;
+
+/// What a ScriptBit does to the main stack and the depths the stack can be at once it has been executed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StackStep {
+    pub index: usize,
+    pub byte_offset: usize,
+    /// `None` for direct pushes.
+    pub opcode: Option<OpCodes>,
+    /// `None` if the opcode is invalid or the elements it uses depend on the values on the stack.
+    pub arity: Option<StackArity>,
+    /// Executed by some path through the script.
+    pub reachable: bool,
+    /// Shallowest and deepest the main stack is after the ScriptBit, when the script starts with `required_depth` elements.
+    /// Both are `None` if every path fails or finishes on it, or it is never executed.
+    pub min_depth: Option<usize>,
+    pub max_depth: Option<usize>,
+}
+
+/// Deepest the main stack gets on each side of an OP_IF, OP_NOTIF, OP_VERIF or OP_VERNOTIF, `None` for a side that is never executed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BranchDepth {
+    pub index: usize,
+    pub byte_offset: usize,
+    pub opcode: OpCodes,
+    /// The statements following the conditional.
+    pub then_max_depth: Option<usize>,
+    /// The OP_ELSE statements, or the stack left by the conditional if there is no OP_ELSE.
+    pub else_max_depth: Option<usize>,
+}
+
+/// Something found during analysis that fails the script or makes the analysis approximate, at the index of the ScriptBit it was found at.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StackIssue {
+    /// Fails the script wherever it appears, the rest of the script is not analysed.
+    DisabledOpcode { index: usize, opcode: OpCodes },
+    /// Fails the script when executed.
+    InvalidOpcode { index: usize, opcode: OpCodes },
+    /// OP_PICK, OP_ROLL, OP_CHECKMULTISIG or OP_CHECKMULTISIGVERIFY with a count computed by the script. OP_PICK and OP_ROLL are
+    /// analysed as if the count were zero, OP_CHECKMULTISIG is not analysed further.
+    UnknownStackEffect { index: usize, opcode: OpCodes },
+    /// OP_FROMALTSTACK is executed with nothing on the alt stack.
+    EmptyAltStack { index: usize },
+    /// OP_ELSE or OP_ENDIF without an open conditional, or the script ends with one still open.
+    UnbalancedConditional { index: usize },
+    /// The script ends part way through a push.
+    TruncatedPush { index: usize },
+}
+
+/// The stack effect of a script, worked out from its opcodes without executing it, see `Script::analyze_stack`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StackAnalysis {
+    pub steps: Vec<StackStep>,
+    /// Elements that must be on the stack before the script is executed, ie. pushed by the unlocking script.
+    pub required_depth: usize,
+    /// Deepest the main stack gets on any path through the script.
+    pub max_depth: usize,
+    pub branches: Vec<BranchDepth>,
+    /// Every ScriptBit from this index on is never executed, eg. following an OP_RETURN outside of any conditional.
+    pub unreachable_from: Option<usize>,
+    pub issues: Vec<StackIssue>,
+}
+
+impl Script {
+    /// Works out how each ScriptBit changes the main stack and how deep the stack needs to be and gets, without executing the script.
+    ///
+    /// Every combination of outcomes of the conditionals is followed, except where the condition was pushed as a constant.
+    /// Values computed by the script are unknown, so signature checks, OP_VERIFY and the like are assumed to pass.
+    pub fn analyze_stack(&self, flags: ScriptFlags) -> StackAnalysis {
+        let stream = OpcodeStream::decode(&self.to_bytes());
+        let mut analyzer = StackAnalyzer {
+            flags,
+            paths: vec![PathState::default()],
+            frames: vec![],
+            steps: vec![],
+            branches: vec![],
+            required_depth: 0,
+            issues: vec![],
+        };
+
+        for (index, bit) in stream.bits.iter().enumerate() {
+            if !analyzer.is_live() {
+                break;
+            }
+            analyzer.step(index, bit);
+        }
+
+        if analyzer.is_live() {
+            let index = stream.bits.len();
+            match stream.is_truncated {
+                true => analyzer.issues.push(StackIssue::TruncatedPush { index }),
+                false if !analyzer.frames.is_empty() => analyzer.issues.push(StackIssue::UnbalancedConditional { index }),
+                false => (),
+            }
+        }
+
+        analyzer.finish(&stream)
+    }
+}
+
+/// One way execution can reach a point in the script. Only the top `consumed` elements of the stack the script starts with are
+/// tracked, and the value of an element is only known if it was pushed as a number.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+struct PathState {
+    stack: Vec<Option<i64>>,
+    alt_stack: Vec<Option<i64>>,
+    consumed: usize,
+}
+
+impl PathState {
+    /// Tracks at least `count` elements, taking any missing from the stack the script starts with.
+    fn ensure(&mut self, count: usize) {
+        if count > self.stack.len() {
+            let missing = count - self.stack.len();
+            self.stack.splice(0..0, std::iter::repeat_n(None, missing));
+            self.consumed += missing;
+        }
+    }
+
+    fn pop(&mut self) -> Option<i64> {
+        self.ensure(1);
+        self.stack.pop().flatten()
+    }
+
+    /// Depth of the main stack less the depth the script started with.
+    fn relative_depth(&self) -> isize {
+        self.stack.len() as isize - self.consumed as isize
+    }
+}
+
+/// An open conditional.
+struct Frame {
+    /// Paths waiting to execute the other side of the conditional.
+    paused: Vec<PathState>,
+    has_else: bool,
+    is_else: bool,
+    branch: usize,
+}
+
+/// Depths are kept relative to the depth the script starts with until the required depth is known.
+struct StackAnalyzer {
+    flags: ScriptFlags,
+    /// Paths executing the current ScriptBit.
+    paths: Vec<PathState>,
+    frames: Vec<Frame>,
+    /// Whether each ScriptBit was executed and the range of depths relative to the start afterwards.
+    steps: Vec<(bool, Option<(isize, isize)>)>,
+    branches: Vec<(usize, OpCodes, [Option<isize>; 2])>,
+    required_depth: usize,
+    issues: Vec<StackIssue>,
+}
+
+impl StackAnalyzer {
+    /// Some path has yet to finish or fail, so the rest of the script is still read.
+    fn is_live(&self) -> bool {
+        !self.paths.is_empty() || self.frames.iter().any(|x| !x.paused.is_empty())
+    }
+
+    fn step(&mut self, index: usize, bit: &ScriptBit) {
+        let is_chronicle = self.flags.contains(ScriptFlags::UTXO_AFTER_CHRONICLE);
+        // Conditionals are read by every path still running, even those not executing the side they are on
+        let mut is_reached = true;
+        let is_conditional = match bit {
+            ScriptBit::OpCode(code) if code.is_disabled(self.flags) => {
+                self.fail_all(StackIssue::DisabledOpcode { index, opcode: *code });
+                false
+            }
+            ScriptBit::OpCode(code @ (OpCodes::OP_IF | OpCodes::OP_NOTIF)) => {
+                self.open_branch(index, *code);
+                true
+            }
+            ScriptBit::OpCode(code @ (OpCodes::OP_VERIF | OpCodes::OP_VERNOTIF)) if is_chronicle => {
+                self.open_branch(index, *code);
+                true
+            }
+            ScriptBit::OpCode(OpCodes::OP_ELSE) => {
+                let is_after_genesis = self.flags.contains(ScriptFlags::UTXO_AFTER_GENESIS);
+                match self.frames.last_mut() {
+                    // Any number of OP_ELSE may toggle the branch before Genesis, only one is allowed after
+                    Some(frame) if !(frame.has_else && is_after_genesis) => {
+                        std::mem::swap(&mut self.paths, &mut frame.paused);
+                        frame.has_else = true;
+                        frame.is_else = !frame.is_else;
+                    }
+                    _ => self.fail_all(StackIssue::UnbalancedConditional { index }),
+                }
+                true
+            }
+            ScriptBit::OpCode(OpCodes::OP_ENDIF) => {
+                match self.frames.pop() {
+                    Some(frame) => self.paths.extend(frame.paused),
+                    None => self.fail_all(StackIssue::UnbalancedConditional { index }),
+                }
+                dedup(&mut self.paths);
+                false
+            }
+            _ => {
+                is_reached = !self.paths.is_empty();
+                let mut paths = vec![];
+                for path in std::mem::take(&mut self.paths) {
+                    self.execute(index, bit, path, &mut paths);
+                }
+                dedup(&mut paths);
+                self.paths = paths;
+                false
+            }
+        };
+
+        self.record(is_reached, is_conditional);
+    }
+
+    /// Every path fails, eg. on a disabled opcode.
+    fn fail_all(&mut self, issue: StackIssue) {
+        self.issues.push(issue);
+        self.paths.clear();
+        self.frames.clear();
+    }
+
+    fn open_branch(&mut self, index: usize, code: OpCodes) {
+        let (mut then_paths, mut else_paths) = (vec![], vec![]);
+        for mut path in std::mem::take(&mut self.paths) {
+            let condition = path.pop();
+            // OP_VERIF and OP_VERNOTIF compare against the transaction version, which is never known
+            let is_taken = match code {
+                OpCodes::OP_IF => condition.map(|x| x != 0),
+                OpCodes::OP_NOTIF => condition.map(|x| x == 0),
+                _ => None,
+            };
+
+            match is_taken {
+                Some(true) => then_paths.push(path),
+                Some(false) => else_paths.push(path),
+                None => {
+                    then_paths.push(path.clone());
+                    else_paths.push(path);
+                }
+            }
+        }
+        dedup(&mut then_paths);
+        dedup(&mut else_paths);
+
+        self.branches.push((index, code, [max_relative_depth(&then_paths), max_relative_depth(&else_paths)]));
+        self.frames.push(Frame {
+            paused: else_paths,
+            has_else: false,
+            is_else: false,
+            branch: self.branches.len() - 1,
+        });
+        self.paths = then_paths;
+    }
+
+    /// Executes a ScriptBit other than a conditional on one path, adding the paths that do not fail to `out`.
+    fn execute(&mut self, index: usize, bit: &ScriptBit, mut path: PathState, out: &mut Vec<PathState>) {
+        let opcode = match bit {
+            ScriptBit::Push(data) | ScriptBit::PushData(_, data) => {
+                path.stack.push(to_number(data));
+                out.push(path);
+                return;
+            }
+            ScriptBit::OpCode(code) => *code,
+            // Never decoded from a serialised script
+            ScriptBit::If { .. } | ScriptBit::Coinbase(_) | ScriptBit::Unparsed(_) => return,
+        };
+
+        let small_number = OpCodes::OP_1 as u8..=OpCodes::OP_16 as u8;
+        match opcode {
+            // The path finishes, successfully after Genesis
+            OpCodes::OP_RETURN => return,
+            _ if opcode.is_invalid(self.flags) => {
+                self.issues.push(StackIssue::InvalidOpcode { index, opcode });
+                return;
+            }
+            OpCodes::OP_0 => path.stack.push(Some(0)),
+            OpCodes::OP_1NEGATE => path.stack.push(Some(-1)),
+            _ if small_number.contains(&(opcode as u8)) => path.stack.push(Some((opcode as u8 - OpCodes::OP_1 as u8 + 1) as i64)),
+            OpCodes::OP_VERIFY => {
+                if path.pop() == Some(0) {
+                    return;
+                }
+            }
+            OpCodes::OP_TOALTSTACK => {
+                let value = path.pop();
+                path.alt_stack.push(value);
+            }
+            OpCodes::OP_FROMALTSTACK => match path.alt_stack.pop() {
+                Some(value) => path.stack.push(value),
+                None => {
+                    self.issues.push(StackIssue::EmptyAltStack { index });
+                    return;
+                }
+            },
+            OpCodes::OP_IFDUP => {
+                path.ensure(1);
+                let top = path.stack[path.stack.len() - 1];
+                match top {
+                    Some(0) => (),
+                    Some(_) => path.stack.push(top),
+                    // Follow both outcomes
+                    None => {
+                        out.push(path.clone());
+                        path.stack.push(None);
+                    }
+                }
+            }
+            OpCodes::OP_PICK | OpCodes::OP_ROLL => {
+                let n = match self.pop_count(index, opcode, &mut path) {
+                    Ok(Some(n)) => n,
+                    Ok(None) => 0,
+                    Err(()) => return,
+                };
+
+                path.ensure(n + 1);
+                let position = path.stack.len() - 1 - n;
+                let value = match opcode {
+                    OpCodes::OP_ROLL => path.stack.remove(position),
+                    _ => path.stack[position],
+                };
+                path.stack.push(value);
+            }
+            OpCodes::OP_CHECKMULTISIG | OpCodes::OP_CHECKMULTISIGVERIFY => {
+                let keys = match self.pop_count(index, opcode, &mut path) {
+                    Ok(Some(n)) => n,
+                    _ => return,
+                };
+                path.ensure(keys);
+                path.stack.truncate(path.stack.len() - keys);
+
+                let signatures = match self.pop_count(index, opcode, &mut path) {
+                    Ok(Some(n)) if n <= keys => n,
+                    _ => return,
+                };
+                // Plus the extra element popped by the off by one bug
+                path.ensure(signatures + 1);
+                path.stack.truncate(path.stack.len() - signatures - 1);
+
+                if opcode == OpCodes::OP_CHECKMULTISIG {
+                    path.stack.push(None);
+                }
+            }
+            _ => {
+                let arity = match opcode.stack_arity(self.flags) {
+                    Some(v) => v,
+                    None => return,
+                };
+
+                path.ensure(arity.inputs);
+                let inputs = path.stack.split_off(path.stack.len() - arity.inputs);
+                match rearrangement(opcode, arity) {
+                    Some(order) => path.stack.extend(order.iter().map(|x| x.and_then(|i| inputs[i]))),
+                    None => path.stack.extend(std::iter::repeat_n(None, arity.outputs)),
+                }
+            }
+        }
+
+        out.push(path);
+    }
+
+    /// Pops a count for OP_PICK, OP_ROLL or OP_CHECKMULTISIG. `None` if it was computed by the script, `Err` if it fails the script.
+    fn pop_count(&mut self, index: usize, opcode: OpCodes, path: &mut PathState) -> Result<Option<usize>, ()> {
+        match path.pop() {
+            Some(n) if n < 0 => Err(()),
+            Some(n) if n <= MAX_TRACKED_COUNT => Ok(Some(n as usize)),
+            _ => {
+                self.issues.push(StackIssue::UnknownStackEffect { index, opcode });
+                Ok(None)
+            }
+        }
+    }
+
+    /// Records the depths after the current ScriptBit, including paths waiting on the other side of a conditional it opened or toggled.
+    fn record(&mut self, is_reached: bool, is_conditional: bool) {
+        self.required_depth = self
+            .paths
+            .iter()
+            .chain(self.frames.iter().flat_map(|x| x.paused.iter()))
+            .map(|x| x.consumed)
+            .fold(self.required_depth, usize::max);
+
+        let executing = max_relative_depth(&self.paths);
+        let waiting = match (is_conditional, self.frames.last()) {
+            (true, Some(frame)) => frame.paused.as_slice(),
+            _ => &[],
+        };
+
+        let present = self.paths.iter().chain(waiting.iter()).map(|x| x.relative_depth());
+        let range = present.fold(None, |range: Option<(isize, isize)>, depth| match range {
+            Some((min, max)) => Some((min.min(depth), max.max(depth))),
+            None => Some((depth, depth)),
+        });
+        self.steps.push((is_reached, range));
+
+        // The conditional just opened or toggled only counts the paths executing its current side
+        let top = self.frames.len();
+        for (i, frame) in self.frames.iter().enumerate() {
+            let depth = match is_conditional && i + 1 == top {
+                true => executing,
+                false => range.map(|x| x.1),
+            };
+            let side = &mut self.branches[frame.branch].2[frame.is_else as usize];
+            *side = max_option(*side, depth);
+        }
+    }
+
+    fn finish(self, stream: &OpcodeStream) -> StackAnalysis {
+        let required = self.required_depth as isize;
+        let absolute = |depth: isize| (required + depth) as usize;
+
+        let steps: Vec<StackStep> = stream
+            .bits
+            .iter()
+            .zip(stream.offsets.iter())
+            .enumerate()
+            .map(|(index, (bit, byte_offset))| {
+                let (opcode, arity) = match bit {
+                    ScriptBit::Push(_) => (None, Some(StackArity { inputs: 0, outputs: 1 })),
+                    ScriptBit::OpCode(code) | ScriptBit::PushData(code, _) | ScriptBit::If { code, .. } => (Some(*code), code.stack_arity(self.flags)),
+                    ScriptBit::Coinbase(_) | ScriptBit::Unparsed(_) => (None, None),
+                };
+                let (reachable, depth) = self.steps.get(index).copied().unwrap_or_default();
+
+                StackStep {
+                    index,
+                    byte_offset: *byte_offset,
+                    opcode,
+                    arity,
+                    reachable,
+                    min_depth: depth.map(|x| absolute(x.0)),
+                    max_depth: depth.map(|x| absolute(x.1)),
+                }
+            })
+            .collect();
+
+        let max_depth = steps.iter().filter_map(|x| x.max_depth).fold(self.required_depth, usize::max);
+        let unreachable_from = match steps.iter().rposition(|x| x.reachable) {
+            Some(last) if last + 1 < steps.len() => Some(last + 1),
+            Some(_) => None,
+            None if steps.is_empty() => None,
+            None => Some(0),
+        };
+
+        let branches = self
+            .branches
+            .iter()
+            .map(|(index, opcode, [then_depth, else_depth])| BranchDepth {
+                index: *index,
+                byte_offset: stream.offsets[*index],
+                opcode: *opcode,
+                then_max_depth: then_depth.map(absolute),
+                else_max_depth: else_depth.map(absolute),
+            })
+            .collect();
+
+        StackAnalysis {
+            steps,
+            required_depth: self.required_depth,
+            max_depth,
+            branches,
+            unreachable_from,
+            issues: self.issues,
+        }
+    }
+}
+
+/// Where each output of an opcode that only rearranges the stack comes from, as indexes into its inputs from the deepest.
+/// `None` outputs are computed by the opcode. Opcodes that compute every output return `None`.
+fn rearrangement(opcode: OpCodes, arity: StackArity) -> Option<&'static [Option<usize>]> {
+    let order: &'static [Option<usize>] = match opcode {
+        OpCodes::OP_DUP => &[Some(0), Some(0)],
+        OpCodes::OP_SIZE => &[Some(0), None],
+        OpCodes::OP_NIP => &[Some(1)],
+        OpCodes::OP_OVER => &[Some(0), Some(1), Some(0)],
+        OpCodes::OP_TUCK => &[Some(1), Some(0), Some(1)],
+        OpCodes::OP_ROT => &[Some(1), Some(2), Some(0)],
+        OpCodes::OP_SWAP => &[Some(1), Some(0)],
+        OpCodes::OP_2DUP => &[Some(0), Some(1), Some(0), Some(1)],
+        OpCodes::OP_3DUP => &[Some(0), Some(1), Some(2), Some(0), Some(1), Some(2)],
+        OpCodes::OP_2OVER => &[Some(0), Some(1), Some(2), Some(3), Some(0), Some(1)],
+        OpCodes::OP_2ROT => &[Some(2), Some(3), Some(4), Some(5), Some(0), Some(1)],
+        OpCodes::OP_2SWAP => &[Some(2), Some(3), Some(0), Some(1)],
+        // The locktime is left on the stack when checked
+        OpCodes::OP_CHECKLOCKTIMEVERIFY | OpCodes::OP_CHECKSEQUENCEVERIFY if arity.inputs == 1 => &[Some(0)],
+        _ => return None,
+    };
+
+    Some(order)
+}
+
+/// The value of a push used as a number, if it fits.
+fn to_number(data: &[u8]) -> Option<i64> {
+    match data.len() {
+        0..=8 => ScriptNum::from_bytes_lenient(data).to_i64(),
+        _ => None,
+    }
+}
+
+fn dedup(paths: &mut Vec<PathState>) {
+    let mut seen = HashSet::new();
+    paths.retain(|x| seen.insert(x.clone()));
+
+    if paths.len() > MAX_PATHS {
+        for path in paths.iter_mut() {
+            path.stack.iter_mut().chain(path.alt_stack.iter_mut()).for_each(|x| *x = None);
+        }
+        let mut seen = HashSet::new();
+        paths.retain(|x| seen.insert(x.clone()));
+    }
+}
+
+fn max_relative_depth(paths: &[PathState]) -> Option<isize> {
+    paths.iter().map(|x| x.relative_depth()).max()
+}
+
+fn max_option(a: Option<isize>, b: Option<isize>) -> Option<isize> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.max(b)),
+        (a, b) => a.or(b),
+    }
+}
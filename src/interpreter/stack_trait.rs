@@ -1,6 +1,9 @@
-use num_bigint::{BigInt, Sign};
+use num_bigint::BigInt;
+use num_traits::ToPrimitive;
 
-use super::errors::InterpreterError;
+use crate::ScriptNum;
+
+use super::{errors::InterpreterError, stack::Stack};
 
 pub trait ScriptStack {
     fn push_bytes(&mut self, data: Vec<u8>);
@@ -9,28 +12,22 @@ pub trait ScriptStack {
     fn push_bool(&mut self, boolean: bool) -> Result<(), InterpreterError>;
 
     fn pop_bytes(&mut self) -> Result<Vec<u8>, InterpreterError>;
-    fn pop_number(&mut self) -> Result<i32, InterpreterError>;
-    fn pop_bigint(&mut self) -> Result<BigInt, InterpreterError>;
+    fn pop_refs<const N: usize>(&mut self) -> Result<[&[u8]; N], InterpreterError>;
+    fn pop_bigint(&mut self, require_minimal: bool, max_length: usize) -> Result<BigInt, InterpreterError>;
+    fn peek_bigint(&self, require_minimal: bool, max_length: usize) -> Result<BigInt, InterpreterError>;
+    fn pop_index(&mut self, require_minimal: bool, max_length: usize) -> Result<usize, InterpreterError>;
     fn pop_bool(&mut self) -> Result<bool, InterpreterError>;
 }
 
-pub fn to_bigint(data: &[u8]) -> Result<BigInt, InterpreterError> {
-    let mut data = data.to_vec();
-    let length = data.len();
-
-    if length == 0 {
-        return Ok(BigInt::from(0_usize));
+/// Any data of any length is true unless it is all zero bytes, or negative zero (all zero bytes with the sign bit set on the last).
+pub fn cast_to_bool(data: &[u8]) -> bool {
+    match data.split_last() {
+        None => false,
+        Some((last, rest)) => rest.iter().any(|x| *x != 0) || (*last != 0 && *last != 0x80),
     }
-
-    let mut sign = Sign::Plus;
-    if data[length - 1] & 0x80 == 0x80 {
-        sign = Sign::Minus;
-    }
-    data[length - 1] &= !0x80;
-    Ok(BigInt::from_bytes_le(sign, &data))
 }
 
-impl ScriptStack for Vec<Vec<u8>> {
+impl ScriptStack for Stack {
     fn push_bytes(&mut self, data: Vec<u8>) {
         self.push(data)
     }
@@ -39,103 +36,53 @@ impl ScriptStack for Vec<Vec<u8>> {
         self.pop().ok_or(InterpreterError::EmptyStack)
     }
 
-    fn push_number(&mut self, val: i64) -> Result<(), InterpreterError> {
-        // Range: [-2^31+1, 2^31-1]
-        if val > i32::MAX as i64 || val < i32::MIN as i64 {
-            return Err(InterpreterError::NumberOutOfRange);
-        }
-        let (posval, negmask) = if val < 0 { (-val, 128) } else { (val, 0) };
-        if posval == 0 {
-            self.push(vec![])
-        } else if posval < 128 {
-            self.push(vec![(posval as u8) | negmask])
-        } else if posval < 32768 {
-            self.push(vec![posval as u8, ((posval >> 8) as u8) | negmask])
-        } else if posval < 8388608 {
-            self.push(vec![posval as u8, (posval >> 8) as u8, ((posval >> 16) as u8) | negmask])
-        } else {
-            self.push(vec![posval as u8, (posval >> 8) as u8, (posval >> 16) as u8, ((posval >> 24) as u8) | negmask])
-        }
+    /// Pops the top `N` elements top first, borrowing them instead of copying them, see `Stack::lend_top`.
+    fn pop_refs<const N: usize>(&mut self) -> Result<[&[u8]; N], InterpreterError> {
+        self.lend_top().ok_or(InterpreterError::EmptyStack)
+    }
 
-        Ok(())
+    fn push_number(&mut self, val: i64) -> Result<(), InterpreterError> {
+        self.push_bigint(BigInt::from(val))
     }
 
     fn push_bigint(&mut self, bigint: BigInt) -> Result<(), InterpreterError> {
-        let (sign, mut bytes) = bigint.to_bytes_le();
-        if bytes[bytes.len() - 1] & 0x80 == 0x80 {
-            bytes.push(match sign {
-                Sign::Plus | Sign::NoSign => 0x00,
-                Sign::Minus => 0x80,
-            });
-        } else if sign == Sign::Minus {
-            let len = bytes.len();
-            bytes[len - 1] |= 0x80;
-        }
-        if bytes.len() == 1 && bytes[0] == 0 {
-            self.push(vec![]);
-        } else {
-            self.push(bytes);
-        }
-
+        self.push(ScriptNum::from(bigint).to_bytes());
         Ok(())
     }
 
-    fn pop_bigint(&mut self) -> Result<BigInt, InterpreterError> {
-        let data = self.pop().ok_or(InterpreterError::EmptyStack)?;
-        to_bigint(&data)
+    /// Numbers may be longer than `max_length` on the stack, eg. as the result of an operation, but cannot be popped as a number.
+    fn pop_bigint(&mut self, require_minimal: bool, max_length: usize) -> Result<BigInt, InterpreterError> {
+        let number = self.peek_bigint(require_minimal, max_length)?;
+        self.discard(self.len() - 1);
+        Ok(number)
     }
 
-    fn pop_bool(&mut self) -> Result<bool, InterpreterError> {
-        let data = self.pop().ok_or(InterpreterError::EmptyStack)?;
+    /// Reads the number on top of the stack without removing it, with the same checks as `pop_bigint`.
+    fn peek_bigint(&self, require_minimal: bool, max_length: usize) -> Result<BigInt, InterpreterError> {
+        let data = self.last().ok_or(InterpreterError::EmptyStack)?;
+        Ok(ScriptNum::from_bytes(data, require_minimal, max_length)?.into())
+    }
 
-        if data.len() > 4 {
-            return Err(InterpreterError::TooLongForBool);
-        }
+    /// Pops a number used as a stack index, position or count, which may not be negative.
+    fn pop_index(&mut self, require_minimal: bool, max_length: usize) -> Result<usize, InterpreterError> {
+        self.pop_bigint(require_minimal, max_length)?
+            .to_usize()
+            .ok_or(InterpreterError::InvalidStackOperation("Index must be a positive number"))
+    }
 
-        Ok(BigInt::from_signed_bytes_le(&data) >= BigInt::from_slice(num_bigint::Sign::Plus, &[1]))
+    fn pop_bool(&mut self) -> Result<bool, InterpreterError> {
+        let [data] = self.pop_refs()?;
+        Ok(cast_to_bool(data))
     }
 
+    /// Pushes `0x01` for true and an empty array for false, the minimal encodings of 1 and 0.
     fn push_bool(&mut self, boolean: bool) -> Result<(), InterpreterError> {
         let data = match boolean {
             true => vec![1],
-            false => vec![0],
+            false => vec![],
         };
 
         self.push(data);
         Ok(())
     }
-
-    fn pop_number(&mut self) -> Result<i32, InterpreterError> {
-        let bytes = self.pop_bytes()?;
-        // Numbers cannot be popped having more than 4 bytes, but may overflow on the stack to 5 bytes
-        // after certain operations and may be used as byte vectors.
-        if bytes.len() > 4 {
-            // let msg = format!("Cannot pop num, len too long {}", top.len());
-            return Err(InterpreterError::NumberOutOfRange);
-        }
-
-        let mut val = match bytes.len() {
-            0 => return Ok(0),
-            1 => (bytes[0] & 127) as i64,
-            2 => (((bytes[1] & 127) as i64) << 8) + (bytes[0] as i64),
-            3 => (((bytes[2] & 127) as i64) << 16) + ((bytes[1] as i64) << 8) + (bytes[0] as i64),
-            4 => (((bytes[3] & 127) as i64) << 24) + ((bytes[2] as i64) << 16) + ((bytes[1] as i64) << 8) + (bytes[0] as i64),
-            _ => {
-                for byte in &bytes {
-                    if byte != &0 {
-                        return Err(InterpreterError::NumberOutOfRange);
-                    }
-                }
-                if bytes[bytes.len() - 1] & 127 != 0 {
-                    return Err(InterpreterError::NumberOutOfRange);
-                }
-                ((bytes[3] as i64) << 24) + ((bytes[2] as i64) << 16) + ((bytes[1] as i64) << 8) + (bytes[0] as i64)
-            }
-        };
-        if bytes[bytes.len() - 1] & 128 != 0 {
-            val = 0 - val;
-        }
-
-        Ok(val as i32)
-    }
 }
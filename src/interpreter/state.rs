@@ -5,11 +5,14 @@ use serde::{Deserialize, Serialize};
 
 use crate::Status;
 
+use super::stack::Stack;
+
 #[derive(Default, Clone, Serialize, Deserialize)]
 pub struct State {
-    pub stack: Vec<Vec<u8>>,
-    pub alt_stack: Vec<Vec<u8>>,
+    pub stack: Stack,
+    pub alt_stack: Stack,
     pub status: Status,
+    /// Only recorded once enabled with `Interpreter::set_record_executed_opcodes`.
     pub executed_opcodes: Vec<OpCodes>,
     pub codeseparator_offset: usize,
 }
@@ -18,7 +21,7 @@ impl State {
     /// Get a reference to the state's stack.
     #[must_use]
     pub fn stack(&self) -> &[Vec<u8>] {
-        self.stack.as_ref()
+        &self.stack
     }
 }
 
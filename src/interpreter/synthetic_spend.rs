@@ -0,0 +1,108 @@
+use crate::{BSVErrors, PrivateKey, Script, SigHash, SighashSignature, Transaction, TxIn, TxOut};
+
+use super::{errors::InterpreterError, flags::ScriptFlags, verdict::ScriptVerdict, Interpreter};
+
+/// Spends a locking script from a fake funding transaction, so locking scripts can be tested without a real UTXO.
+///
+/// The spending transaction has a single input with its locking script and satoshis set, ready for `Transaction::sign`,
+/// and a single output returning the satoshis to an empty script.
+#[derive(Debug, Clone)]
+pub struct SyntheticSpend {
+    funding_tx: Transaction,
+    tx: Transaction,
+    locking_script: Script,
+    satoshis: u64,
+}
+
+impl SyntheticSpend {
+    /// Index of the input spending the locking script.
+    pub const INPUT_INDEX: usize = 0;
+
+    pub fn new(locking_script: &Script, satoshis: u64) -> Result<SyntheticSpend, BSVErrors> {
+        let mut funding_tx = Transaction::new(1, 0);
+        funding_tx.add_input(&TxIn::new(&[0; 32], u32::MAX, &Script::default(), None));
+        funding_tx.add_output(&TxOut::new(satoshis, locking_script));
+
+        let mut txin = TxIn::new(&funding_tx.get_id_bytes()?, 0, &Script::default(), None);
+        txin.set_locking_script(locking_script);
+        txin.set_satoshis(satoshis);
+
+        let mut tx = Transaction::new(2, 0);
+        tx.add_input(&txin);
+        tx.add_output(&TxOut::new(satoshis, &Script::default()));
+
+        Ok(SyntheticSpend {
+            funding_tx,
+            tx,
+            locking_script: locking_script.clone(),
+            satoshis,
+        })
+    }
+
+    /// Get the transaction creating the output being spent.
+    pub fn funding_tx(&self) -> &Transaction {
+        &self.funding_tx
+    }
+
+    /// Get the spending transaction.
+    pub fn tx(&self) -> &Transaction {
+        &self.tx
+    }
+
+    /// Get the spending transaction, to change its locktime, sequence numbers or outputs before signing.
+    pub fn tx_mut(&mut self) -> &mut Transaction {
+        &mut self.tx
+    }
+
+    pub fn locking_script(&self) -> &Script {
+        &self.locking_script
+    }
+
+    pub fn satoshis(&self) -> u64 {
+        self.satoshis
+    }
+
+    /// Signs the spending input with `Transaction::sign`, using the whole locking script as the scriptCode.
+    pub fn sign(&self, private_key: &PrivateKey, sighash: SigHash) -> Result<SighashSignature, BSVErrors> {
+        self.tx.clone().sign(private_key, sighash, SyntheticSpend::INPUT_INDEX, &self.locking_script, self.satoshis)
+    }
+
+    pub fn set_unlocking_script(&mut self, unlocking_script: &Script) {
+        if let Some(mut txin) = self.tx.get_input(SyntheticSpend::INPUT_INDEX) {
+            txin.set_unlocking_script(unlocking_script);
+            self.tx.set_input(SyntheticSpend::INPUT_INDEX, &txin);
+        }
+    }
+
+    /// Sets the unlocking script returned by `unlock`, which is given the spend so it can sign it.
+    pub fn unlock<F>(&mut self, unlock: F) -> Result<(), BSVErrors>
+    where
+        F: FnOnce(&SyntheticSpend) -> Result<Script, BSVErrors>,
+    {
+        let unlocking_script = unlock(self)?;
+        self.set_unlocking_script(&unlocking_script);
+        Ok(())
+    }
+
+    /// Creates an Interpreter for the spending input, to step through it or inspect the stack.
+    pub fn interpreter(&self, flags: ScriptFlags) -> Result<Interpreter, InterpreterError> {
+        Interpreter::from_transaction(&self.tx, SyntheticSpend::INPUT_INDEX, flags)
+    }
+
+    /// Verifies the spending input with the unlocking script that has been set.
+    pub fn verify(&self, flags: ScriptFlags) -> ScriptVerdict {
+        self.tx.verify_input(SyntheticSpend::INPUT_INDEX, flags)
+    }
+
+    /// Spends `locking_script` with the unlocking script built by `unlock` and verifies it, in one go.
+    pub fn verify_unlock<F>(locking_script: &Script, satoshis: u64, flags: ScriptFlags, unlock: F) -> ScriptVerdict
+    where
+        F: FnOnce(&SyntheticSpend) -> Result<Script, BSVErrors>,
+    {
+        let spend = SyntheticSpend::new(locking_script, satoshis).and_then(|mut spend| spend.unlock(unlock).map(|_| spend));
+        match spend {
+            Ok(spend) => spend.verify(flags),
+            Err(e) => ScriptVerdict::Failure(e.into()),
+        }
+    }
+}
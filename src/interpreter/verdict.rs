@@ -0,0 +1,80 @@
+use crate::Transaction;
+
+use super::{errors::InterpreterError, flags::ScriptFlags, stack_trait::cast_to_bool, Interpreter};
+
+/// Final result of verifying an input, using the same success criteria as the node.
+#[derive(Debug)]
+pub enum ScriptVerdict {
+    /// Both scripts ran to the end and left a true value on top of the stack.
+    Success,
+    /// A post-Genesis OP_RETURN finished execution early and left a true value on top of the stack.
+    EarlyReturn,
+    /// The input is a coinbase, which spends nothing and is not executed, with the block height from its script if it has one.
+    Coinbase { height: Option<u32> },
+    /// The scripts could not be executed, failed part way through, or finished without a true value on top of the stack.
+    Failure(InterpreterError),
+}
+
+impl ScriptVerdict {
+    pub fn is_success(&self) -> bool {
+        matches!(self, ScriptVerdict::Success | ScriptVerdict::EarlyReturn | ScriptVerdict::Coinbase { .. })
+    }
+
+    /// Get the reason verification failed, if it did.
+    pub fn error(&self) -> Option<&InterpreterError> {
+        match self {
+            ScriptVerdict::Failure(e) => Some(e),
+            _ => None,
+        }
+    }
+
+    pub fn into_result(self) -> Result<(), InterpreterError> {
+        match self {
+            ScriptVerdict::Failure(e) => Err(e),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl From<Result<ScriptVerdict, InterpreterError>> for ScriptVerdict {
+    fn from(v: Result<ScriptVerdict, InterpreterError>) -> Self {
+        v.unwrap_or_else(ScriptVerdict::Failure)
+    }
+}
+
+impl Interpreter {
+    pub(crate) fn verify_impl(&mut self) -> Result<ScriptVerdict, InterpreterError> {
+        if self.is_coinbase {
+            return Ok(ScriptVerdict::Coinbase { height: self.coinbase_height });
+        }
+
+        self.execute()?;
+
+        match self.state.stack.last() {
+            Some(top) if cast_to_bool(top) => (),
+            _ => return Err(self.record_failure(InterpreterError::EvalFalse)),
+        }
+
+        if self.flags.contains(ScriptFlags::CLEANSTACK) && self.state.stack.len() != 1 {
+            return Err(self.record_failure(InterpreterError::CleanStack));
+        }
+
+        match self.returned_early {
+            true => Ok(ScriptVerdict::EarlyReturn),
+            false => Ok(ScriptVerdict::Success),
+        }
+    }
+
+    /// Runs the scripts to completion and checks they succeeded, ie. finished with a true value on top of the stack,
+    /// and exactly one element on the stack if `ScriptFlags::CLEANSTACK` is set.
+    pub fn verify(&mut self) -> ScriptVerdict {
+        self.verify_impl().into()
+    }
+}
+
+impl Transaction {
+    /// Verifies the unlocking script of the given input against the locking script set on the TxIn.
+    pub fn verify_input(&self, index: usize, flags: ScriptFlags) -> ScriptVerdict {
+        Interpreter::from_transaction(self, index, flags).and_then(|mut x| x.verify_impl()).into()
+    }
+}
@@ -1,15 +1,10 @@
 use crate::{Hash, OpCodes::OP_0};
 
-use std::{
-    io::{Cursor, Read},
-    slice::Iter,
-    str::FromStr,
-    usize,
-};
+use std::{slice::Iter, str::FromStr, usize};
 
 use crate::{BSVErrors, VarInt};
-use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
-use num_traits::{FromPrimitive, ToPrimitive};
+use byteorder::{LittleEndian, WriteBytesExt};
+use num_traits::ToPrimitive;
 
 use serde::{Deserialize, Serialize};
 
@@ -19,6 +14,18 @@ pub use op_codes::*;
 pub mod script_bit;
 pub use script_bit::*;
 
+mod script_builder;
+pub use script_builder::*;
+
+mod script_class;
+pub use script_class::*;
+
+mod script_num;
+pub use script_num::*;
+
+mod script_parser;
+pub use script_parser::*;
+
 mod script_template;
 pub use script_template::*;
 
@@ -68,7 +75,7 @@ impl Script {
 
                     string_parts.join(" ")
                 }
-                ScriptBit::Coinbase(bytes) => hex::encode(bytes),
+                ScriptBit::Coinbase(bytes) | ScriptBit::Unparsed(bytes) => hex::encode(bytes),
             })
             .collect::<Vec<String>>()
             .join(" ")
@@ -109,7 +116,7 @@ impl Script {
 
                     bytes
                 }
-                ScriptBit::Coinbase(bytes) => bytes.to_vec(),
+                ScriptBit::Coinbase(bytes) | ScriptBit::Unparsed(bytes) => bytes.to_vec(),
             })
             .collect();
 
@@ -124,47 +131,6 @@ impl Script {
         Script::from_bytes(&hex::decode(hex)?)
     }
 
-    pub fn from_bytes(bytes: &[u8]) -> Result<Script, BSVErrors> {
-        let mut cursor = Cursor::new(bytes);
-
-        let mut bit_accumulator = vec![];
-        while let Ok(byte) = cursor.read_u8() {
-            if byte.ne(&(OpCodes::OP_0 as u8)) && byte.lt(&(OpCodes::OP_PUSHDATA1 as u8)) {
-                let mut data: Vec<u8> = vec![0; byte as usize];
-                match cursor.read(&mut data) {
-                    Ok(len) => bit_accumulator.push(ScriptBit::Push(data[..len].to_vec())),
-                    Err(e) => return Err(BSVErrors::DeserialiseScript(format!("Failed to read OP_PUSH data {}", e))),
-                }
-                continue;
-            }
-
-            let bit = match OpCodes::from_u8(byte) {
-                Some(v @ (OpCodes::OP_PUSHDATA1 | OpCodes::OP_PUSHDATA2 | OpCodes::OP_PUSHDATA4)) => {
-                    let data_length = match v {
-                        OpCodes::OP_PUSHDATA1 => cursor.read_u8()? as usize,
-                        OpCodes::OP_PUSHDATA2 => cursor.read_u16::<LittleEndian>()? as usize,
-                        _ => cursor.read_u32::<LittleEndian>()? as usize,
-                    };
-
-                    let mut data = vec![0; data_length];
-                    if let Err(e) = cursor.read(&mut data) {
-                        return Err(BSVErrors::DeserialiseScript(format!("Failed to read OP_PUSHDATA data {}", e)));
-                    }
-
-                    ScriptBit::PushData(v, data)
-                }
-                Some(v) => ScriptBit::OpCode(v),
-                None => return Err(BSVErrors::DeserialiseScript(format!("Unknown opcode {}", byte))),
-            };
-
-            bit_accumulator.push(bit);
-        }
-
-        let nested_bits = Script::if_statement_pass(&mut bit_accumulator.iter())?;
-
-        Ok(Script(nested_bits))
-    }
-
     pub fn from_coinbase_bytes(bytes: &[u8]) -> Result<Script, BSVErrors> {
         Ok(Script(vec![ScriptBit::Coinbase(bytes.to_vec())]))
     }
@@ -198,6 +164,12 @@ impl Script {
             return Ok(ScriptBit::OpCode(opcode));
         }
 
+        // Decimal numbers must be signed, eg. `-5` or `+100`, so they are never mistaken for hex
+        if code.starts_with(['-', '+']) {
+            let number = ScriptNum::from_str(code).map_err(|e| BSVErrors::DeserialiseScript(format!("Invalid decimal number {}: {}", code, e)))?;
+            return Ok(number.to_script_bit());
+        }
+
         // PUSHDATA OP_CODES
         let data_bytes = hex::decode(code)?;
         let bit = match VarInt::get_pushdata_opcode(data_bytes.len() as u64) {
@@ -263,6 +235,10 @@ impl Script {
         Ok(nested_bits)
     }
 
+    /// Parses opcodes and hex data separated by spaces.
+    ///
+    /// 0 - 16 are OP_0 - OP_16, any other unsigned value is hex data, so `100` pushes the bytes `01 00`.
+    /// Other decimal numbers need a sign, eg. `-5` or `+100`, and are pushed as a minimal `ScriptNum`.
     pub fn from_asm_string(asm: &str) -> Result<Script, BSVErrors> {
         let bits: Result<Vec<ScriptBit>, _> = asm.split(' ').filter(|x| !(x.is_empty() || x == &"\n" || x == &"\r")).map(Script::map_string_to_script_bit).collect();
         let bits = Script::if_statement_pass(&mut bits?.iter())?;
@@ -371,4 +347,13 @@ impl Script {
     pub fn to_script_bits(&self) -> Vec<ScriptBit> {
         self.0.clone()
     }
+
+    /// Returns `true` if the script only pushes data, ie. every opcode is OP_16 or lower.
+    pub fn is_push_only(&self) -> bool {
+        self.0.iter().all(|bit| match bit {
+            ScriptBit::Push(_) | ScriptBit::PushData(_, _) => true,
+            ScriptBit::OpCode(code) => (*code as u8) <= OpCodes::OP_16 as u8,
+            ScriptBit::If { .. } | ScriptBit::Coinbase(_) | ScriptBit::Unparsed(_) => false,
+        })
+    }
 }
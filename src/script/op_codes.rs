@@ -2,6 +2,8 @@ use serde::{Deserialize, Serialize};
 use std::fmt;
 use strum_macros::EnumString;
 
+use crate::ScriptFlags;
+
 /**
  * This entire page is borrowed from rust-sv (https://github.com/brentongunning/rust-sv/blob/master/src/script/op_codes.rs)
  */
@@ -258,11 +260,11 @@ pub enum OpCodes {
     // --------------------------------------------------------------------------------------------
     /// Transaction is invalid unless occuring in an unexecuted OP_IF branch
     OP_RESERVED = 80,
-    /// Transaction is invalid unless occuring in an unexecuted OP_IF branch
+    /// Transaction is invalid unless occuring in an unexecuted OP_IF branch. After Chronicle, pushes the transaction's version.
     OP_VER = 98,
-    /// Transaction is invalid even when occuring in an unexecuted OP_IF branch
+    /// Transaction is invalid even when occuring in an unexecuted OP_IF branch. After Chronicle, `OP_VER OP_EQUAL OP_IF`.
     OP_VERIF = 101,
-    /// Transaction is invalid even when occuring in an unexecuted OP_IF branch
+    /// Transaction is invalid even when occuring in an unexecuted OP_IF branch. After Chronicle, `OP_VER OP_EQUAL OP_NOTIF`.
     OP_VERNOTIF = 102,
     /// Transaction is invalid unless occuring in an unexecuted OP_IF branch
     OP_RESERVED1 = 137,
@@ -270,15 +272,25 @@ pub enum OpCodes {
     OP_RESERVED2 = 138,
     /// The word is ignored. Does not mark transaction as invalid.
     OP_NOP1 = 176,
-    /// The word is ignored. Does not mark transaction as invalid.
+    /// The word is ignored. Does not mark transaction as invalid. OP_SUBSTR after Chronicle.
+    #[strum(serialize = "OP_NOP4", serialize = "OP_SUBSTR")]
+    #[serde(alias = "OP_SUBSTR")]
     OP_NOP4 = 179,
-    /// The word is ignored. Does not mark transaction as invalid.
+    /// The word is ignored. Does not mark transaction as invalid. OP_LEFT after Chronicle.
+    #[strum(serialize = "OP_NOP5", serialize = "OP_LEFT")]
+    #[serde(alias = "OP_LEFT")]
     OP_NOP5 = 180,
-    /// The word is ignored. Does not mark transaction as invalid.
+    /// The word is ignored. Does not mark transaction as invalid. OP_RIGHT after Chronicle.
+    #[strum(serialize = "OP_NOP6", serialize = "OP_RIGHT")]
+    #[serde(alias = "OP_RIGHT")]
     OP_NOP6 = 181,
-    /// The word is ignored. Does not mark transaction as invalid.
+    /// The word is ignored. Does not mark transaction as invalid. OP_LSHIFTNUM after Chronicle.
+    #[strum(serialize = "OP_NOP7", serialize = "OP_LSHIFTNUM")]
+    #[serde(alias = "OP_LSHIFTNUM")]
     OP_NOP7 = 182,
-    /// The word is ignored. Does not mark transaction as invalid.
+    /// The word is ignored. Does not mark transaction as invalid. OP_RSHIFTNUM after Chronicle.
+    #[strum(serialize = "OP_NOP8", serialize = "OP_RSHIFTNUM")]
+    #[serde(alias = "OP_RSHIFTNUM")]
     OP_NOP8 = 183,
     /// The word is ignored. Does not mark transaction as invalid.
     OP_NOP9 = 184,
@@ -291,12 +303,158 @@ pub enum OpCodes {
     // --------------------------------------------------------------------------------------------
     // Disabled words
     // --------------------------------------------------------------------------------------------
-    /// The input is multiplied by 2
+    /// The input is multiplied by 2. Re-enabled by Chronicle.
     OP_2MUL = 141,
-    /// The input is divided by 2
+    /// The input is divided by 2. Re-enabled by Chronicle.
     OP_2DIV = 142,
 }
 
+/// Names Chronicle gives to OP_NOP4 - OP_NOP8. Scripts are still displayed with the NOP names so ASM round-trips unchanged.
+impl OpCodes {
+    /// Keeps `size` bytes from position `begin`: `data begin size OP_SUBSTR`.
+    pub const OP_SUBSTR: OpCodes = OpCodes::OP_NOP4;
+    /// Keeps the leftmost `size` bytes: `data size OP_LEFT`.
+    pub const OP_LEFT: OpCodes = OpCodes::OP_NOP5;
+    /// Keeps the rightmost `size` bytes: `data size OP_RIGHT`.
+    pub const OP_RIGHT: OpCodes = OpCodes::OP_NOP6;
+    /// Shifts a number left by `n` bits, keeping its sign: `a n OP_LSHIFTNUM`.
+    pub const OP_LSHIFTNUM: OpCodes = OpCodes::OP_NOP7;
+    /// Shifts a number right by `n` bits, keeping its sign: `a n OP_RSHIFTNUM`.
+    pub const OP_RSHIFTNUM: OpCodes = OpCodes::OP_NOP8;
+}
+
+/// Number of elements an opcode pops off and pushes onto the main stack when it is executed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StackArity {
+    pub inputs: usize,
+    pub outputs: usize,
+}
+
+const fn arity(inputs: usize, outputs: usize) -> Option<StackArity> {
+    Some(StackArity { inputs, outputs })
+}
+
+/// What each opcode does to the stack and whether it may be used, both of which depend on the era the script is executed in.
+impl OpCodes {
+    /// Fails the script wherever it appears, even in an unexecuted branch.
+    pub fn is_disabled(&self, flags: ScriptFlags) -> bool {
+        match self {
+            OpCodes::OP_2MUL | OpCodes::OP_2DIV | OpCodes::OP_VERIF | OpCodes::OP_VERNOTIF => !flags.contains(ScriptFlags::UTXO_AFTER_CHRONICLE),
+            _ => false,
+        }
+    }
+
+    /// Fails the script when it is executed. Pseudo-words used by Script Templates are invalid in scripts.
+    pub fn is_invalid(&self, flags: ScriptFlags) -> bool {
+        match self {
+            OpCodes::OP_RESERVED
+            | OpCodes::OP_RESERVED1
+            | OpCodes::OP_RESERVED2
+            | OpCodes::OP_INVALID_ABOVE
+            | OpCodes::OP_DATA
+            | OpCodes::OP_SIG
+            | OpCodes::OP_PUBKEYHASH
+            | OpCodes::OP_PUBKEY
+            | OpCodes::OP_INVALIDOPCODE => true,
+            OpCodes::OP_VER => !flags.contains(ScriptFlags::UTXO_AFTER_CHRONICLE),
+            OpCodes::OP_RETURN => !flags.contains(ScriptFlags::UTXO_AFTER_GENESIS),
+            _ => self.is_disabled(flags),
+        }
+    }
+
+    /// Elements popped off and pushed onto the main stack when executed, `None` if the opcode is invalid or the count depends on
+    /// the values on the stack, ie. OP_IFDUP, OP_PICK, OP_ROLL, OP_CHECKMULTISIG and OP_CHECKMULTISIGVERIFY.
+    pub fn stack_arity(&self, flags: ScriptFlags) -> Option<StackArity> {
+        if self.is_invalid(flags) {
+            return None;
+        }
+
+        let is_chronicle = flags.contains(ScriptFlags::UTXO_AFTER_CHRONICLE);
+        let is_locktime_enabled = |flag| flags.contains(flag) && !flags.contains(ScriptFlags::UTXO_AFTER_GENESIS);
+        match *self {
+            OpCodes::OP_0 | OpCodes::OP_PUSHDATA1 | OpCodes::OP_PUSHDATA2 | OpCodes::OP_PUSHDATA4 | OpCodes::OP_1NEGATE => arity(0, 1),
+            OpCodes::OP_1
+            | OpCodes::OP_2
+            | OpCodes::OP_3
+            | OpCodes::OP_4
+            | OpCodes::OP_5
+            | OpCodes::OP_6
+            | OpCodes::OP_7
+            | OpCodes::OP_8
+            | OpCodes::OP_9
+            | OpCodes::OP_10
+            | OpCodes::OP_11
+            | OpCodes::OP_12
+            | OpCodes::OP_13
+            | OpCodes::OP_14
+            | OpCodes::OP_15
+            | OpCodes::OP_16 => arity(0, 1),
+            OpCodes::OP_NOP | OpCodes::OP_NOP1 | OpCodes::OP_NOP9 | OpCodes::OP_NOP10 | OpCodes::OP_CODESEPARATOR | OpCodes::OP_RETURN => arity(0, 0),
+            OpCodes::OP_ELSE | OpCodes::OP_ENDIF => arity(0, 0),
+            OpCodes::OP_IF | OpCodes::OP_NOTIF | OpCodes::OP_VERIF | OpCodes::OP_VERNOTIF | OpCodes::OP_VERIFY => arity(1, 0),
+            OpCodes::OP_VER => arity(0, 1),
+            OpCodes::OP_TOALTSTACK => arity(1, 0),
+            OpCodes::OP_FROMALTSTACK | OpCodes::OP_DEPTH => arity(0, 1),
+            OpCodes::OP_IFDUP | OpCodes::OP_PICK | OpCodes::OP_ROLL | OpCodes::OP_CHECKMULTISIG | OpCodes::OP_CHECKMULTISIGVERIFY => None,
+            OpCodes::OP_DROP => arity(1, 0),
+            OpCodes::OP_DUP => arity(1, 2),
+            OpCodes::OP_NIP => arity(2, 1),
+            OpCodes::OP_OVER | OpCodes::OP_TUCK => arity(2, 3),
+            OpCodes::OP_ROT => arity(3, 3),
+            OpCodes::OP_SWAP => arity(2, 2),
+            OpCodes::OP_2DROP => arity(2, 0),
+            OpCodes::OP_2DUP => arity(2, 4),
+            OpCodes::OP_3DUP => arity(3, 6),
+            OpCodes::OP_2OVER => arity(4, 6),
+            OpCodes::OP_2ROT => arity(6, 6),
+            OpCodes::OP_2SWAP => arity(4, 4),
+            OpCodes::OP_CAT | OpCodes::OP_NUM2BIN => arity(2, 1),
+            OpCodes::OP_SPLIT => arity(2, 2),
+            OpCodes::OP_SIZE => arity(1, 2),
+            OpCodes::OP_BIN2NUM | OpCodes::OP_INVERT => arity(1, 1),
+            OpCodes::OP_AND | OpCodes::OP_OR | OpCodes::OP_XOR | OpCodes::OP_EQUAL => arity(2, 1),
+            OpCodes::OP_EQUALVERIFY | OpCodes::OP_NUMEQUALVERIFY | OpCodes::OP_CHECKSIGVERIFY => arity(2, 0),
+            OpCodes::OP_1ADD | OpCodes::OP_1SUB | OpCodes::OP_2MUL | OpCodes::OP_2DIV | OpCodes::OP_NEGATE | OpCodes::OP_ABS | OpCodes::OP_NOT | OpCodes::OP_0NOTEQUAL => arity(1, 1),
+            OpCodes::OP_ADD
+            | OpCodes::OP_SUB
+            | OpCodes::OP_MUL
+            | OpCodes::OP_DIV
+            | OpCodes::OP_MOD
+            | OpCodes::OP_LSHIFT
+            | OpCodes::OP_RSHIFT
+            | OpCodes::OP_BOOLAND
+            | OpCodes::OP_BOOLOR
+            | OpCodes::OP_NUMEQUAL
+            | OpCodes::OP_NUMNOTEQUAL
+            | OpCodes::OP_LESSTHAN
+            | OpCodes::OP_GREATERTHAN
+            | OpCodes::OP_LESSTHANOREQUAL
+            | OpCodes::OP_GREATERTHANOREQUAL
+            | OpCodes::OP_MIN
+            | OpCodes::OP_MAX => arity(2, 1),
+            OpCodes::OP_WITHIN => arity(3, 1),
+            OpCodes::OP_RIPEMD160 | OpCodes::OP_SHA1 | OpCodes::OP_SHA256 | OpCodes::OP_HASH160 | OpCodes::OP_HASH256 => arity(1, 1),
+            OpCodes::OP_CHECKSIG => arity(2, 1),
+            // Only checked before Genesis when their flags are set, NOPs otherwise. The locktime is left on the stack.
+            OpCodes::OP_CHECKLOCKTIMEVERIFY if is_locktime_enabled(ScriptFlags::CHECKLOCKTIMEVERIFY) => arity(1, 1),
+            OpCodes::OP_CHECKSEQUENCEVERIFY if is_locktime_enabled(ScriptFlags::CHECKSEQUENCEVERIFY) => arity(1, 1),
+            OpCodes::OP_CHECKLOCKTIMEVERIFY | OpCodes::OP_CHECKSEQUENCEVERIFY => arity(0, 0),
+            OpCodes::OP_SUBSTR if is_chronicle => arity(3, 1),
+            OpCodes::OP_LEFT | OpCodes::OP_RIGHT | OpCodes::OP_LSHIFTNUM | OpCodes::OP_RSHIFTNUM if is_chronicle => arity(2, 1),
+            OpCodes::OP_SUBSTR | OpCodes::OP_LEFT | OpCodes::OP_RIGHT | OpCodes::OP_LSHIFTNUM | OpCodes::OP_RSHIFTNUM => arity(0, 0),
+            OpCodes::OP_RESERVED
+            | OpCodes::OP_RESERVED1
+            | OpCodes::OP_RESERVED2
+            | OpCodes::OP_INVALID_ABOVE
+            | OpCodes::OP_DATA
+            | OpCodes::OP_SIG
+            | OpCodes::OP_PUBKEYHASH
+            | OpCodes::OP_PUBKEY
+            | OpCodes::OP_INVALIDOPCODE => None,
+        }
+    }
+}
+
 impl fmt::Display for OpCodes {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{:?}", self)
@@ -1,5 +1,5 @@
 use crate::utils::{from_hex, to_hex};
-use crate::OpCodes;
+use crate::{OpCodes, VarInt};
 use serde::*;
 use strum_macros::Display;
 
@@ -7,8 +7,53 @@ use strum_macros::Display;
 #[serde(untagged)]
 pub enum ScriptBit {
     OpCode(OpCodes),
-    If { code: OpCodes, pass: Vec<ScriptBit>, fail: Option<Vec<ScriptBit>> },
+    If {
+        code: OpCodes,
+        pass: Vec<ScriptBit>,
+        fail: Option<Vec<ScriptBit>>,
+    },
     Push(#[serde(serialize_with = "to_hex", deserialize_with = "from_hex")] Vec<u8>),
     PushData(OpCodes, #[serde(serialize_with = "to_hex", deserialize_with = "from_hex")] Vec<u8>),
     Coinbase(#[serde(serialize_with = "to_hex", deserialize_with = "from_hex")] Vec<u8>),
+    /// Raw tail of a script from the first byte that could not be parsed, see `Script::from_bytes_lenient`.
+    Unparsed(#[serde(serialize_with = "to_hex", deserialize_with = "from_hex")] Vec<u8>),
+}
+
+impl ScriptBit {
+    /// Pushes the data with the smallest encoding, the same one required by the MINIMALDATA flag.
+    ///
+    /// Empty data and single bytes 1 - 16 and 0x81 are pushed with OP_0, OP_1 - OP_16 and OP_1NEGATE, up to 75 bytes are pushed
+    /// directly and anything longer with OP_PUSHDATA1, OP_PUSHDATA2 or OP_PUSHDATA4.
+    pub fn minimal_push(data: &[u8]) -> ScriptBit {
+        match data {
+            [] => ScriptBit::OpCode(OpCodes::OP_0),
+            [0x81] => ScriptBit::OpCode(OpCodes::OP_1NEGATE),
+            [n @ 1..=16] => ScriptBit::OpCode(small_number_opcode(*n)),
+            _ => match VarInt::get_pushdata_opcode(data.len() as u64) {
+                Some(code) => ScriptBit::PushData(code, data.to_vec()),
+                None => ScriptBit::Push(data.to_vec()),
+            },
+        }
+    }
+}
+
+fn small_number_opcode(n: u8) -> OpCodes {
+    match n {
+        1 => OpCodes::OP_1,
+        2 => OpCodes::OP_2,
+        3 => OpCodes::OP_3,
+        4 => OpCodes::OP_4,
+        5 => OpCodes::OP_5,
+        6 => OpCodes::OP_6,
+        7 => OpCodes::OP_7,
+        8 => OpCodes::OP_8,
+        9 => OpCodes::OP_9,
+        10 => OpCodes::OP_10,
+        11 => OpCodes::OP_11,
+        12 => OpCodes::OP_12,
+        13 => OpCodes::OP_13,
+        14 => OpCodes::OP_14,
+        15 => OpCodes::OP_15,
+        _ => OpCodes::OP_16,
+    }
 }
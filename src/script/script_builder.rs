@@ -0,0 +1,176 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{OpCodes, Script, ScriptBit, ScriptNum};
+
+/// Where a label was placed in a script built by `ScriptBuilder`, pointing at the ScriptBit added after it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LabelPosition {
+    /// Index of the opcode or push with conditionals flattened, as used by the Interpreter and `Breakpoint::Index`.
+    pub index: usize,
+    pub byte_offset: usize,
+}
+
+/// Builds a Script one opcode or push at a time, choosing the encoding of each push instead of formatting ASM.
+///
+/// ```
+/// use bsv::{OpCodes, ScriptBuilder};
+///
+/// let script = ScriptBuilder::new()
+///     .push_int(1000)
+///     .push_opcode(OpCodes::OP_CHECKSEQUENCEVERIFY)
+///     .push_opcode(OpCodes::OP_DROP)
+///     .build();
+///
+/// assert_eq!(script.to_hex(), "02e803b275");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ScriptBuilder {
+    bits: Vec<ScriptBit>,
+    /// Opcodes and pushes added so far, with conditionals flattened.
+    length: usize,
+    byte_length: usize,
+    labels: BTreeMap<String, LabelPosition>,
+}
+
+impl ScriptBuilder {
+    pub fn new() -> ScriptBuilder {
+        ScriptBuilder::default()
+    }
+
+    fn push_bit(mut self, bit: ScriptBit) -> ScriptBuilder {
+        self.byte_length += Script::script_bits_to_bytes(std::slice::from_ref(&bit)).len();
+        self.length += 1;
+        self.bits.push(bit);
+        self
+    }
+
+    /// Adds an opcode. Use `push_data` and `push_int` rather than OP_PUSHDATA1 - OP_PUSHDATA4, and `if_then` and `if_else` for conditionals.
+    pub fn push_opcode(self, code: OpCodes) -> ScriptBuilder {
+        self.push_bit(ScriptBit::OpCode(code))
+    }
+
+    /// Pushes the data with the smallest encoding, see `ScriptBit::minimal_push`.
+    pub fn push_data(self, data: &[u8]) -> ScriptBuilder {
+        self.push_bit(ScriptBit::minimal_push(data))
+    }
+
+    /// Pushes the number encoded as a script number, ie. minimal little endian bytes with the sign in the highest bit.
+    pub fn push_int(self, number: i64) -> ScriptBuilder {
+        self.push_num(&ScriptNum::from(number))
+    }
+
+    /// Pushes a script number of any size, see `ScriptNum`.
+    pub fn push_num(self, number: &ScriptNum) -> ScriptBuilder {
+        self.push_bit(number.to_script_bit())
+    }
+
+    /// Pushes OP_1 for true and OP_0 for false.
+    pub fn push_bool(self, boolean: bool) -> ScriptBuilder {
+        self.push_int(boolean as i64)
+    }
+
+    /// Adds an OP_IF block with the statements added by `then`, closed by OP_ENDIF.
+    pub fn if_then<F>(self, then: F) -> ScriptBuilder
+    where
+        F: FnOnce(ScriptBuilder) -> ScriptBuilder,
+    {
+        self.conditional(then, None::<fn(ScriptBuilder) -> ScriptBuilder>)
+    }
+
+    /// Adds an OP_IF block with the statements added by `then`, and an OP_ELSE block with those added by `otherwise`.
+    pub fn if_else<F, G>(self, then: F, otherwise: G) -> ScriptBuilder
+    where
+        F: FnOnce(ScriptBuilder) -> ScriptBuilder,
+        G: FnOnce(ScriptBuilder) -> ScriptBuilder,
+    {
+        self.conditional(then, Some(otherwise))
+    }
+
+    fn conditional<F, G>(mut self, then: F, otherwise: Option<G>) -> ScriptBuilder
+    where
+        F: FnOnce(ScriptBuilder) -> ScriptBuilder,
+        G: FnOnce(ScriptBuilder) -> ScriptBuilder,
+    {
+        // OP_IF
+        self.length += 1;
+        self.byte_length += 1;
+        let pass = self.append_block(then(ScriptBuilder::new()));
+
+        let fail = otherwise.map(|otherwise| {
+            // OP_ELSE
+            self.length += 1;
+            self.byte_length += 1;
+            self.append_block(otherwise(ScriptBuilder::new()))
+        });
+
+        // OP_ENDIF
+        self.length += 1;
+        self.byte_length += 1;
+        self.bits.push(ScriptBit::If { code: OpCodes::OP_IF, pass, fail });
+        self
+    }
+
+    /// Moves the labels of a block built separately to where it is being added, giving back its ScriptBits.
+    fn append_block(&mut self, block: ScriptBuilder) -> Vec<ScriptBit> {
+        for (name, position) in block.labels {
+            self.labels.insert(
+                name,
+                LabelPosition {
+                    index: self.length + position.index,
+                    byte_offset: self.byte_length + position.byte_offset,
+                },
+            );
+        }
+        self.length += block.length;
+        self.byte_length += block.byte_length;
+        block.bits
+    }
+
+    /// Adds every opcode and push of another script as is.
+    pub fn append(mut self, script: &Script) -> ScriptBuilder {
+        self.length += flattened_length(&script.0);
+        self.byte_length += script.to_bytes().len();
+        self.bits.extend_from_slice(&script.0);
+        self
+    }
+
+    /// Names the position of whatever is added next, replacing any earlier label with the same name.
+    pub fn label(mut self, name: &str) -> ScriptBuilder {
+        let position = LabelPosition {
+            index: self.length,
+            byte_offset: self.byte_length,
+        };
+        self.labels.insert(name.to_string(), position);
+        self
+    }
+
+    pub fn labels(&self) -> &BTreeMap<String, LabelPosition> {
+        &self.labels
+    }
+
+    pub fn get_label(&self, name: &str) -> Option<LabelPosition> {
+        self.labels.get(name).copied()
+    }
+
+    pub fn build(&self) -> Script {
+        Script(self.bits.clone())
+    }
+}
+
+impl From<ScriptBuilder> for Script {
+    fn from(builder: ScriptBuilder) -> Script {
+        Script(builder.bits)
+    }
+}
+
+/// Opcodes and pushes in the ScriptBits once conditionals are flattened into OP_IF, OP_ELSE and OP_ENDIF.
+fn flattened_length(bits: &[ScriptBit]) -> usize {
+    bits.iter()
+        .map(|bit| match bit {
+            ScriptBit::If { pass, fail, .. } => 2 + flattened_length(pass) + fail.as_ref().map(|x| 1 + flattened_length(x)).unwrap_or_default(),
+            _ => 1,
+        })
+        .sum()
+}
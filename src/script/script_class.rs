@@ -0,0 +1,254 @@
+use std::sync::OnceLock;
+
+use num_traits::FromPrimitive;
+use serde::{Deserialize, Serialize};
+
+use crate::{OpCodes, Script, ScriptBit, ScriptTemplate, TemplateMatch};
+
+/// Hash opcodes an R-puzzle may apply to the R value of the signature before comparing it.
+const R_PUZZLE_HASHES: [OpCodes; 5] = [OpCodes::OP_HASH160, OpCodes::OP_SHA1, OpCodes::OP_SHA256, OpCodes::OP_HASH256, OpCodes::OP_RIPEMD160];
+
+/// Pushed first in a 1Sat Ordinals inscription envelope.
+const ORD: &[u8] = b"ord";
+
+/// The standard output types a script can be recognised as, see `Script::classify`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScriptClass {
+    /// `OP_DUP OP_HASH160 <pubkey hash> OP_EQUALVERIFY OP_CHECKSIG`
+    P2PKH {
+        pubkey_hash: Vec<u8>,
+    },
+    /// `<pubkey> OP_CHECKSIG`
+    P2PK {
+        public_key: Vec<u8>,
+    },
+    /// `OP_m <pubkey> ... OP_n OP_CHECKMULTISIG`
+    Multisig {
+        required: usize,
+        public_keys: Vec<Vec<u8>>,
+    },
+    /// `OP_HASH160 <script hash> OP_EQUAL`, the legacy pay to script hash pattern, matched whatever the era.
+    ///
+    /// Nodes only evaluate the redeem script of such outputs before Genesis.
+    P2SH {
+        script_hash: Vec<u8>,
+    },
+    /// `OP_FALSE OP_RETURN` followed by anything, even bytes that do not parse as a script.
+    ///
+    /// `pushes` holds the data pushed up to the first opcode that is not a push, `payload` every byte after OP_RETURN.
+    FalseReturn {
+        pushes: Vec<Vec<u8>>,
+        payload: Vec<u8>,
+    },
+    /// `OP_RETURN <data> ...`, only pushes may follow but the script may end in bytes that do not parse.
+    ///
+    /// `pushes` holds the data pushed, `payload` every byte after OP_RETURN.
+    OpReturn {
+        pushes: Vec<Vec<u8>>,
+        payload: Vec<u8>,
+    },
+    /// `OP_FALSE OP_IF "ord" OP_1 <content type> OP_0 <content> OP_ENDIF` before or after the script locking the inscription.
+    Inscription {
+        content_type: Vec<u8>,
+        content: Vec<u8>,
+        lock: Box<ScriptClass>,
+    },
+    /// `OP_OVER OP_3 OP_SPLIT OP_NIP OP_1 OP_SPLIT OP_SWAP OP_SPLIT OP_DROP [hash] <R> OP_EQUALVERIFY OP_CHECKSIG`
+    ///
+    /// `value` is the R value of the signature, or its hash if `hash` is set.
+    RPuzzle {
+        hash: Option<OpCodes>,
+        value: Vec<u8>,
+    },
+    Nonstandard,
+}
+
+/// Templates for each output type, compiled the first time a script is classified.
+struct ClassTemplates {
+    p2pkh: ScriptTemplate,
+    p2pk: ScriptTemplate,
+    p2sh: ScriptTemplate,
+    multisig: ScriptTemplate,
+    false_return: ScriptTemplate,
+    op_return: ScriptTemplate,
+    r_puzzle: ScriptTemplate,
+    inscription: ScriptTemplate,
+}
+
+impl ClassTemplates {
+    fn get() -> &'static ClassTemplates {
+        static TEMPLATES: OnceLock<ClassTemplates> = OnceLock::new();
+
+        TEMPLATES.get_or_init(|| {
+            let compile = |asm: &str| ScriptTemplate::from_asm_string(asm).expect("Output type templates are valid");
+            let small_numbers = |name: &str| format!("({})", (1..=16).map(|n| format!("OP_{}:{}", n, name)).collect::<Vec<_>>().join("|"));
+            let hashes = R_PUZZLE_HASHES.iter().map(|x| format!("{}:hash", x)).collect::<Vec<_>>().join("|");
+
+            ClassTemplates {
+                p2pkh: compile("OP_DUP OP_HASH160 OP_PUBKEYHASH:pubkey_hash OP_EQUALVERIFY OP_CHECKSIG"),
+                p2pk: compile("OP_PUBKEY:public_key OP_CHECKSIG"),
+                p2sh: compile("OP_HASH160 OP_DATA=20:script_hash OP_EQUAL"),
+                multisig: compile(&format!("{} OP_PUBKEY:public_keys{{1,16}} {} OP_CHECKMULTISIG", small_numbers("required"), small_numbers("keys"))),
+                false_return: compile("OP_0 OP_RETURN ...:payload"),
+                op_return: compile("OP_RETURN ...:payload"),
+                r_puzzle: compile(&format!(
+                    "OP_OVER OP_3 OP_SPLIT OP_NIP OP_1 OP_SPLIT OP_SWAP OP_SPLIT OP_DROP ({})? OP_DATA:value OP_EQUALVERIFY OP_CHECKSIG",
+                    hashes
+                )),
+                inscription: compile(&format!("{} OP_1 OP_DATA:content_type OP_0 OP_DATA:content", hex::encode(ORD))),
+            }
+        })
+    }
+}
+
+/// Reads the number pushed by OP_1 - OP_16 from the byte captured for it.
+fn small_number(captured: &[u8]) -> Option<usize> {
+    match captured {
+        [code] => Some((*code - OpCodes::OP_1 as u8) as usize + 1),
+        _ => None,
+    }
+}
+
+/// The data pushed at the start of the ScriptBits, and whether they are all pushes.
+///
+/// OP_0, OP_1NEGATE and OP_1 - OP_16 push their numbers, and a raw unparsed tail ends the pushes without counting as anything else.
+fn leading_pushes(bits: &[ScriptBit]) -> (Vec<Vec<u8>>, bool) {
+    let mut pushes = vec![];
+    for bit in bits {
+        let data = match bit {
+            ScriptBit::Push(data) | ScriptBit::PushData(_, data) => data.clone(),
+            ScriptBit::OpCode(OpCodes::OP_0) => vec![],
+            ScriptBit::OpCode(OpCodes::OP_1NEGATE) => vec![0x81],
+            ScriptBit::OpCode(code) if (OpCodes::OP_1 as u8..=OpCodes::OP_16 as u8).contains(&(*code as u8)) => vec![*code as u8 - OpCodes::OP_1 as u8 + 1],
+            ScriptBit::Unparsed(_) => break,
+            _ => return (pushes, false),
+        };
+        pushes.push(data);
+    }
+
+    (pushes, true)
+}
+
+impl Script {
+    /// Recognises the standard output type of the script and extracts its fields.
+    ///
+    /// # Example
+    /// ```
+    /// use bsv::{Script, ScriptClass};
+    ///
+    /// let script = Script::from_asm_string("OP_DUP OP_HASH160 05186ff0710ed004229e644c0653b2985c648a23 OP_EQUALVERIFY OP_CHECKSIG").unwrap();
+    /// assert_eq!(
+    ///     script.classify(),
+    ///     ScriptClass::P2PKH {
+    ///         pubkey_hash: hex::decode("05186ff0710ed004229e644c0653b2985c648a23").unwrap()
+    ///     }
+    /// );
+    /// ```
+    pub fn classify(&self) -> ScriptClass {
+        let templates = ClassTemplates::get();
+
+        if let Some(matched) = self.template_captures(&templates.p2pkh) {
+            return ScriptClass::P2PKH {
+                pubkey_hash: matched.get("pubkey_hash").unwrap_or_default().to_vec(),
+            };
+        }
+
+        if let Some(matched) = self.template_captures(&templates.p2pk) {
+            return ScriptClass::P2PK {
+                public_key: matched.get("public_key").unwrap_or_default().to_vec(),
+            };
+        }
+
+        if let Some(matched) = self.template_captures(&templates.p2sh) {
+            return ScriptClass::P2SH {
+                script_hash: matched.get("script_hash").unwrap_or_default().to_vec(),
+            };
+        }
+
+        if let Some(class) = self.classify_multisig(&templates.multisig) {
+            return class;
+        }
+
+        if let Some(matched) = self.template_captures(&templates.false_return) {
+            return ScriptClass::FalseReturn {
+                pushes: leading_pushes(&self.0[2..]).0,
+                payload: matched.get("payload").unwrap_or_default().to_vec(),
+            };
+        }
+
+        if let Some(matched) = self.template_captures(&templates.op_return) {
+            if let (pushes, true) = leading_pushes(&self.0[1..]) {
+                return ScriptClass::OpReturn {
+                    pushes,
+                    payload: matched.get("payload").unwrap_or_default().to_vec(),
+                };
+            }
+        }
+
+        if let Some(class) = self.classify_r_puzzle(&templates.r_puzzle) {
+            return class;
+        }
+
+        if let Some(class) = self.classify_inscription(&templates.inscription) {
+            return class;
+        }
+
+        ScriptClass::Nonstandard
+    }
+
+    /// What the named tokens of the template captured, if the script matches it.
+    fn template_captures(&self, template: &ScriptTemplate) -> Option<TemplateMatch> {
+        self.match_template(template).ok()
+    }
+
+    fn classify_multisig(&self, template: &ScriptTemplate) -> Option<ScriptClass> {
+        let matched = self.template_captures(template)?;
+        let required = small_number(matched.get("required")?)?;
+        let public_keys = matched.get_all("public_keys").to_vec();
+
+        // The key count must match the keys given, and cannot be less than the signatures required
+        match small_number(matched.get("keys")?)? {
+            keys if keys == public_keys.len() && required <= keys => Some(ScriptClass::Multisig { required, public_keys }),
+            _ => None,
+        }
+    }
+
+    fn classify_r_puzzle(&self, template: &ScriptTemplate) -> Option<ScriptClass> {
+        let matched = self.template_captures(template)?;
+        let hash = match matched.get("hash") {
+            Some([code]) => Some(OpCodes::from_u8(*code)?),
+            _ => None,
+        };
+
+        Some(ScriptClass::RPuzzle {
+            hash,
+            value: matched.get("value")?.to_vec(),
+        })
+    }
+
+    fn classify_inscription(&self, template: &ScriptTemplate) -> Option<ScriptClass> {
+        // The envelope is parsed into a conditional, whose contents are matched on their own
+        let envelope = self.0.windows(2).position(|x| match x {
+            [ScriptBit::OpCode(OpCodes::OP_0), ScriptBit::If {
+                code: OpCodes::OP_IF,
+                pass,
+                fail: None,
+            }] => pass.first() == Some(&ScriptBit::Push(ORD.to_vec())),
+            _ => false,
+        })?;
+
+        let matched = match &self.0[envelope + 1] {
+            ScriptBit::If { pass, .. } => Script(pass.clone()).template_captures(template)?,
+            _ => return None,
+        };
+
+        let mut lock = self.0.clone();
+        lock.drain(envelope..envelope + 2);
+
+        Some(ScriptClass::Inscription {
+            content_type: matched.get("content_type")?.to_vec(),
+            content: matched.get("content")?.to_vec(),
+            lock: Box::new(Script(lock).classify()),
+        })
+    }
+}
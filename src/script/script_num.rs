@@ -0,0 +1,220 @@
+use std::{
+    fmt,
+    ops::{Add, Mul, Neg, Sub},
+    str::FromStr,
+};
+
+use num_bigint::{BigInt, ParseBigIntError, Sign};
+use num_traits::{ToPrimitive, Zero};
+use thiserror::Error;
+
+use crate::ScriptBit;
+
+/// Why bytes could not be read as a script number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum ScriptNumError {
+    #[error("Number is {length} bytes, longer than the maximum of {max_length}")]
+    TooLong { length: usize, max_length: usize },
+
+    #[error("Number is not encoded with the fewest bytes possible")]
+    NotMinimal,
+}
+
+/// A number as the interpreter reads it from and writes it to the stack.
+///
+/// Script numbers are little endian bytes of the magnitude with the sign in the highest bit of the last byte, zero is empty.
+/// The same value can be encoded with extra zero bytes, which the MINIMALDATA flag rejects and `to_bytes` never produces.
+///
+/// ```
+/// use bsv::ScriptNum;
+///
+/// assert_eq!(ScriptNum::from(-128).to_bytes(), vec![0x80, 0x80]);
+/// assert_eq!(ScriptNum::from_bytes(&[0xe8, 0x03], true, 4).unwrap().to_i64(), Some(1000));
+/// assert!(ScriptNum::from_bytes(&[0xe8, 0x03, 0x00], true, 4).is_err());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ScriptNum(BigInt);
+
+impl ScriptNum {
+    /// Reads a number of at most `max_length` bytes, which must also be minimally encoded if `require_minimal` is set.
+    pub fn from_bytes(bytes: &[u8], require_minimal: bool, max_length: usize) -> Result<ScriptNum, ScriptNumError> {
+        if bytes.len() > max_length {
+            return Err(ScriptNumError::TooLong { length: bytes.len(), max_length });
+        }
+        if require_minimal && !ScriptNum::is_minimal(bytes) {
+            return Err(ScriptNumError::NotMinimal);
+        }
+
+        Ok(ScriptNum::from_bytes_lenient(bytes))
+    }
+
+    /// Reads any bytes as a number regardless of their length or encoding, the same as OP_BIN2NUM.
+    pub fn from_bytes_lenient(bytes: &[u8]) -> ScriptNum {
+        let mut magnitude = bytes.to_vec();
+        let sign = match magnitude.last_mut() {
+            Some(last) if *last & 0x80 != 0 => {
+                *last &= !0x80;
+                Sign::Minus
+            }
+            _ => Sign::Plus,
+        };
+
+        ScriptNum(BigInt::from_bytes_le(sign, &magnitude))
+    }
+
+    /// The minimal encoding of the number.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        if self.0.is_zero() {
+            return vec![];
+        }
+
+        let (sign, mut bytes) = self.0.to_bytes_le();
+        // An extra byte is needed for the sign if the highest bit is already used by the magnitude
+        match bytes.last_mut() {
+            Some(last) if *last & 0x80 != 0 => bytes.push(if sign == Sign::Minus { 0x80 } else { 0x00 }),
+            Some(last) if sign == Sign::Minus => *last |= 0x80,
+            _ => (),
+        }
+
+        bytes
+    }
+
+    /// Checks the number is encoded with the fewest bytes possible, ie. has no superfluous trailing zero bytes.
+    pub fn is_minimal(bytes: &[u8]) -> bool {
+        match bytes.last() {
+            None => true,
+            // The last byte may only be 0x00 or 0x80 if the byte before it needs its high bit for the value
+            Some(last) if last & 0x7f == 0 => bytes.len() > 1 && bytes[bytes.len() - 2] & 0x80 != 0,
+            Some(_) => true,
+        }
+    }
+
+    /// The smallest push of the number, OP_0, OP_1NEGATE and OP_1 - OP_16 where possible.
+    pub fn to_script_bit(&self) -> ScriptBit {
+        ScriptBit::minimal_push(&self.to_bytes())
+    }
+
+    /// Length of the minimal encoding.
+    pub fn encoded_len(&self) -> usize {
+        match self.0.is_zero() {
+            true => 0,
+            // One bit is kept for the sign
+            false => (self.0.bits() / 8 + 1) as usize,
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0.is_zero()
+    }
+
+    pub fn to_i64(&self) -> Option<i64> {
+        self.0.to_i64()
+    }
+
+    pub fn to_bigint(&self) -> BigInt {
+        self.0.clone()
+    }
+
+    /// `None` if the sum is longer than `max_length` bytes.
+    pub fn checked_add(&self, other: &ScriptNum, max_length: usize) -> Option<ScriptNum> {
+        ScriptNum(&self.0 + &other.0).within(max_length)
+    }
+
+    /// `None` if the difference is longer than `max_length` bytes.
+    pub fn checked_sub(&self, other: &ScriptNum, max_length: usize) -> Option<ScriptNum> {
+        ScriptNum(&self.0 - &other.0).within(max_length)
+    }
+
+    /// `None` if the product is longer than `max_length` bytes.
+    pub fn checked_mul(&self, other: &ScriptNum, max_length: usize) -> Option<ScriptNum> {
+        ScriptNum(&self.0 * &other.0).within(max_length)
+    }
+
+    /// Rounds towards zero like OP_DIV, `None` when dividing by zero or if the quotient is longer than `max_length` bytes.
+    pub fn checked_div(&self, other: &ScriptNum, max_length: usize) -> Option<ScriptNum> {
+        match other.is_zero() {
+            true => None,
+            false => ScriptNum(&self.0 / &other.0).within(max_length),
+        }
+    }
+
+    /// Takes the sign of the dividend like OP_MOD, `None` when dividing by zero or if the remainder is longer than `max_length` bytes.
+    pub fn checked_rem(&self, other: &ScriptNum, max_length: usize) -> Option<ScriptNum> {
+        match other.is_zero() {
+            true => None,
+            false => ScriptNum(&self.0 % &other.0).within(max_length),
+        }
+    }
+
+    fn within(self, max_length: usize) -> Option<ScriptNum> {
+        match self.encoded_len() <= max_length {
+            true => Some(self),
+            false => None,
+        }
+    }
+}
+
+impl From<i64> for ScriptNum {
+    fn from(number: i64) -> ScriptNum {
+        ScriptNum(BigInt::from(number))
+    }
+}
+
+impl From<BigInt> for ScriptNum {
+    fn from(number: BigInt) -> ScriptNum {
+        ScriptNum(number)
+    }
+}
+
+impl From<ScriptNum> for BigInt {
+    fn from(number: ScriptNum) -> BigInt {
+        number.0
+    }
+}
+
+impl fmt::Display for ScriptNum {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// Parses a decimal number, with a leading `-` if it is negative.
+impl FromStr for ScriptNum {
+    type Err = ParseBigIntError;
+
+    fn from_str(s: &str) -> Result<ScriptNum, ParseBigIntError> {
+        Ok(ScriptNum(BigInt::from_str(s)?))
+    }
+}
+
+impl Add for ScriptNum {
+    type Output = ScriptNum;
+
+    fn add(self, other: ScriptNum) -> ScriptNum {
+        ScriptNum(self.0 + other.0)
+    }
+}
+
+impl Sub for ScriptNum {
+    type Output = ScriptNum;
+
+    fn sub(self, other: ScriptNum) -> ScriptNum {
+        ScriptNum(self.0 - other.0)
+    }
+}
+
+impl Mul for ScriptNum {
+    type Output = ScriptNum;
+
+    fn mul(self, other: ScriptNum) -> ScriptNum {
+        ScriptNum(self.0 * other.0)
+    }
+}
+
+impl Neg for ScriptNum {
+    type Output = ScriptNum;
+
+    fn neg(self) -> ScriptNum {
+        ScriptNum(-self.0)
+    }
+}
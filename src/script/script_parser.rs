@@ -0,0 +1,185 @@
+use std::fmt;
+
+use num_traits::FromPrimitive;
+use serde::{Deserialize, Serialize};
+
+use crate::{BSVErrors, OpCodes, Script, ScriptBit};
+
+/// Why part of a serialised script could not be parsed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScriptParseErrorKind {
+    /// A byte that is not assigned to any opcode.
+    UnknownOpcode(u8),
+    /// A push, or the length of an OP_PUSHDATA, runs past the end of the script.
+    TruncatedPush,
+    /// OP_ELSE or OP_ENDIF without an open conditional, or a conditional never closed with OP_ENDIF.
+    UnbalancedConditional(OpCodes),
+}
+
+/// A part of a serialised script that could not be parsed, at the offset of the byte it starts at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScriptParseError {
+    pub offset: usize,
+    pub kind: ScriptParseErrorKind,
+}
+
+impl fmt::Display for ScriptParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            ScriptParseErrorKind::UnknownOpcode(byte) => write!(f, "unknown opcode {:#04x} at byte {}", byte, self.offset),
+            ScriptParseErrorKind::TruncatedPush => write!(f, "push runs past the end of the script at byte {}", self.offset),
+            ScriptParseErrorKind::UnbalancedConditional(code) => write!(f, "unbalanced {} at byte {}", code, self.offset),
+        }
+    }
+}
+
+pub(crate) fn display_parse_errors(errors: &[ScriptParseError]) -> String {
+    errors.iter().map(|x| x.to_string()).collect::<Vec<String>>().join(", ")
+}
+
+/// A script parsed by `Script::from_bytes_lenient`, with everything that `Script::from_bytes` would have rejected.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParsedScript {
+    pub script: Script,
+    pub errors: Vec<ScriptParseError>,
+}
+
+impl ParsedScript {
+    /// The script ends part way through a push, which is kept in the raw tail.
+    pub fn is_truncated(&self) -> bool {
+        self.errors.iter().any(|x| x.kind == ScriptParseErrorKind::TruncatedPush)
+    }
+}
+
+/// A serialised script split into opcodes and pushes, without nesting conditionals.
+struct ScannedScript {
+    bits: Vec<ScriptBit>,
+    offsets: Vec<usize>,
+    /// Bytes from the first unknown opcode or truncated push on.
+    tail_offset: Option<usize>,
+    errors: Vec<ScriptParseError>,
+}
+
+impl ScannedScript {
+    /// Unknown opcodes are skipped so every one of them is reported, a truncated push ends the script.
+    fn scan(bytes: &[u8]) -> ScannedScript {
+        let mut scanned = ScannedScript {
+            bits: vec![],
+            offsets: vec![],
+            tail_offset: None,
+            errors: vec![],
+        };
+        let mut offset = 0;
+
+        while let Some(byte) = bytes.get(offset).copied() {
+            let length_size = match byte {
+                0x4c => 1,
+                0x4d => 2,
+                0x4e => 4,
+                _ => 0,
+            };
+
+            let data_length = match byte {
+                0x01..=0x4b => Some(byte as usize),
+                0x4c..=0x4e => bytes.get(offset + 1..offset + 1 + length_size).map(|x| x.iter().rev().fold(0_usize, |acc, x| (acc << 8) | *x as usize)),
+                _ => {
+                    match OpCodes::from_u8(byte) {
+                        Some(code) => {
+                            scanned.bits.push(ScriptBit::OpCode(code));
+                            scanned.offsets.push(offset);
+                        }
+                        None => scanned.error(offset, ScriptParseErrorKind::UnknownOpcode(byte)),
+                    }
+                    offset += 1;
+                    continue;
+                }
+            };
+
+            let data_start = offset + 1 + length_size;
+            let data = match data_length.and_then(|length| bytes.get(data_start..data_start.checked_add(length)?)) {
+                Some(v) => v.to_vec(),
+                None => {
+                    scanned.error(offset, ScriptParseErrorKind::TruncatedPush);
+                    break;
+                }
+            };
+
+            scanned.offsets.push(offset);
+            offset = data_start + data.len();
+            scanned.bits.push(match byte {
+                0x4c => ScriptBit::PushData(OpCodes::OP_PUSHDATA1, data),
+                0x4d => ScriptBit::PushData(OpCodes::OP_PUSHDATA2, data),
+                0x4e => ScriptBit::PushData(OpCodes::OP_PUSHDATA4, data),
+                _ => ScriptBit::Push(data),
+            });
+        }
+
+        scanned.check_conditionals();
+        scanned.errors.sort_by_key(|x| x.offset);
+        scanned
+    }
+
+    fn error(&mut self, offset: usize, kind: ScriptParseErrorKind) {
+        self.tail_offset.get_or_insert(offset);
+        self.errors.push(ScriptParseError { offset, kind });
+    }
+
+    fn check_conditionals(&mut self) {
+        let mut open = vec![];
+        for (bit, offset) in self.bits.iter().zip(self.offsets.iter()) {
+            match bit {
+                ScriptBit::OpCode(code @ (OpCodes::OP_IF | OpCodes::OP_NOTIF | OpCodes::OP_VERIF | OpCodes::OP_VERNOTIF)) => open.push((*offset, *code)),
+                ScriptBit::OpCode(code @ OpCodes::OP_ELSE) if open.is_empty() => self.errors.push(ScriptParseError {
+                    offset: *offset,
+                    kind: ScriptParseErrorKind::UnbalancedConditional(*code),
+                }),
+                ScriptBit::OpCode(code @ OpCodes::OP_ENDIF) if open.pop().is_none() => self.errors.push(ScriptParseError {
+                    offset: *offset,
+                    kind: ScriptParseErrorKind::UnbalancedConditional(*code),
+                }),
+                _ => (),
+            }
+        }
+
+        self.errors.extend(open.into_iter().map(|(offset, code)| ScriptParseError {
+            offset,
+            kind: ScriptParseErrorKind::UnbalancedConditional(code),
+        }));
+    }
+}
+
+impl Script {
+    /// Parses a serialised script, failing with the byte offset of every unknown opcode, truncated push and unbalanced conditional.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Script, BSVErrors> {
+        let scanned = ScannedScript::scan(bytes);
+        if !scanned.errors.is_empty() {
+            return Err(BSVErrors::ParseScript(scanned.errors));
+        }
+
+        Ok(Script(Script::if_statement_pass(&mut scanned.bits.iter())?))
+    }
+
+    /// Parses any serialised script, such as arbitrary data following an OP_RETURN, so that `to_bytes` gives back the same bytes.
+    ///
+    /// Everything from the first unknown opcode or truncated push on is kept as a raw `ScriptBit::Unparsed` tail,
+    /// and conditionals are only nested if they are balanced. Whatever `Script::from_bytes` would have rejected is returned alongside.
+    pub fn from_bytes_lenient(bytes: &[u8]) -> ParsedScript {
+        let scanned = ScannedScript::scan(bytes);
+        let tail_offset = scanned.tail_offset.unwrap_or(bytes.len());
+        let parsed_bits = scanned.offsets.iter().take_while(|x| **x < tail_offset).count();
+
+        let bits = &scanned.bits[..parsed_bits];
+        let mut script = match Script::if_statement_pass(&mut bits.iter()) {
+            Ok(v) => v,
+            Err(_) => bits.to_vec(),
+        };
+        if tail_offset < bytes.len() {
+            script.push(ScriptBit::Unparsed(bytes[tail_offset..].to_vec()));
+        }
+
+        ParsedScript {
+            script: Script(script),
+            errors: scanned.errors,
+        }
+    }
+}
@@ -1,5 +1,8 @@
 use crate::OpCodes::OP_0;
-use std::str::FromStr;
+use std::{
+    collections::{BTreeMap, HashSet},
+    str::FromStr,
+};
 
 use crate::{BSVErrors, OpCodes, PublicKey, Script, ScriptBit, Signature, VarInt};
 use hex::FromHexError;
@@ -8,6 +11,12 @@ use serde::{Deserialize, Serialize};
 use strum_macros::Display;
 use thiserror::Error;
 
+/// Largest count a `{n}`, `{n,}` or `{n,m}` quantifier may use.
+pub const MAX_TEMPLATE_REPEAT: usize = 1_000;
+
+/// Most instructions a template may compile to. Repeats are unrolled, so nested repeats multiply the size of their contents.
+pub const MAX_TEMPLATE_INSTRUCTIONS: usize = 100_000;
+
 #[derive(Debug, Error)]
 pub enum ScriptTemplateErrors {
     #[error("Script did not match template at index {0}. {2} is not equal to {1:?}. Error: {3:?}")]
@@ -19,6 +28,9 @@ pub enum ScriptTemplateErrors {
     #[error("Script Template and Script lengths do not match.")]
     LengthsDiffer,
 
+    #[error("Invalid Script Template syntax: {0}")]
+    Syntax(String),
+
     #[error("{0}")]
     MalformedHex(
         #[from]
@@ -51,7 +63,7 @@ pub enum MatchToken {
     PublicKeyHash,
 }
 
-#[derive(Debug, Clone, Display, Serialize, Deserialize)]
+#[derive(Debug, Clone, Display, PartialEq, Eq, Serialize, Deserialize)]
 pub enum MatchDataTypes {
     Data,
     Signature,
@@ -59,8 +71,243 @@ pub enum MatchDataTypes {
     PublicKeyHash,
 }
 
+/// Data extracted from a Script by a ScriptTemplate, see `Script::match_template`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TemplateMatch {
+    /// Data matched by every OP_DATA, OP_SIG, OP_PUBKEY and OP_PUBKEYHASH, in the order they appear in the Script.
+    pub data: Vec<(MatchDataTypes, Vec<u8>)>,
+    /// What each named token matched, in order as a repeated token matches several times.
+    ///
+    /// Pushes are captured as their data, opcodes as their byte and `...` as the rest of the serialised Script.
+    pub captures: BTreeMap<String, Vec<Vec<u8>>>,
+}
+
+impl TemplateMatch {
+    /// The first match of the named token.
+    pub fn get(&self, name: &str) -> Option<&[u8]> {
+        self.captures.get(name)?.first().map(|x| x.as_slice())
+    }
+
+    /// Every match of the named token, empty if it was never matched.
+    pub fn get_all(&self, name: &str) -> &[Vec<u8>] {
+        self.captures.get(name).map(|x| x.as_slice()).unwrap_or_default()
+    }
+}
+
+/// A parsed template, before it is compiled into `Instruction`s.
+#[derive(Debug, Clone)]
+enum TemplateNode {
+    Token(MatchToken, Option<String>),
+    Rest(Option<String>),
+    Alternatives(Vec<Vec<TemplateNode>>),
+    Repeat(Box<TemplateNode>, usize, Option<usize>),
+}
+
+/// A step of a compiled ScriptTemplate.
 #[derive(Debug, Clone)]
-pub struct ScriptTemplate(Vec<MatchToken>);
+enum Instruction {
+    /// Matches one ScriptBit, and saves it under the name if there is one.
+    Token(MatchToken, Option<String>),
+    /// Matches the rest of the Script, whatever it is.
+    Rest(Option<String>),
+    /// Tries to match from the first instruction, then from the second if that fails.
+    Split(usize, usize),
+    Jump(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Lexeme<'a> {
+    Open,
+    /// With the quantifier that follows it, if any.
+    Close(&'a str),
+    Or,
+    Word(&'a str),
+}
+
+/// Template syntax, as a token optionally followed by a name and quantifier, eg. `OP_PUBKEY:keys{1,16}`.
+struct TemplateParser<'a> {
+    lexemes: Vec<Lexeme<'a>>,
+    position: usize,
+}
+
+#[allow(clippy::result_large_err)]
+impl<'a> TemplateParser<'a> {
+    fn parse(asm: &'a str) -> Result<Vec<TemplateNode>, ScriptTemplateErrors> {
+        let mut parser = TemplateParser {
+            lexemes: TemplateParser::lex(asm),
+            position: 0,
+        };
+
+        let mut alternatives = parser.parse_alternatives()?;
+        if parser.position < parser.lexemes.len() {
+            return Err(ScriptTemplateErrors::Syntax("\")\" without a matching \"(\"".into()));
+        }
+
+        Ok(match alternatives.len() {
+            1 => alternatives.remove(0),
+            _ => vec![TemplateNode::Alternatives(alternatives)],
+        })
+    }
+
+    fn lex(asm: &str) -> Vec<Lexeme<'_>> {
+        let word_end = |s: &str| s.find(|c: char| c.is_whitespace() || "()|".contains(c)).unwrap_or(s.len());
+
+        let mut lexemes = vec![];
+        let mut rest = asm.trim_start();
+        while let Some(c) = rest.chars().next() {
+            let length = match c {
+                '(' => {
+                    lexemes.push(Lexeme::Open);
+                    1
+                }
+                '|' => {
+                    lexemes.push(Lexeme::Or);
+                    1
+                }
+                ')' => {
+                    let suffix = &rest[1..1 + word_end(&rest[1..])];
+                    lexemes.push(Lexeme::Close(suffix));
+                    1 + suffix.len()
+                }
+                _ => {
+                    let word = &rest[..word_end(rest)];
+                    lexemes.push(Lexeme::Word(word));
+                    word.len()
+                }
+            };
+            rest = rest[length..].trim_start();
+        }
+
+        lexemes
+    }
+
+    fn parse_alternatives(&mut self) -> Result<Vec<Vec<TemplateNode>>, ScriptTemplateErrors> {
+        let mut alternatives = vec![self.parse_sequence()?];
+        while self.lexemes.get(self.position) == Some(&Lexeme::Or) {
+            self.position += 1;
+            alternatives.push(self.parse_sequence()?);
+        }
+
+        Ok(alternatives)
+    }
+
+    fn parse_sequence(&mut self) -> Result<Vec<TemplateNode>, ScriptTemplateErrors> {
+        let mut nodes = vec![];
+        loop {
+            match self.lexemes.get(self.position).copied() {
+                None | Some(Lexeme::Or) | Some(Lexeme::Close(_)) => return Ok(nodes),
+                Some(Lexeme::Open) => {
+                    self.position += 1;
+                    let group = TemplateNode::Alternatives(self.parse_alternatives()?);
+                    let suffix = match self.lexemes.get(self.position) {
+                        Some(Lexeme::Close(suffix)) => *suffix,
+                        _ => return Err(ScriptTemplateErrors::Syntax("\"(\" without a matching \")\"".into())),
+                    };
+                    self.position += 1;
+
+                    let (name, quantifier) = TemplateParser::parse_suffix(suffix)?;
+                    if name.is_some() {
+                        return Err(ScriptTemplateErrors::Syntax(format!("Group cannot be named: ){}", suffix)));
+                    }
+                    nodes.push(TemplateParser::repeat(group, quantifier));
+                }
+                Some(Lexeme::Word(word)) => {
+                    self.position += 1;
+                    nodes.push(TemplateParser::parse_word(word)?);
+                }
+            }
+        }
+    }
+
+    fn parse_word(word: &str) -> Result<TemplateNode, ScriptTemplateErrors> {
+        let base_end = word.find(|c: char| ":?*+{".contains(c)).unwrap_or(word.len());
+        let (base, suffix) = word.split_at(base_end);
+        let (name, quantifier) = TemplateParser::parse_suffix(suffix)?;
+
+        if base == "..." {
+            if quantifier.is_some() {
+                return Err(ScriptTemplateErrors::Syntax(format!("{} cannot be repeated", word)));
+            }
+            return Ok(TemplateNode::Rest(name));
+        }
+
+        let token = ScriptTemplate::map_string_to_match_token(base)?;
+        Ok(TemplateParser::repeat(TemplateNode::Token(token, name), quantifier))
+    }
+
+    /// Reads an optional `:name` and quantifier, in either order.
+    #[allow(clippy::type_complexity)]
+    fn parse_suffix(suffix: &str) -> Result<(Option<String>, Option<(usize, Option<usize>)>), ScriptTemplateErrors> {
+        let invalid = || ScriptTemplateErrors::Syntax(format!("Invalid name or quantifier {}", suffix));
+        let parse_count = |count: &str| match usize::from_str(count.trim()) {
+            Ok(v) if v > MAX_TEMPLATE_REPEAT => Err(ScriptTemplateErrors::Syntax(format!("{} repeats more than {} times", suffix, MAX_TEMPLATE_REPEAT))),
+            Ok(v) => Ok(v),
+            Err(_) => Err(invalid()),
+        };
+
+        let mut name = None;
+        let mut quantifier = None;
+        let mut rest = suffix;
+        while let Some(c) = rest.chars().next() {
+            let length = match c {
+                ':' if name.is_none() => {
+                    let length = rest[1..].find(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).unwrap_or(rest.len() - 1);
+                    if length == 0 {
+                        return Err(invalid());
+                    }
+                    name = Some(rest[1..1 + length].to_string());
+                    1 + length
+                }
+                '?' | '*' | '+' if quantifier.is_none() => {
+                    quantifier = Some(match c {
+                        '?' => (0, Some(1)),
+                        '*' => (0, None),
+                        _ => (1, None),
+                    });
+                    1
+                }
+                '{' if quantifier.is_none() => {
+                    let length = rest.find('}').ok_or_else(invalid)?;
+                    let (min, max) = match rest[1..length].split_once(',') {
+                        None => {
+                            let count = parse_count(&rest[1..length])?;
+                            (count, Some(count))
+                        }
+                        Some((min, max)) if max.trim().is_empty() => (parse_count(min)?, None),
+                        Some((min, max)) => (parse_count(min)?, Some(parse_count(max)?)),
+                    };
+                    if max.map(|max| min > max).unwrap_or_default() {
+                        return Err(invalid());
+                    }
+                    quantifier = Some((min, max));
+                    length + 1
+                }
+                _ => return Err(invalid()),
+            };
+            rest = &rest[length..];
+        }
+
+        Ok((name, quantifier))
+    }
+
+    fn repeat(node: TemplateNode, quantifier: Option<(usize, Option<usize>)>) -> TemplateNode {
+        match quantifier {
+            Some((min, max)) => TemplateNode::Repeat(Box::new(node), min, max),
+            None => node,
+        }
+    }
+}
+
+/// A pattern of opcodes and pushes that Scripts can be matched against, written like ASM.
+///
+/// Besides opcodes and hex data, a template can contain:
+/// - `OP_DATA`, `OP_DATA=20`, `OP_DATA>=20` etc. for any push or pushes of a length, and `OP_SIG`, `OP_PUBKEY` and `OP_PUBKEYHASH`
+/// - `?`, `*`, `+`, `{n}`, `{n,}` and `{n,m}` after a token or group to repeat it, eg. `OP_PUBKEY{1,16}`
+/// - `(OP_CHECKSIG|OP_CHECKSIGVERIFY)` to match any one of several sequences, eg. `(OP_DUP OP_HASH160 OP_PUBKEYHASH OP_EQUALVERIFY OP_CHECKSIG)?`
+/// - `:name` after a token to capture what it matched, eg. `OP_DATA:owner` or `OP_PUBKEY:keys+`
+/// - `...` to match the rest of the Script whatever it is, eg. `OP_RETURN ...:payload`
+#[derive(Debug, Clone)]
+pub struct ScriptTemplate(Vec<Instruction>);
 
 impl ScriptTemplate {
     fn map_string_to_match_token(code: &str) -> Result<MatchToken, ScriptTemplateErrors> {
@@ -128,14 +375,134 @@ impl ScriptTemplate {
         Ok(token)
     }
 
+    /// The number of instructions `compile` turns the nodes into.
+    fn instruction_count(nodes: &[TemplateNode]) -> usize {
+        nodes.iter().fold(0_usize, |acc, node| {
+            let count = match node {
+                TemplateNode::Token(..) | TemplateNode::Rest(_) => 1,
+                // A split before and a jump after every alternative but the last
+                TemplateNode::Alternatives(alternatives) => alternatives
+                    .iter()
+                    .fold(2 * alternatives.len().saturating_sub(1), |acc, x| acc.saturating_add(ScriptTemplate::instruction_count(x))),
+                TemplateNode::Repeat(node, min, max) => {
+                    let size = ScriptTemplate::instruction_count(std::slice::from_ref(node.as_ref()));
+                    let optional = match max {
+                        None => size.saturating_add(2),
+                        Some(max) => (max - min).saturating_mul(size.saturating_add(1)),
+                    };
+                    min.saturating_mul(size).saturating_add(optional)
+                }
+            };
+            acc.saturating_add(count)
+        })
+    }
+
+    fn compile(nodes: &[TemplateNode], program: &mut Vec<Instruction>) {
+        for node in nodes {
+            match node {
+                TemplateNode::Token(token, name) => program.push(Instruction::Token(token.clone(), name.clone())),
+                TemplateNode::Rest(name) => program.push(Instruction::Rest(name.clone())),
+                TemplateNode::Alternatives(alternatives) => {
+                    let mut jumps = vec![];
+                    for (i, alternative) in alternatives.iter().enumerate() {
+                        if i + 1 == alternatives.len() {
+                            ScriptTemplate::compile(alternative, program);
+                            break;
+                        }
+
+                        let split = program.len();
+                        program.push(Instruction::Split(split + 1, split + 1));
+                        ScriptTemplate::compile(alternative, program);
+                        jumps.push(program.len());
+                        program.push(Instruction::Jump(0));
+                        program[split] = Instruction::Split(split + 1, program.len());
+                    }
+
+                    let end = program.len();
+                    for jump in jumps {
+                        program[jump] = Instruction::Jump(end);
+                    }
+                }
+                TemplateNode::Repeat(node, min, max) => {
+                    let node = std::slice::from_ref(node.as_ref());
+                    for _ in 0..*min {
+                        ScriptTemplate::compile(node, program);
+                    }
+
+                    match max {
+                        None => {
+                            let split = program.len();
+                            program.push(Instruction::Split(split + 1, split + 1));
+                            ScriptTemplate::compile(node, program);
+                            program.push(Instruction::Jump(split));
+                            program[split] = Instruction::Split(split + 1, program.len());
+                        }
+                        // Each optional repetition skips to the end if it does not match
+                        Some(max) => {
+                            let mut splits = vec![];
+                            for _ in *min..*max {
+                                splits.push(program.len());
+                                program.push(Instruction::Split(0, 0));
+                                ScriptTemplate::compile(node, program);
+                            }
+
+                            let end = program.len();
+                            for split in splits {
+                                program[split] = Instruction::Split(split + 1, end);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Collects the data and captures of the tokens matched, given as the instruction that matched them and what they matched.
+    fn to_template_match(program: &[Instruction], matched: Vec<(usize, Vec<u8>)>) -> TemplateMatch {
+        let mut template_match = TemplateMatch::default();
+        for (pc, data) in matched {
+            let (data_type, name) = match &program[pc] {
+                Instruction::Token(token, name) => (token.data_type(), name),
+                Instruction::Rest(name) => (None, name),
+                _ => continue,
+            };
+
+            if let Some(name) = name {
+                template_match.captures.entry(name.clone()).or_default().push(data.clone());
+            }
+            if let Some(data_type) = data_type {
+                template_match.data.push((data_type, data));
+            }
+        }
+
+        template_match
+    }
+
+    /// The number of ScriptBits matched, if the template has no repetition, alternation or `...`.
+    fn fixed_length(&self) -> Option<usize> {
+        match self.0.iter().all(|x| matches!(x, Instruction::Token(..))) {
+            true => Some(self.0.len()),
+            false => None,
+        }
+    }
+
     pub fn from_script_impl(script: &Script) -> Result<ScriptTemplate, ScriptTemplateErrors> {
         ScriptTemplate::from_asm_string_impl(&script.to_asm_string_impl(false))
     }
 
     pub fn from_asm_string_impl(asm: &str) -> Result<ScriptTemplate, ScriptTemplateErrors> {
-        let tokens: Result<Vec<_>, _> = asm.split(' ').map(ScriptTemplate::map_string_to_match_token).collect();
+        let nodes = TemplateParser::parse(asm)?;
+        if ScriptTemplate::instruction_count(&nodes) > MAX_TEMPLATE_INSTRUCTIONS {
+            return Err(ScriptTemplateErrors::Syntax(format!(
+                "Template is larger than {} instructions once repeats are expanded",
+                MAX_TEMPLATE_INSTRUCTIONS
+            )));
+        }
 
-        Ok(ScriptTemplate(tokens?))
+        let mut program = vec![];
+        ScriptTemplate::compile(&nodes, &mut program);
+
+        Ok(ScriptTemplate(program))
     }
 }
 
@@ -164,72 +531,130 @@ impl ScriptTemplate {
 /**
  * Script Template
  */
-impl Script {
-    pub fn match_impl(&self, script_template: &ScriptTemplate) -> Result<Vec<(MatchDataTypes, Vec<u8>)>, ScriptTemplateErrors> {
-        if self.0.len() != script_template.0.len() {
-            return Err(ScriptTemplateErrors::LengthsDiffer);
-        }
-
-        let mut matches = vec![];
+impl MatchToken {
+    fn is_match(&self, script: &ScriptBit) -> Result<bool, BSVErrors> {
+        match (self, script) {
+            (MatchToken::OpCode(tmpl_code), ScriptBit::OpCode(op_code)) => Ok(tmpl_code == op_code),
+            (MatchToken::Push(tmpl_data), ScriptBit::Push(data)) => Ok(*tmpl_data == *data),
+            (MatchToken::PushData(tmpl_op, tmpl_data), ScriptBit::PushData(op, data)) => Ok(tmpl_op == op && tmpl_data == data),
 
-        for (i, (template, script)) in script_template.0.iter().zip(self.0.iter()).enumerate() {
-            let is_match = match (template, script) {
-                (MatchToken::OpCode(tmpl_code), ScriptBit::OpCode(op_code)) => Ok(tmpl_code == op_code),
-                (MatchToken::Push(tmpl_data), ScriptBit::Push(data)) => Ok(*tmpl_data == *data),
-                (MatchToken::PushData(tmpl_op, tmpl_data), ScriptBit::PushData(op, data)) => Ok(tmpl_op == op && tmpl_data == data),
+            (MatchToken::Data(len, constraint), ScriptBit::PushData(_, data) | ScriptBit::Push(data)) => match constraint {
+                DataLengthConstraints::Equals => Ok(&data.len() == len),
+                DataLengthConstraints::GreaterThan => Ok(&data.len() > len),
+                DataLengthConstraints::LessThan => Ok(&data.len() < len),
+                DataLengthConstraints::GreaterThanOrEquals => Ok(&data.len() >= len),
+                DataLengthConstraints::LessThanOrEquals => Ok(&data.len() <= len),
+            },
 
-                (MatchToken::Data(len, constraint), ScriptBit::PushData(_, data) | ScriptBit::Push(data)) => match constraint {
-                    DataLengthConstraints::Equals => Ok(&data.len() == len),
-                    DataLengthConstraints::GreaterThan => Ok(&data.len() > len),
-                    DataLengthConstraints::LessThan => Ok(&data.len() < len),
-                    DataLengthConstraints::GreaterThanOrEquals => Ok(&data.len() >= len),
-                    DataLengthConstraints::LessThanOrEquals => Ok(&data.len() <= len),
-                },
+            (MatchToken::AnyData, ScriptBit::Push(_)) => Ok(true),
+            (MatchToken::AnyData, ScriptBit::PushData(_, _)) => Ok(true),
 
-                (MatchToken::AnyData, ScriptBit::Push(_)) => Ok(true),
-                (MatchToken::AnyData, ScriptBit::PushData(_, _)) => Ok(true),
+            (MatchToken::Signature, ScriptBit::Push(sig_buf)) => Signature::from_der_impl(sig_buf).map(|_| true),
 
-                (MatchToken::Signature, ScriptBit::Push(sig_buf)) => Signature::from_der_impl(sig_buf).map(|_| true),
+            (MatchToken::PublicKey, ScriptBit::Push(pubkey_buf)) => PublicKey::from_bytes_impl(pubkey_buf).map(|_| true),
 
-                (MatchToken::PublicKey, ScriptBit::Push(pubkey_buf)) => PublicKey::from_bytes_impl(pubkey_buf).map(|_| true),
+            (MatchToken::PublicKeyHash, ScriptBit::Push(pubkeyhash_buf)) => Ok(pubkeyhash_buf.len() == 20), // OP_HASH160
 
-                (MatchToken::PublicKeyHash, ScriptBit::Push(pubkeyhash_buf)) => Ok(pubkeyhash_buf.len() == 20), // OP_HASH160
+            _ => Ok(false),
+        }
+    }
 
-                _ => Ok(false),
-            };
+    /// The kind of data extracted when the token matches.
+    fn data_type(&self) -> Option<MatchDataTypes> {
+        match self {
+            MatchToken::Data(_, _) | MatchToken::AnyData => Some(MatchDataTypes::Data),
+            MatchToken::Signature => Some(MatchDataTypes::Signature),
+            MatchToken::PublicKey => Some(MatchDataTypes::PublicKey),
+            MatchToken::PublicKeyHash => Some(MatchDataTypes::PublicKeyHash), // OP_HASH160
+            _ => None,
+        }
+    }
+}
 
-            match is_match {
-                Ok(true) => (),
-                Ok(false) => {
-                    return Err(ScriptTemplateErrors::MatchFailure(
-                        i,
-                        template.clone(),
-                        script.clone(),
-                        BSVErrors::GenericError(format!("{} != {}", template, script)),
-                    ));
-                }
-                Err(e) => {
-                    return Err(ScriptTemplateErrors::MatchFailure(i, template.clone(), script.clone(), e));
-                }
+/**
+ * Script Template
+ */
+impl Script {
+    /// Runs the template with backtracking, preferring to match repeated tokens as many times as possible and alternatives from the left.
+    ///
+    /// Each instruction is only tried once at each position of the Script, which bounds the matching time for any template.
+    #[allow(clippy::result_large_err)]
+    pub fn match_template_impl(&self, script_template: &ScriptTemplate) -> Result<TemplateMatch, ScriptTemplateErrors> {
+        if let Some(length) = script_template.fixed_length() {
+            if self.0.len() != length {
+                return Err(ScriptTemplateErrors::LengthsDiffer);
             }
+        }
 
-            // Now that we know script bit is a match, we can add the data parts to the matches array.
-            match (template, script) {
-                (MatchToken::Data(_, _), ScriptBit::PushData(_, data) | ScriptBit::Push(data)) => matches.push((MatchDataTypes::Data, data.clone())),
-
-                (MatchToken::AnyData, ScriptBit::Push(data)) => matches.push((MatchDataTypes::Data, data.clone())),
-                (MatchToken::AnyData, ScriptBit::PushData(_, data)) => matches.push((MatchDataTypes::Data, data.clone())),
-
-                (MatchToken::Signature, ScriptBit::Push(data)) => matches.push((MatchDataTypes::Signature, data.clone())),
-
-                (MatchToken::PublicKey, ScriptBit::Push(data)) => matches.push((MatchDataTypes::PublicKey, data.clone())),
+        let program = &script_template.0;
+        // Instruction to run, position in the Script, number of matched bits to keep
+        let mut jobs = vec![(0_usize, 0_usize, 0_usize)];
+        let mut visited = HashSet::new();
+        let mut matched: Vec<(usize, Vec<u8>)> = vec![];
+        // Position in the Script of the closest match that failed
+        let mut furthest_failure: Option<(usize, ScriptTemplateErrors)> = None;
+        let mut fail = |position: usize, error: ScriptTemplateErrors| {
+            if !matches!(furthest_failure, Some((i, _)) if i >= position) {
+                furthest_failure = Some((position, error));
+            }
+        };
 
-                (MatchToken::PublicKeyHash, ScriptBit::Push(data)) => matches.push((MatchDataTypes::PublicKeyHash, data.clone())), // OP_HASH160
-                _ => (),
+        while let Some((mut pc, mut position, matched_length)) = jobs.pop() {
+            matched.truncate(matched_length);
+
+            while visited.insert((pc, position)) {
+                match program.get(pc) {
+                    None if position == self.0.len() => return Ok(ScriptTemplate::to_template_match(program, matched)),
+                    None => {
+                        fail(position, ScriptTemplateErrors::LengthsDiffer);
+                        break;
+                    }
+                    Some(Instruction::Jump(to)) => pc = *to,
+                    Some(Instruction::Split(first, second)) => {
+                        jobs.push((*second, position, matched.len()));
+                        pc = *first;
+                    }
+                    Some(Instruction::Rest(_)) => {
+                        matched.push((pc, Script::script_bits_to_bytes(&self.0[position..])));
+                        position = self.0.len();
+                        pc += 1;
+                    }
+                    Some(Instruction::Token(template, _)) => {
+                        let script = match self.0.get(position) {
+                            Some(v) => v,
+                            None => {
+                                fail(position, ScriptTemplateErrors::LengthsDiffer);
+                                break;
+                            }
+                        };
+
+                        let error = match template.is_match(script) {
+                            Ok(true) => {
+                                let data = match script {
+                                    ScriptBit::Push(data) | ScriptBit::PushData(_, data) => data.clone(),
+                                    _ => Script::script_bits_to_bytes(std::slice::from_ref(script)),
+                                };
+                                matched.push((pc, data));
+                                position += 1;
+                                pc += 1;
+                                continue;
+                            }
+                            Ok(false) => BSVErrors::GenericError(format!("{} != {}", template, script)),
+                            Err(e) => e,
+                        };
+
+                        fail(position, ScriptTemplateErrors::MatchFailure(position, template.clone(), script.clone(), error));
+                        break;
+                    }
+                }
             }
         }
 
-        Ok(matches)
+        Err(furthest_failure.map_or(ScriptTemplateErrors::LengthsDiffer, |(_, error)| error))
+    }
+
+    pub fn match_impl(&self, script_template: &ScriptTemplate) -> Result<Vec<(MatchDataTypes, Vec<u8>)>, ScriptTemplateErrors> {
+        Ok(self.match_template_impl(script_template)?.data)
     }
 
     pub fn test_impl(&self, script_template: &ScriptTemplate) -> bool {
@@ -270,6 +695,24 @@ impl Script {
     pub fn is_match(&self, script_template: &ScriptTemplate) -> bool {
         self.test_impl(script_template)
     }
+
+    /// Matches the Script against the provided ScriptTemplate, returning the extracted data along with what each named token matched.
+    ///
+    /// # Example
+    /// ```
+    /// use bsv::{Script, ScriptTemplate};
+    ///
+    /// let script = Script::from_asm_string("OP_DUP OP_HASH160 05186ff0710ed004229e644c0653b2985c648a23 OP_EQUALVERIFY OP_CHECKSIG OP_RETURN 21e8 6d02").unwrap();
+    /// let script_template = ScriptTemplate::from_asm_string("OP_DUP OP_HASH160 OP_PUBKEYHASH:owner OP_EQUALVERIFY OP_CHECKSIG (OP_RETURN OP_DATA*:payload)?").unwrap();
+    ///
+    /// let matched = script.match_template(&script_template).unwrap();
+    /// assert_eq!(matched.get("owner"), Some(hex::decode("05186ff0710ed004229e644c0653b2985c648a23").unwrap().as_slice()));
+    /// assert_eq!(matched.get_all("payload"), &[vec![0x21, 0xe8], vec![0x6d, 0x02]]);
+    /// ```
+    #[allow(clippy::result_large_err)]
+    pub fn match_template(&self, script_template: &ScriptTemplate) -> Result<TemplateMatch, ScriptTemplateErrors> {
+        self.match_template_impl(script_template)
+    }
 }
 
 //#[cfg(all(feature = "wasm-bindgen-script-template"))]
@@ -59,6 +59,49 @@ pub enum SigHash {
      * SINGLE | ANYONECANPAY
      */
     Legacy_InputOutput = 0x83,
+
+    /**
+     * Set with FORKID from Chronicle to sign with the Original Transaction Digest Algorithm (OTDA) instead of BIP143
+     */
+    CHRONICLE = 0x20,
+    /**
+     * ALL | FORKID | CHRONICLE
+     */
+    OTDA_InputsOutputs = 0x61,
+    /**
+     * NONE | FORKID | CHRONICLE
+     */
+    OTDA_Inputs = 0x62,
+    /**
+     * SINGLE | FORKID | CHRONICLE
+     */
+    OTDA_InputsOutput = 0x63,
+    /**
+     * ALL | ANYONECANPAY | FORKID | CHRONICLE
+     */
+    OTDA_InputOutputs = 0xe1,
+    /**
+     * NONE | ANYONECANPAY | FORKID | CHRONICLE
+     */
+    OTDA_Input = 0xe2,
+    /**
+     * SINGLE | ANYONECANPAY | FORKID | CHRONICLE
+     */
+    OTDA_InputOutput = 0xe3,
+}
+
+impl SigHash {
+    /// Whether the preimage is built with the original algorithm once Chronicle is active, either because FORKID is not set or because CHRONICLE is.
+    /// Before Chronicle the CHRONICLE bit is ignored and FORKID signatures always use BIP143.
+    pub fn uses_otda(&self) -> bool {
+        let value = *self as u8;
+        value & SigHash::FORKID as u8 == 0 || value & SigHash::CHRONICLE as u8 != 0
+    }
+
+    /// Get the sighash with the CHRONICLE bit cleared, which picks the parts of the transaction BIP143 signs over.
+    fn without_chronicle(&self) -> SigHash {
+        FromPrimitive::from_u8(*self as u8 & !(SigHash::CHRONICLE as u8)).unwrap_or(*self)
+    }
 }
 
 impl TryFrom<u8> for SigHash {
@@ -154,10 +197,23 @@ impl Transaction {
             SigHash::Input | SigHash::InputOutput | SigHash::InputOutputs | SigHash::Inputs | SigHash::InputsOutput | SigHash::InputsOutputs => {
                 self.sighash_bip143(n_tx_in, sighash, unsigned_script, value)
             }
+            // Including the Chronicle OTDA variants, which opt back into the original algorithm
             _ => self.sighash_legacy(n_tx_in, sighash, unsigned_script),
         }
     }
 
+    /**
+     * Calculates the SIGHASH Buffer a signature is checked against by the Interpreter, which like the node only uses the
+     * original algorithm for FORKID signatures with the CHRONICLE bit once Chronicle is active
+     */
+    pub(crate) fn sighash_preimage_for_era(&mut self, n_tx_in: usize, sighash: SigHash, unsigned_script: &Script, value: u64, is_chronicle: bool) -> Result<Vec<u8>, BSVErrors> {
+        let is_forkid_otda = sighash as u8 & (SigHash::FORKID | SigHash::CHRONICLE) == SigHash::FORKID | SigHash::CHRONICLE;
+        match is_forkid_otda && !is_chronicle {
+            true => self.sighash_bip143(n_tx_in, sighash, unsigned_script, value),
+            false => self.sighash_preimage_impl(n_tx_in, sighash, unsigned_script, value),
+        }
+    }
+
     pub(crate) fn sighash_legacy(&mut self, n_tx_in: usize, sighash: SigHash, unsigned_script: &Script) -> Result<Vec<u8>, BSVErrors> {
         let mut tx = self.clone();
         let mut script = unsigned_script.clone();
@@ -171,7 +227,7 @@ impl Transaction {
         tx.set_input(n_tx_in, &prev_txin);
 
         match sighash {
-            SigHash::SINGLE | SigHash::Legacy_InputOutput => {
+            SigHash::SINGLE | SigHash::Legacy_InputOutput | SigHash::OTDA_InputsOutput | SigHash::OTDA_InputOutput => {
                 // Not supporting the SIGHASH_SINGLE bug. Sue me craig.
                 // // This if statement is needed because of Consensus SIGHASH_SINGLE bug
                 // // https://bitcoinfiles.org/t/9a3a165cc7881bb2e37567dec5eaab64568a889e83e6b850b42f347e1d96a555
@@ -180,13 +236,9 @@ impl Transaction {
                 // }
 
                 let txout = tx.get_output(n_tx_in).ok_or_else(|| BSVErrors::OutOfBounds(format!("Could not get TxOut at index {}", n_tx_in)))?;
-                tx.outputs = vec![txout];
-
-                for i in 0..tx.outputs.len() {
-                    if i < n_tx_in {
-                        tx.set_output(i, &TxOut::new(0xffffffffffffffff, &Script::default()));
-                    }
-                }
+                // Like the node, every output before the signed one is kept as a blank (-1 value, empty script) so it stays at the same index
+                tx.outputs = vec![TxOut::new(0xffffffffffffffff, &Script::default()); n_tx_in];
+                tx.outputs.push(txout);
 
                 for i in 0..tx.inputs.len() {
                     if i == n_tx_in {
@@ -197,7 +249,7 @@ impl Transaction {
                 }
             }
 
-            SigHash::NONE | SigHash::Legacy_Input => {
+            SigHash::NONE | SigHash::Legacy_Input | SigHash::OTDA_Inputs | SigHash::OTDA_Input => {
                 tx.outputs.clear();
 
                 for i in 0..tx.inputs.len() {
@@ -229,11 +281,13 @@ impl Transaction {
 
         let input = self.get_input(n_tx_in).ok_or_else(|| BSVErrors::OutOfBounds(format!("Could not get TxIn at index {}", n_tx_in)))?;
 
-        let hashed_outputs = self.hash_outputs(sighash, n_tx_in)?;
+        // The CHRONICLE bit only changes the algorithm, the sighash byte itself is still signed as given
+        let hash_type = sighash.without_chronicle();
+        let hashed_outputs = self.hash_outputs(hash_type, n_tx_in)?;
 
         buffer.write_u32::<LittleEndian>(self.version)?;
-        buffer.write_all(&self.hash_inputs(sighash))?;
-        buffer.write_all(&self.hash_sequence(sighash))?;
+        buffer.write_all(&self.hash_inputs(hash_type))?;
+        buffer.write_all(&self.hash_sequence(hash_type))?;
         buffer.write_all(&input.get_outpoint_bytes(Some(true)))?;
         buffer.write_varint(unsigned_script.to_bytes().len() as u64)?;
         buffer.write_all(&unsigned_script.to_bytes())?;
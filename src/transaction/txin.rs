@@ -6,7 +6,7 @@ use std::io::Read;
 use std::io::Write;
 
 use crate::{
-    utils::{from_reverse_hex, to_reverse_hex},
+    utils::{bounded_length, from_reverse_hex, to_reverse_hex},
     Script,
 };
 use serde::*;
@@ -63,7 +63,7 @@ impl TxIn {
                 let locking_script_bytes = locking_script.to_bytes();
 
                 unlocking_script_bytes.extend_from_slice(&locking_script_bytes);
-                Script::from_bytes(&unlocking_script_bytes)
+                Ok(Script::from_bytes_lenient(&unlocking_script_bytes).script)
             }
             None => Ok(self.unlocking_script.clone()),
         }
@@ -85,6 +85,28 @@ impl TxIn {
         TxIn::is_coinbase_outpoint_impl(&self.prev_tx_id, &self.vout)
     }
 
+    /// BIP34 puts the block height at the start of the coinbase script, as a small integer opcode or a push of up to 4 bytes.
+    pub(crate) fn get_bip34_height_impl(&self) -> Option<u32> {
+        if !self.is_coinbase_impl() {
+            return None;
+        }
+
+        let script = self.unlocking_script.to_bytes();
+        match *script.first()? {
+            0x00 => Some(0),
+            v @ 0x51..=0x60 => Some((v - 0x50) as u32),
+            len @ 0x01..=0x04 => {
+                let bytes = script.get(1..=len as usize)?;
+                // Script numbers are sign-magnitude, heights are never negative
+                if bytes[bytes.len() - 1] & 0x80 != 0 {
+                    return None;
+                }
+                Some(bytes.iter().rev().fold(0, |height, x| (height << 8) | *x as u32))
+            }
+            _ => None,
+        }
+    }
+
     pub(crate) fn read_in(cursor: &mut Cursor<Vec<u8>>) -> Result<TxIn, BSVErrors> {
         // PrevTxId - 32 bytes
         let mut prev_tx_id = vec![0; 32];
@@ -107,7 +129,7 @@ impl TxIn {
         };
 
         // Script Sig
-        let mut unlocking_script = vec![0; unlocking_script_size as usize];
+        let mut unlocking_script = vec![0; bounded_length(cursor, unlocking_script_size)];
         if let Err(e) = cursor.read(&mut unlocking_script) {
             return Err(BSVErrors::DeserialiseTxIn("unlocking_script".to_string(), e));
         }
@@ -121,7 +143,7 @@ impl TxIn {
         Ok(TxIn {
             unlocking_script: match TxIn::is_coinbase_outpoint_impl(&prev_tx_id, &vout) {
                 true => Script::from_coinbase_bytes(&unlocking_script)?,
-                false => Script::from_bytes(&unlocking_script)?,
+                false => Script::from_bytes_lenient(&unlocking_script).script,
             },
             prev_tx_id,
             vout,
@@ -487,4 +509,9 @@ impl TxIn {
     pub fn is_coinbase(&self) -> bool {
         self.is_coinbase_impl()
     }
+
+    /// Get the block height from the start of a coinbase script, None if this is not a coinbase input or the script does not start with a height.
+    pub fn get_bip34_height(&self) -> Option<u32> {
+        self.get_bip34_height_impl()
+    }
 }
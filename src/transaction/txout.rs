@@ -1,4 +1,4 @@
-use crate::{BSVErrors, Script, VarIntReader, VarIntWriter};
+use crate::{utils::bounded_length, BSVErrors, Script, VarIntReader, VarIntWriter};
 use byteorder::*;
 use serde::*;
 use std::io::Read;
@@ -33,14 +33,14 @@ impl TxOut {
         };
 
         // Script Pub Key
-        let mut script_pub_key = vec![0; script_pub_key_size as usize];
+        let mut script_pub_key = vec![0; bounded_length(cursor, script_pub_key_size)];
         if let Err(e) = cursor.read(&mut script_pub_key) {
             return Err(BSVErrors::DeserialiseTxOut("script_pub_key".to_string(), e));
         }
 
         Ok(TxOut {
             value: satoshis,
-            script_pub_key: Script::from_bytes(&script_pub_key)?,
+            script_pub_key: Script::from_bytes_lenient(&script_pub_key).script,
         })
     }
 
@@ -1,4 +1,5 @@
 use serde::{Deserialize, Deserializer, Serializer};
+use std::io::Cursor;
 
 pub fn to_hex<S>(vec: &[u8], serializer: S) -> Result<S::Ok, S::Error>
 where
@@ -58,3 +59,10 @@ where
 
     bs58::decode(buf).into_vec().map_err(serde::de::Error::custom)
 }
+
+/// Caps a length read from serialised data at the number of bytes left in the cursor,
+/// so a corrupt or malicious length cannot make the reader allocate more than the data it was given.
+pub(crate) fn bounded_length<T: AsRef<[u8]>>(cursor: &Cursor<T>, length: u64) -> usize {
+    let remaining = (cursor.get_ref().as_ref().len() as u64).saturating_sub(cursor.position());
+    length.min(remaining) as usize
+}
@@ -0,0 +1,32 @@
+//! Helpers shared by the interpreter tests, not every test file uses all of them.
+#![allow(dead_code)]
+
+use bsv::{Interpreter, InterpreterError, Script, ScriptFlags, Transaction, TxIn};
+
+/// Creates an interpreter for a single script.
+pub fn interpreter(asm: &str, flags: ScriptFlags) -> Interpreter {
+    Interpreter::from_script(&Script::from_asm_string(asm).unwrap(), flags)
+}
+
+/// Runs the interpreter to the end, returning the stack it finished with.
+pub fn run_interpreter(mut interpreter: Interpreter) -> Result<Vec<Vec<u8>>, InterpreterError> {
+    interpreter.run()?;
+    Ok(interpreter.state().stack().to_vec())
+}
+
+/// Runs a single script, returning the stack it finished with.
+pub fn run(asm: &str, flags: ScriptFlags) -> Result<Vec<Vec<u8>>, InterpreterError> {
+    run_interpreter(interpreter(asm, flags))
+}
+
+/// A transaction with a single input that spends `locking_script` with `unlocking_script`.
+pub fn spending_tx(unlocking_script: &str, locking_script: &str) -> Transaction {
+    let mut tx = Transaction::new(2, 0);
+
+    let mut txin = TxIn::default();
+    txin.set_satoshis(0);
+    txin.set_locking_script(&Script::from_asm_string(locking_script).unwrap());
+    txin.set_unlocking_script(&Script::from_asm_string(unlocking_script).unwrap());
+    tx.add_input(&txin);
+    tx
+}
@@ -1,7 +1,10 @@
+mod common;
+
 #[cfg(test)]
 mod interpreter_arithmetic_tests {
+    use crate::common::run;
     use bsv::Interpreter;
-    use bsv::Script;
+    use bsv::{InterpreterError, Script, ScriptFlags};
 
     #[test]
     fn one_plus_one_equals_two() {
@@ -14,7 +17,7 @@ mod interpreter_arithmetic_tests {
         )
         .unwrap();
 
-        let mut interpreter = Interpreter::from_script(&script);
+        let mut interpreter = Interpreter::from_script(&script, ScriptFlags::default());
         interpreter.run().unwrap();
 
         assert_eq!(interpreter.state().stack().last().unwrap(), &vec![2_u8]);
@@ -33,7 +36,7 @@ mod interpreter_arithmetic_tests {
         )
         .unwrap();
 
-        let mut interpreter = Interpreter::from_script(&script);
+        let mut interpreter = Interpreter::from_script(&script, ScriptFlags::default());
         interpreter.run().unwrap();
 
         assert_eq!(interpreter.state().stack().last(), None);
@@ -52,7 +55,7 @@ mod interpreter_arithmetic_tests {
         )
         .unwrap();
 
-        let mut interpreter = Interpreter::from_script(&script);
+        let mut interpreter = Interpreter::from_script(&script, ScriptFlags::default());
         interpreter.run().unwrap();
 
         assert_eq!(interpreter.state().stack().last().unwrap(), &vec![1_u8]);
@@ -71,9 +74,92 @@ mod interpreter_arithmetic_tests {
         )
         .unwrap();
 
-        let mut interpreter = Interpreter::from_script(&script);
+        let mut interpreter = Interpreter::from_script(&script, ScriptFlags::default());
         interpreter.run().unwrap();
 
         assert_eq!(interpreter.state().stack().last().unwrap(), &vec![1_u8]);
     }
+
+    #[test]
+    fn big_numbers_after_genesis() {
+        // 2^64 - 1 + 1 = 2^64
+        let stack = run("ffffffffffffffff00 OP_1 OP_ADD", ScriptFlags::default()).unwrap();
+        assert_eq!(stack, vec![hex::decode("000000000000000001").unwrap()]);
+
+        // (2^64 - 1)^2 - 1
+        let stack = run(
+            "ffffffffffffffff00 OP_DUP OP_MUL OP_1 OP_SUB 0000000000000000feffffffffffffff00 OP_NUMEQUAL",
+            ScriptFlags::default() | ScriptFlags::MINIMALDATA,
+        )
+        .unwrap();
+        assert_eq!(stack, vec![vec![1_u8]]);
+    }
+
+    #[test]
+    fn numbers_limited_to_four_bytes_before_genesis() {
+        assert!(matches!(run("0000000001 OP_1 OP_ADD", ScriptFlags::NONE), Err(InterpreterError::NumberOutOfRange)));
+
+        // Results may overflow to 5 bytes, but cannot be used as a number again
+        let stack = run("ffffff7f OP_1 OP_ADD", ScriptFlags::NONE).unwrap();
+        assert_eq!(stack, vec![hex::decode("0000008000").unwrap()]);
+        assert!(matches!(run("ffffff7f OP_1 OP_ADD OP_1 OP_ADD", ScriptFlags::NONE), Err(InterpreterError::NumberOutOfRange)));
+    }
+
+    #[test]
+    fn configurable_max_script_num_length() {
+        let script = Script::from_asm_string("0000000000000001 OP_1 OP_ADD").unwrap();
+
+        let mut interpreter = Interpreter::from_script(&script, ScriptFlags::default());
+        interpreter.set_max_script_num_length(7);
+        assert!(matches!(interpreter.run(), Err(InterpreterError::NumberOutOfRange)));
+
+        let mut interpreter = Interpreter::from_script(&script, ScriptFlags::default());
+        interpreter.set_max_script_num_length(8);
+        interpreter.run().unwrap();
+
+        // Pre-Genesis always uses 4 bytes
+        let mut interpreter = Interpreter::from_script(&script, ScriptFlags::NONE);
+        interpreter.set_max_script_num_length(8);
+        assert_eq!(interpreter.max_script_num_length(), 4);
+    }
+
+    #[test]
+    fn operands_are_taken_in_script_order() {
+        assert_eq!(run("OP_5 OP_3 OP_SUB", ScriptFlags::default()).unwrap(), vec![vec![2_u8]]);
+        assert_eq!(run("OP_7 OP_2 OP_DIV", ScriptFlags::default()).unwrap(), vec![vec![3_u8]]);
+        assert_eq!(run("OP_7 OP_3 OP_MOD", ScriptFlags::default()).unwrap(), vec![vec![1_u8]]);
+        assert_eq!(run("OP_2 OP_3 OP_LESSTHAN", ScriptFlags::default()).unwrap(), vec![vec![1_u8]]);
+        assert_eq!(run("OP_3 OP_2 OP_4 OP_WITHIN", ScriptFlags::default()).unwrap(), vec![vec![1_u8]]);
+        assert_eq!(run("OP_4 OP_2 OP_4 OP_WITHIN", ScriptFlags::default()).unwrap(), vec![Vec::<u8>::new()]);
+        assert_eq!(run("OP_1NEGATE OP_3 OP_ADD", ScriptFlags::default()).unwrap(), vec![vec![2_u8]]);
+    }
+
+    #[test]
+    fn division_by_zero_fails() {
+        assert!(matches!(run("OP_1 OP_0 OP_DIV", ScriptFlags::default()), Err(InterpreterError::DivByZero)));
+        assert!(matches!(run("OP_1 OP_0 OP_MOD", ScriptFlags::default()), Err(InterpreterError::ModByZero)));
+    }
+
+    #[test]
+    fn big_number_indices() {
+        let stack = run("aabbcc OP_1 OP_SPLIT", ScriptFlags::default()).unwrap();
+        assert_eq!(stack, vec![vec![0xaa_u8], vec![0xbb, 0xcc]]);
+        assert!(matches!(run("aabbcc OP_4 OP_SPLIT", ScriptFlags::default()), Err(InterpreterError::InvalidSplitRange)));
+
+        assert_eq!(run("OP_7 OP_8 OP_9 OP_2 OP_PICK", ScriptFlags::default()).unwrap(), vec![vec![7_u8], vec![8], vec![9], vec![7]]);
+        assert_eq!(run("OP_7 OP_8 OP_9 OP_2 OP_ROLL", ScriptFlags::default()).unwrap(), vec![vec![8_u8], vec![9], vec![7]]);
+
+        // Indices past the end of the stack or negative fail rather than panic
+        for asm in ["OP_1 ffffffffffffffff00 OP_PICK", "OP_1 OP_1 OP_ROLL", "OP_1 OP_1NEGATE OP_PICK"] {
+            assert!(matches!(run(asm, ScriptFlags::default()), Err(InterpreterError::InvalidStackOperation(_))));
+        }
+    }
+
+    #[test]
+    fn shifts_move_bits_of_byte_array() {
+        assert_eq!(run("0180 OP_1 OP_LSHIFT", ScriptFlags::default()).unwrap(), vec![vec![0x03_u8, 0x00]]);
+        assert_eq!(run("0180 OP_1 OP_RSHIFT", ScriptFlags::default()).unwrap(), vec![vec![0x00_u8, 0xc0]]);
+        assert_eq!(run("0180 OP_9 OP_LSHIFT", ScriptFlags::default()).unwrap(), vec![vec![0x00_u8, 0x00]]);
+        assert!(matches!(run("0180 OP_1NEGATE OP_LSHIFT", ScriptFlags::default()), Err(InterpreterError::InvalidNumberRange)));
+    }
 }
@@ -0,0 +1,152 @@
+mod common;
+
+#[cfg(test)]
+mod interpreter_chronicle_tests {
+    use crate::common::{run, run_interpreter};
+    use bsv::{InterpreterError, OpCodes, PrivateKey, Script, ScriptFlags, SigHash, SigningHash, SyntheticSpend, ECDSA};
+
+    fn chronicle() -> ScriptFlags {
+        ScriptFlags::UTXO_AFTER_GENESIS | ScriptFlags::UTXO_AFTER_CHRONICLE
+    }
+
+    /// Runs the locking script with an empty unlocking script in a version 2 transaction.
+    fn run_spend(asm: &str, flags: ScriptFlags) -> Result<Vec<Vec<u8>>, InterpreterError> {
+        let spend = SyntheticSpend::new(&Script::from_asm_string(asm).unwrap(), 1000).unwrap();
+        run_interpreter(spend.interpreter(flags)?)
+    }
+
+    #[test]
+    fn substr_left_and_right() {
+        assert_eq!(run("0102030405 OP_1 OP_3 OP_SUBSTR", chronicle()).unwrap(), vec![vec![2_u8, 3, 4]]);
+        assert_eq!(run("0102030405 OP_2 OP_LEFT", chronicle()).unwrap(), vec![vec![1_u8, 2]]);
+        assert_eq!(run("0102030405 OP_2 OP_RIGHT", chronicle()).unwrap(), vec![vec![4_u8, 5]]);
+        assert_eq!(run("0102030405 OP_5 OP_0 OP_SUBSTR", chronicle()).unwrap(), vec![Vec::<u8>::new()]);
+
+        assert!(matches!(run("0102030405 OP_3 OP_3 OP_SUBSTR", chronicle()), Err(InterpreterError::InvalidSplitRange)));
+        assert!(matches!(run("0102030405 OP_6 OP_LEFT", chronicle()), Err(InterpreterError::InvalidSplitRange)));
+        assert!(matches!(run("0102030405 OP_1NEGATE OP_RIGHT", chronicle()), Err(InterpreterError::InvalidStackOperation(_))));
+    }
+
+    #[test]
+    fn numeric_shifts_keep_sign() {
+        assert_eq!(run("OP_5 OP_2 OP_LSHIFTNUM", chronicle()).unwrap(), vec![vec![20_u8]]);
+        assert_eq!(run("OP_5 OP_1 OP_RSHIFTNUM", chronicle()).unwrap(), vec![vec![2_u8]]);
+        // -5 << 2 and -5 >> 1
+        assert_eq!(run("85 OP_2 OP_LSHIFTNUM", chronicle()).unwrap(), vec![vec![0x94_u8]]);
+        assert_eq!(run("85 OP_1 OP_RSHIFTNUM", chronicle()).unwrap(), vec![vec![0x82_u8]]);
+        // Every bit shifted out
+        assert_eq!(run("85 OP_16 OP_RSHIFTNUM", chronicle()).unwrap(), vec![Vec::<u8>::new()]);
+        // 1 << 127 has its high bit set, so needs a 17th byte for the sign
+        let mut expected = vec![0_u8; 15];
+        expected.extend([0x80, 0x00]);
+        assert_eq!(run("OP_1 7f OP_LSHIFTNUM", chronicle()).unwrap(), vec![expected]);
+
+        assert!(matches!(run("OP_1 OP_1NEGATE OP_LSHIFTNUM", chronicle()), Err(InterpreterError::InvalidNumberRange)));
+        assert!(matches!(run("OP_1 ffffff7f OP_LSHIFTNUM", chronicle()), Err(InterpreterError::NumberOutOfRange)));
+    }
+
+    #[test]
+    fn mul_and_div_by_two() {
+        assert_eq!(run("OP_3 OP_2MUL", chronicle()).unwrap(), vec![vec![6_u8]]);
+        assert_eq!(run("OP_7 OP_2DIV", chronicle()).unwrap(), vec![vec![3_u8]]);
+        assert_eq!(run("87 OP_2DIV", chronicle()).unwrap(), vec![vec![0x83_u8]]);
+
+        // Disabled before Chronicle, even in unexecuted branches
+        assert!(matches!(run("OP_3 OP_2MUL", ScriptFlags::default()), Err(InterpreterError::DisabledOpCode(OpCodes::OP_2MUL))));
+        assert!(matches!(
+            run("OP_0 OP_IF OP_2DIV OP_ENDIF", ScriptFlags::default()),
+            Err(InterpreterError::DisabledOpCode(OpCodes::OP_2DIV))
+        ));
+    }
+
+    #[test]
+    fn ver_verif_and_vernotif() {
+        assert_eq!(run_spend("OP_VER", chronicle()).unwrap(), vec![vec![2_u8, 0, 0, 0]]);
+        assert_eq!(run_spend("02000000 OP_VERIF OP_2 OP_ELSE OP_3 OP_ENDIF", chronicle()).unwrap(), vec![vec![2_u8]]);
+        assert_eq!(run_spend("01000000 OP_VERIF OP_2 OP_ELSE OP_3 OP_ENDIF", chronicle()).unwrap(), vec![vec![3_u8]]);
+        assert_eq!(run_spend("01000000 OP_VERNOTIF OP_2 OP_ELSE OP_3 OP_ENDIF", chronicle()).unwrap(), vec![vec![2_u8]]);
+        // Unexecuted branches are skipped like OP_IF
+        assert_eq!(run_spend("OP_0 OP_IF OP_VERIF OP_ENDIF OP_ENDIF OP_1", chronicle()).unwrap(), vec![vec![1_u8]]);
+
+        assert!(matches!(run("OP_VER", chronicle()), Err(InterpreterError::RequiresTransaction(OpCodes::OP_VER))));
+        assert!(matches!(run_spend("OP_VER", ScriptFlags::default()), Err(InterpreterError::DisabledOpCode(OpCodes::OP_VER))));
+        assert!(matches!(
+            run_spend("OP_0 OP_IF OP_VERIF OP_ENDIF OP_ENDIF OP_1", ScriptFlags::default()),
+            Err(InterpreterError::InvalidOpcode(OpCodes::OP_VERIF))
+        ));
+    }
+
+    #[test]
+    fn new_opcodes_are_nops_before_chronicle() {
+        assert_eq!(run("OP_1 OP_SUBSTR OP_LEFT OP_RIGHT OP_LSHIFTNUM OP_RSHIFTNUM", ScriptFlags::default()).unwrap(), vec![vec![1_u8]]);
+        assert!(matches!(
+            run("OP_1 OP_LEFT", ScriptFlags::default() | ScriptFlags::DISCOURAGE_UPGRADABLE_NOPS),
+            Err(InterpreterError::DiscourageUpgradableNops(OpCodes::OP_LEFT))
+        ));
+    }
+
+    #[test]
+    fn asm_round_trip() {
+        let script = Script::from_asm_string("OP_VER OP_SUBSTR OP_LEFT OP_RIGHT OP_LSHIFTNUM OP_RSHIFTNUM OP_2MUL OP_2DIV").unwrap();
+        assert_eq!(script.to_hex(), "62b3b4b5b6b78d8e");
+        // The NOP names are still the ones displayed, so existing ASM round-trips unchanged
+        assert_eq!(script.to_asm_string(), "OP_VER OP_NOP4 OP_NOP5 OP_NOP6 OP_NOP7 OP_NOP8 OP_2MUL OP_2DIV");
+        assert_eq!(Script::from_asm_string("OP_NOP4 OP_NOP8").unwrap().to_asm_string(), "OP_NOP4 OP_NOP8");
+        assert_eq!(OpCodes::OP_NOP5, OpCodes::OP_LEFT);
+        assert_eq!(serde_json::to_string(&OpCodes::OP_LEFT).unwrap(), "\"OP_NOP5\"");
+        assert_eq!(serde_json::from_str::<OpCodes>("\"OP_LEFT\"").unwrap(), OpCodes::OP_NOP5);
+    }
+
+    #[test]
+    fn otda_signatures() {
+        let private_key = PrivateKey::from_wif("L2WAdy8C19GHNtZDSkbsVBJrBaF9XHpPLTgmnc2N5aGyguhJf7zh").unwrap();
+        let locking_script = Script::from_asm_string(&format!("{} OP_CHECKSIG", private_key.to_public_key().unwrap().to_hex().unwrap())).unwrap();
+
+        for sighash in [SigHash::OTDA_InputsOutputs, SigHash::OTDA_Input, SigHash::OTDA_InputsOutput] {
+            let verify = |flags: ScriptFlags| {
+                SyntheticSpend::verify_unlock(&locking_script, 1000, flags, |spend| {
+                    let signature = spend.sign(&private_key, sighash)?;
+                    Script::from_asm_string(&signature.to_hex()?)
+                })
+            };
+
+            assert!(verify(ScriptFlags::consensus(true) | ScriptFlags::UTXO_AFTER_CHRONICLE).is_success());
+            // The CHRONICLE bit is undefined before Chronicle
+            assert!(matches!(verify(ScriptFlags::consensus(true)).into_result(), Err(InterpreterError::SigHashType)));
+        }
+
+        // The signature commits to the algorithm, a BIP143 signature with the CHRONICLE bit added fails
+        let mut spend = SyntheticSpend::new(&locking_script, 1000).unwrap();
+        let mut signature = spend.sign(&private_key, SigHash::InputsOutputs).unwrap().to_bytes().unwrap();
+        *signature.last_mut().unwrap() = SigHash::OTDA_InputsOutputs as u8;
+        spend.set_unlocking_script(&Script::from_asm_string(&hex::encode(signature)).unwrap());
+        assert!(!spend.verify(chronicle()).is_success());
+    }
+
+    #[test]
+    fn chronicle_bit_keeps_bip143_before_chronicle() {
+        let private_key = PrivateKey::from_wif("L2WAdy8C19GHNtZDSkbsVBJrBaF9XHpPLTgmnc2N5aGyguhJf7zh").unwrap();
+        let locking_script = Script::from_asm_string(&format!("{} OP_CHECKSIG", private_key.to_public_key().unwrap().to_hex().unwrap())).unwrap();
+        // Without STRICTENC the CHRONICLE bit is not rejected before Chronicle
+        let before_chronicle = ScriptFlags::UTXO_AFTER_GENESIS | ScriptFlags::SIGHASH_FORKID;
+
+        // Like the node, the bit is ignored and the BIP143 digest signs over the whole sighash byte
+        let mut spend = SyntheticSpend::new(&locking_script, 1000).unwrap();
+        let mut preimage = spend.tx().clone().sighash_preimage(SigHash::InputsOutputs, SyntheticSpend::INPUT_INDEX, &locking_script, 1000).unwrap();
+        let sighash_type_index = preimage.len() - 4;
+        preimage[sighash_type_index] = SigHash::OTDA_InputsOutputs as u8;
+        let mut signature = ECDSA::sign_with_deterministic_k(&private_key, &preimage, SigningHash::Sha256d, true).unwrap().to_der_bytes();
+        signature.push(SigHash::OTDA_InputsOutputs as u8);
+        spend.set_unlocking_script(&Script::from_asm_string(&hex::encode(signature)).unwrap());
+
+        assert!(spend.verify(before_chronicle).is_success());
+        assert!(!spend.verify(chronicle()).is_success());
+
+        // An OTDA signature is only valid once Chronicle is active
+        spend
+            .unlock(|spend| Script::from_asm_string(&spend.sign(&private_key, SigHash::OTDA_InputsOutputs)?.to_hex()?))
+            .unwrap();
+        assert!(!spend.verify(before_chronicle).is_success());
+        assert!(spend.verify(chronicle()).is_success());
+    }
+}
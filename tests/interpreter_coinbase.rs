@@ -0,0 +1,61 @@
+#[cfg(test)]
+mod interpreter_coinbase_tests {
+    use bsv::{Interpreter, InterpreterError, Script, ScriptBit, ScriptFlags, ScriptVerdict, Transaction, TxIn, TxOut};
+
+    fn coinbase_tx(coinbase_script: &str) -> Transaction {
+        let mut tx = Transaction::new(1, 0);
+        tx.add_input(&TxIn::new(&[0; 32], u32::MAX, &Script::from_coinbase_bytes(&hex::decode(coinbase_script).unwrap()).unwrap(), None));
+        tx.add_output(&TxOut::new(625_000_000, &Script::from_asm_string("OP_1").unwrap()));
+        tx
+    }
+
+    #[test]
+    fn coinbase_input_is_not_executed() {
+        // Height 100000 followed by bytes that are not a valid script
+        let tx = coinbase_tx("03a08601ff4d");
+
+        assert!(matches!(tx.verify_input(0, ScriptFlags::default()), ScriptVerdict::Coinbase { height: Some(100_000) }));
+        assert!(matches!(tx.verify_input(0, ScriptFlags::NONE), ScriptVerdict::Coinbase { height: Some(100_000) }));
+
+        let mut interpreter = Interpreter::from_transaction(&tx, 0, ScriptFlags::default()).unwrap();
+        assert!(interpreter.is_coinbase());
+        assert_eq!(interpreter.coinbase_height(), Some(100_000));
+        interpreter.run().unwrap();
+        assert!(interpreter.next().is_none());
+
+        // Parsed from the wire, the coinbase script is kept as a single opaque bit
+        let tx = Transaction::from_bytes(&tx.to_bytes().unwrap()).unwrap();
+        assert!(matches!(tx.verify_input(0, ScriptFlags::default()), ScriptVerdict::Coinbase { height: Some(100_000) }));
+    }
+
+    #[test]
+    fn bip34_height() {
+        let height = |coinbase_script: &str| coinbase_tx(coinbase_script).get_input(0).unwrap().get_bip34_height();
+
+        assert_eq!(height("00"), Some(0));
+        assert_eq!(height("60"), Some(16));
+        assert_eq!(height("0201"), None);
+        assert_eq!(height("0180"), None);
+        assert_eq!(height("04ffffff7f"), Some(i32::MAX as u32));
+        assert_eq!(height("4c"), None);
+        assert_eq!(height(""), None);
+
+        let txin = TxIn::new(&[1; 32], 0, &Script::from_asm_string("OP_1").unwrap(), None);
+        assert_eq!(txin.get_bip34_height(), None);
+    }
+
+    #[test]
+    fn malformed_data_does_not_panic() {
+        let mut interpreter = Interpreter::from_script(&Script::from_asm_string("OP_SIZE").unwrap(), ScriptFlags::default());
+        assert!(matches!(interpreter.run(), Err(InterpreterError::EmptyStack)));
+
+        // Script lengths larger than the data are not allocated up front
+        let mut tx_bytes = hex::decode("0100000001").unwrap();
+        tx_bytes.extend_from_slice(&[0; 36]);
+        tx_bytes.extend_from_slice(&[0xff; 9]);
+        assert!(Transaction::from_bytes(&tx_bytes).is_err());
+
+        let script = Script::from_bytes_lenient(&[0x4e, 0xff, 0xff, 0xff, 0xff, 0xaa]).script;
+        assert_eq!(script.to_script_bits(), vec![ScriptBit::Unparsed(vec![0x4e, 0xff, 0xff, 0xff, 0xff, 0xaa])]);
+    }
+}
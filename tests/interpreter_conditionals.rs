@@ -0,0 +1,93 @@
+mod common;
+
+#[cfg(test)]
+mod interpreter_conditionals_tests {
+    use crate::common::run;
+    use bsv::{ExecutionPhase, Interpreter, InterpreterError, OpCodes, Script, ScriptBit, ScriptFlags, ScriptVerdict, Transaction, TxIn};
+
+    /// Builds a script from opcodes without nesting If statements, so unbalanced conditionals can be represented.
+    fn flat_script(opcodes: &[OpCodes]) -> Script {
+        Script::from_script_bits(opcodes.iter().map(|x| ScriptBit::OpCode(*x)).collect())
+    }
+
+    #[test]
+    fn notif_executes_on_false() {
+        assert_eq!(run("OP_0 OP_NOTIF OP_2 OP_ELSE OP_3 OP_ENDIF", ScriptFlags::default()).unwrap(), vec![vec![2_u8]]);
+        assert_eq!(run("OP_1 OP_NOTIF OP_2 OP_ELSE OP_3 OP_ENDIF", ScriptFlags::default()).unwrap(), vec![vec![3_u8]]);
+    }
+
+    #[test]
+    fn nested_conditionals() {
+        let asm = "OP_1 OP_IF OP_0 OP_IF OP_2 OP_ELSE OP_3 OP_ENDIF OP_ELSE OP_0 OP_IF OP_4 OP_ELSE OP_5 OP_ENDIF OP_ENDIF";
+
+        assert_eq!(run(asm, ScriptFlags::default()).unwrap(), vec![vec![3_u8]]);
+    }
+
+    #[test]
+    fn multiple_else_toggles_branch_before_genesis() {
+        let asm = "OP_1 OP_IF OP_2 OP_ELSE OP_3 OP_ELSE OP_4 OP_ENDIF";
+
+        assert_eq!(run(asm, ScriptFlags::NONE).unwrap(), vec![vec![2_u8], vec![4_u8]]);
+        assert!(matches!(run(asm, ScriptFlags::UTXO_AFTER_GENESIS), Err(InterpreterError::UnbalancedConditional)));
+    }
+
+    #[test]
+    fn verif_fails_in_unexecuted_branch() {
+        for opcode in [OpCodes::OP_VERIF, OpCodes::OP_VERNOTIF] {
+            let script = flat_script(&[OpCodes::OP_0, OpCodes::OP_IF, opcode, OpCodes::OP_ENDIF, OpCodes::OP_1]);
+            let mut interpreter = Interpreter::from_script(&script, ScriptFlags::default());
+            assert!(matches!(interpreter.run(), Err(InterpreterError::InvalidOpcode(x)) if x == opcode));
+        }
+
+        // Other invalid opcodes only fail when executed
+        assert_eq!(run("OP_0 OP_IF OP_RESERVED OP_RETURN OP_ENDIF OP_1", ScriptFlags::NONE).unwrap(), vec![vec![1_u8]]);
+    }
+
+    #[test]
+    fn unbalanced_conditionals_fail() {
+        for opcodes in [&[OpCodes::OP_1, OpCodes::OP_IF][..], &[OpCodes::OP_ENDIF], &[OpCodes::OP_ELSE], &[OpCodes::OP_IF, OpCodes::OP_ENDIF]] {
+            let mut interpreter = Interpreter::from_script(&flat_script(opcodes), ScriptFlags::default());
+            assert!(matches!(interpreter.run(), Err(InterpreterError::UnbalancedConditional)));
+        }
+    }
+
+    #[test]
+    fn unlocking_script_cannot_leave_conditional_open() {
+        let mut tx = Transaction::new(2, 0);
+        let mut txin = TxIn::default();
+        txin.set_satoshis(0);
+        txin.set_unlocking_script(&flat_script(&[OpCodes::OP_1, OpCodes::OP_IF]));
+        txin.set_locking_script(&flat_script(&[OpCodes::OP_ENDIF, OpCodes::OP_1]));
+        tx.add_input(&txin);
+
+        let mut interpreter = Interpreter::from_transaction(&tx, 0, ScriptFlags::NONE).unwrap();
+        assert!(matches!(interpreter.run(), Err(InterpreterError::UnbalancedConditional)));
+        assert_eq!(interpreter.failure().unwrap().phase, ExecutionPhase::Unlocking);
+    }
+
+    #[test]
+    fn top_level_return_ignores_rest_of_script_post_genesis() {
+        let script = flat_script(&[OpCodes::OP_1, OpCodes::OP_RETURN, OpCodes::OP_IF, OpCodes::OP_ELSE, OpCodes::OP_INVALIDOPCODE]);
+
+        let mut interpreter = Interpreter::from_script(&script, ScriptFlags::UTXO_AFTER_GENESIS);
+        assert!(matches!(interpreter.verify(), ScriptVerdict::EarlyReturn));
+    }
+
+    #[test]
+    fn nested_return_still_requires_balanced_conditionals() {
+        let script = flat_script(&[OpCodes::OP_1, OpCodes::OP_1, OpCodes::OP_IF, OpCodes::OP_RETURN, OpCodes::OP_ENDIF, OpCodes::OP_0]);
+        let mut interpreter = Interpreter::from_script(&script, ScriptFlags::UTXO_AFTER_GENESIS);
+        assert!(matches!(interpreter.verify(), ScriptVerdict::EarlyReturn));
+        // Nothing after the OP_RETURN is executed
+        assert_eq!(interpreter.state().stack(), &[vec![1_u8]]);
+
+        let script = flat_script(&[OpCodes::OP_1, OpCodes::OP_1, OpCodes::OP_IF, OpCodes::OP_RETURN]);
+        let mut interpreter = Interpreter::from_script(&script, ScriptFlags::UTXO_AFTER_GENESIS);
+        assert!(matches!(interpreter.verify(), ScriptVerdict::Failure(InterpreterError::UnbalancedConditional)));
+    }
+
+    #[test]
+    fn if_requires_a_condition() {
+        assert!(matches!(run("OP_IF OP_ENDIF", ScriptFlags::default()), Err(InterpreterError::UnbalancedConditional)));
+    }
+}
@@ -0,0 +1,79 @@
+mod common;
+
+#[cfg(test)]
+mod interpreter_coverage_tests {
+    use std::sync::{Arc, Mutex};
+
+    use bsv::{BranchCoverage, CoverageCollector, ExecutionPhase, Interpreter, OpCodes, Script, ScriptFlags};
+
+    use crate::common;
+
+    const CONTRACT: &str = "OP_IF OP_2 OP_ELSE OP_3 OP_ENDIF OP_DROP OP_1";
+
+    fn spend(unlocking_script: &str, collector: &Arc<Mutex<CoverageCollector>>) {
+        let tx = common::spending_tx(unlocking_script, CONTRACT);
+        let mut interpreter = Interpreter::from_transaction(&tx, 0, ScriptFlags::default()).unwrap();
+        interpreter.set_observer(collector.clone());
+        assert!(interpreter.verify().is_success());
+    }
+
+    #[test]
+    fn records_hits_and_branch_outcomes() {
+        let contract = Script::from_asm_string(CONTRACT).unwrap();
+        let collector = Arc::new(Mutex::new(CoverageCollector::new()));
+
+        spend("OP_1", &collector);
+        let report = collector.lock().unwrap().report(&contract, ExecutionPhase::Locking);
+        let hits: Vec<usize> = report.entries.iter().map(|x| x.hits).collect();
+        assert_eq!(hits, vec![1, 1, 1, 0, 1, 1, 1]);
+        assert_eq!(report.entries[0].branch, Some(BranchCoverage { taken: 1, not_taken: 0 }));
+        assert_eq!(report.branch_coverage(), 0.5);
+
+        let uncovered: Vec<(usize, Option<OpCodes>)> = report.uncovered().iter().map(|x| (x.position.index, x.opcode)).collect();
+        assert_eq!(uncovered, vec![(0, Some(OpCodes::OP_IF)), (3, Some(OpCodes::OP_3))]);
+
+        spend("OP_0", &collector);
+        spend("OP_0", &collector);
+        let report = collector.lock().unwrap().report(&contract, ExecutionPhase::Locking);
+        assert_eq!(report.entries[0].branch, Some(BranchCoverage { taken: 1, not_taken: 2 }));
+        assert_eq!(report.entries[3].hits, 2);
+        assert_eq!(report.opcode_coverage(), 1.0);
+        assert_eq!(report.branch_coverage(), 1.0);
+        assert!(report.uncovered().is_empty());
+    }
+
+    #[test]
+    fn merge_runs() {
+        let contract = Script::from_asm_string(CONTRACT).unwrap();
+        let (taken, not_taken) = (Arc::new(Mutex::new(CoverageCollector::new())), Arc::new(Mutex::new(CoverageCollector::new())));
+        spend("OP_1", &taken);
+        spend("OP_0", &not_taken);
+
+        // Collectors can be saved by separate test runs and merged later
+        let saved = not_taken.lock().unwrap().to_json_string().unwrap();
+        let mut merged = taken.lock().unwrap().clone();
+        merged.merge(&CoverageCollector::from_json_string(&saved).unwrap());
+
+        let report = merged.report(&contract, ExecutionPhase::Locking);
+        assert!(report.uncovered().is_empty());
+        assert_eq!(report.entries[5].hits, 2);
+        assert_eq!(merged.unlocking.hits.values().sum::<usize>(), 2);
+    }
+
+    #[test]
+    fn lcov_report() {
+        let contract = Script::from_asm_string(CONTRACT).unwrap();
+        let collector = Arc::new(Mutex::new(CoverageCollector::new()));
+        spend("OP_1", &collector);
+
+        let lcov = collector.lock().unwrap().report(&contract, ExecutionPhase::Locking).to_lcov("contract.scrypt");
+        assert!(lcov.starts_with("TN:\nSF:contract.scrypt\n"));
+        assert!(lcov.contains("BRDA:1,0,0,1\nBRDA:1,0,1,0\nBRF:2\nBRH:1\n"));
+        assert!(lcov.contains("DA:3,1\nDA:4,0\nDA:5,1\n"));
+        assert!(lcov.ends_with("LF:7\nLH:6\nend_of_record\n"));
+
+        // Conditionals that are never reached have no outcomes
+        let lcov = CoverageCollector::new().report(&contract, ExecutionPhase::Locking).to_lcov("contract.scrypt");
+        assert!(lcov.contains("BRDA:1,0,0,-\nBRDA:1,0,1,-\n"));
+    }
+}
@@ -0,0 +1,129 @@
+mod common;
+
+#[cfg(test)]
+mod interpreter_debugger_tests {
+    use crate::common::spending_tx;
+    use bsv::{Breakpoint, DebuggerStop, ExecutionPhase, Interpreter, OpCodes, Script, ScriptDebugger, ScriptFlags};
+
+    fn debugger(asm: &str) -> ScriptDebugger {
+        ScriptDebugger::new(Interpreter::from_script(&Script::from_asm_string(asm).unwrap(), ScriptFlags::default()))
+    }
+
+    #[test]
+    fn step_back_restores_earlier_state() {
+        let mut debugger = debugger("OP_1 OP_IF OP_2 OP_ELSE OP_3 OP_ENDIF OP_4");
+
+        debugger.step().unwrap().unwrap();
+        debugger.step().unwrap().unwrap();
+        debugger.step().unwrap().unwrap();
+        assert_eq!(debugger.state().stack(), &[vec![2_u8]]);
+
+        assert!(debugger.step_back());
+        assert!(debugger.step_back());
+        assert_eq!(debugger.state().stack(), &[vec![1_u8]]);
+        assert_eq!(debugger.position().unwrap().index, 1);
+
+        // Stepping forward again takes the same branch, every opcode is a step including those in the unexecuted branch
+        while let Some(state) = debugger.step() {
+            state.unwrap();
+        }
+        assert_eq!(debugger.state().stack(), &[vec![2_u8], vec![4_u8]]);
+        assert_eq!(debugger.steps_taken(), 7);
+
+        while debugger.step_back() {}
+        assert!(debugger.state().stack().is_empty());
+    }
+
+    #[test]
+    fn index_breakpoint_maps_to_original_script() {
+        let mut debugger = debugger("OP_1 OP_IF OP_2 OP_ELSE OP_3 OP_ENDIF OP_4");
+
+        // OP_1 OP_IF OP_2 OP_ELSE OP_3 OP_ENDIF [OP_4]
+        let id = debugger.add_breakpoint(Breakpoint::Index {
+            phase: ExecutionPhase::Locking,
+            index: 6,
+        });
+
+        assert_eq!(debugger.resume().unwrap(), DebuggerStop::Breakpoint(id));
+        let position = debugger.position().unwrap();
+        assert_eq!(position.index, 6);
+        assert_eq!(position.byte_offset, 6);
+        assert_eq!(debugger.state().stack(), &[vec![2_u8]]);
+
+        assert_eq!(debugger.resume().unwrap(), DebuggerStop::Finished);
+        assert_eq!(debugger.position(), None);
+    }
+
+    #[test]
+    fn opcode_breakpoint_across_scripts() {
+        let tx = spending_tx("OP_2 OP_3", "OP_ADD OP_5 OP_EQUAL");
+        let mut debugger = ScriptDebugger::new(Interpreter::from_transaction(&tx, 0, ScriptFlags::default()).unwrap());
+
+        let add = debugger.add_breakpoint(Breakpoint::OpCode(OpCodes::OP_ADD));
+        let equal = debugger.add_breakpoint(Breakpoint::OpCode(OpCodes::OP_EQUAL));
+
+        assert_eq!(debugger.resume().unwrap(), DebuggerStop::Breakpoint(add));
+        assert_eq!(debugger.position().unwrap().phase, ExecutionPhase::Locking);
+        assert_eq!(debugger.position().unwrap().index, 0);
+        assert_eq!(debugger.state().stack(), &[vec![2_u8], vec![3_u8]]);
+
+        assert_eq!(debugger.resume().unwrap(), DebuggerStop::Breakpoint(equal));
+        assert_eq!(debugger.state().stack(), &[vec![5_u8], vec![5_u8]]);
+
+        assert!(debugger.remove_breakpoint(add));
+        assert!(!debugger.remove_breakpoint(add));
+    }
+
+    #[test]
+    fn step_back_into_unlocking_script() {
+        // Pre-Genesis so the unlocking script can use the alt stack
+        let tx = spending_tx("OP_2 OP_3 OP_TOALTSTACK", "OP_DUP OP_ADD OP_4 OP_EQUAL");
+        let mut debugger = ScriptDebugger::new(Interpreter::from_transaction(&tx, 0, ScriptFlags::NONE).unwrap());
+
+        for _ in 0..4 {
+            debugger.step().unwrap().unwrap();
+        }
+        assert_eq!(debugger.interpreter().phase(), ExecutionPhase::Locking);
+        assert_eq!(debugger.state().stack(), &[vec![2_u8], vec![2_u8]]);
+
+        // The alt stack is cleared between scripts and comes back with the unlocking script
+        assert!(debugger.step_back());
+        assert_eq!(debugger.interpreter().phase(), ExecutionPhase::Unlocking);
+        assert_eq!(debugger.position().unwrap().phase, ExecutionPhase::Locking);
+        assert_eq!(debugger.state().stack(), &[vec![2_u8]]);
+        assert_eq!(debugger.state().alt_stack.to_vec(), vec![vec![3_u8]]);
+
+        assert!(debugger.step_back());
+        assert_eq!(debugger.position().unwrap().phase, ExecutionPhase::Unlocking);
+        assert_eq!(debugger.position().unwrap().index, 2);
+
+        // Stepping forward again gives the same result
+        assert_eq!(debugger.resume().unwrap(), DebuggerStop::Finished);
+        assert_eq!(debugger.state().stack(), &[vec![1_u8]]);
+    }
+
+    #[test]
+    fn stack_breakpoint_and_run_until() {
+        let mut debugger = debugger("OP_1 OP_2 OP_ADD OP_4 OP_ADD OP_DROP");
+
+        debugger.add_breakpoint(Breakpoint::Stack(Box::new(|stack| stack.last() == Some(&vec![3_u8]))));
+        assert!(matches!(debugger.resume().unwrap(), DebuggerStop::Breakpoint(_)));
+        assert_eq!(debugger.steps_taken(), 3);
+
+        debugger.clear_breakpoints();
+        assert!(debugger.run_until(|state| state.stack().last() == Some(&vec![7_u8])).unwrap());
+        assert!(!debugger.run_until(|state| state.stack().len() > 1).unwrap());
+    }
+
+    #[test]
+    fn step_back_after_failure() {
+        let mut debugger = debugger("OP_1 OP_0 OP_VERIFY");
+
+        assert!(debugger.resume().is_err());
+        assert_eq!(debugger.failure().unwrap().opcode, Some(OpCodes::OP_VERIFY));
+
+        assert!(debugger.step_back());
+        assert_eq!(debugger.state().stack(), &[vec![1_u8], vec![]]);
+        assert_eq!(debugger.position().unwrap().index, 2);
+    }
+}
@@ -0,0 +1,223 @@
+mod common;
+
+#[cfg(test)]
+mod interpreter_execution_tests {
+    use crate::common::spending_tx;
+    use bsv::{ExecutionPhase, Interpreter, InterpreterError, OpCodes, Script, ScriptFlags, ScriptVerdict, Transaction, TxIn};
+
+    #[test]
+    fn stack_carries_over_to_locking_script() {
+        let tx = spending_tx("OP_2 OP_3", "OP_ADD OP_5 OP_EQUAL");
+
+        let mut interpreter = Interpreter::from_transaction(&tx, 0, ScriptFlags::default()).unwrap();
+        assert_eq!(interpreter.phase(), ExecutionPhase::Unlocking);
+
+        interpreter.next();
+        interpreter.next();
+        assert_eq!(interpreter.state().stack(), &[vec![2_u8], vec![3_u8]]);
+
+        interpreter.run().unwrap();
+        assert_eq!(interpreter.phase(), ExecutionPhase::Locking);
+        assert_eq!(interpreter.state().stack(), &[vec![1_u8]]);
+    }
+
+    #[test]
+    fn alt_stack_is_cleared_between_scripts() {
+        let tx = spending_tx("OP_1 OP_TOALTSTACK", "OP_FROMALTSTACK");
+
+        let mut interpreter = Interpreter::from_transaction(&tx, 0, ScriptFlags::NONE).unwrap();
+        assert!(matches!(interpreter.run(), Err(InterpreterError::EmptyStack)));
+    }
+
+    #[test]
+    fn verify_input_succeeds_with_true_on_top() {
+        let tx = spending_tx("OP_2 OP_3", "OP_ADD OP_5 OP_EQUAL");
+
+        assert!(matches!(tx.verify_input(0, ScriptFlags::default()), ScriptVerdict::Success));
+    }
+
+    #[test]
+    fn verify_fails_when_run_does_not() {
+        let tx = spending_tx("OP_2 OP_3", "OP_ADD OP_6 OP_EQUAL");
+
+        let mut interpreter = Interpreter::from_transaction(&tx, 0, ScriptFlags::default()).unwrap();
+        interpreter.run().unwrap();
+
+        let verdict = tx.verify_input(0, ScriptFlags::default());
+        assert!(!verdict.is_success());
+        assert!(matches!(verdict.error(), Some(InterpreterError::EvalFalse)));
+    }
+
+    #[test]
+    fn verify_fails_on_empty_stack() {
+        let mut interpreter = Interpreter::from_script(&Script::from_asm_string("OP_1 OP_DROP").unwrap(), ScriptFlags::default());
+
+        assert!(matches!(interpreter.verify(), ScriptVerdict::Failure(InterpreterError::EvalFalse)));
+    }
+
+    #[test]
+    fn verify_treats_negative_zero_as_false() {
+        let mut interpreter = Interpreter::from_script(&Script::from_asm_string("80").unwrap(), ScriptFlags::default());
+        assert!(matches!(interpreter.verify(), ScriptVerdict::Failure(InterpreterError::EvalFalse)));
+
+        let mut interpreter = Interpreter::from_script(&Script::from_asm_string("000000000000000001").unwrap(), ScriptFlags::default());
+        assert!(interpreter.verify().is_success());
+    }
+
+    #[test]
+    fn verify_succeeds_on_early_return_post_genesis() {
+        let script = Script::from_asm_string("OP_1 OP_RETURN OP_0").unwrap();
+
+        let mut interpreter = Interpreter::from_script(&script, ScriptFlags::UTXO_AFTER_GENESIS);
+        assert!(matches!(interpreter.verify(), ScriptVerdict::EarlyReturn));
+
+        let mut interpreter = Interpreter::from_script(&Script::from_asm_string("OP_0 OP_RETURN OP_1").unwrap(), ScriptFlags::UTXO_AFTER_GENESIS);
+        assert!(matches!(interpreter.verify(), ScriptVerdict::Failure(InterpreterError::EvalFalse)));
+
+        let mut interpreter = Interpreter::from_script(&script, ScriptFlags::NONE);
+        assert!(matches!(interpreter.verify(), ScriptVerdict::Failure(InterpreterError::OpReturn)));
+    }
+
+    #[test]
+    fn verify_applies_clean_stack() {
+        let tx = spending_tx("OP_1 OP_1", "OP_1");
+
+        assert!(tx.verify_input(0, ScriptFlags::default()).is_success());
+        assert!(matches!(
+            tx.verify_input(0, ScriptFlags::default() | ScriptFlags::CLEANSTACK).into_result(),
+            Err(InterpreterError::CleanStack)
+        ));
+    }
+
+    #[test]
+    fn verify_input_fails_on_missing_input() {
+        let tx = spending_tx("OP_1", "OP_1");
+
+        assert!(matches!(tx.verify_input(1, ScriptFlags::default()), ScriptVerdict::Failure(InterpreterError::NoTxInProvided)));
+    }
+
+    #[test]
+    fn verify_input_fails_on_missing_locking_script() {
+        let mut tx = Transaction::new(2, 0);
+        let mut txin = TxIn::default();
+        txin.set_satoshis(0);
+        txin.set_unlocking_script(&Script::from_asm_string("OP_1").unwrap());
+        tx.add_input(&txin);
+
+        assert!(matches!(tx.verify_input(0, ScriptFlags::default()), ScriptVerdict::Failure(InterpreterError::MissingLockingScript)));
+    }
+
+    #[test]
+    fn failure_reports_position_in_locking_script() {
+        let tx = spending_tx("OP_2 OP_3", "OP_ADD OP_6 OP_EQUALVERIFY OP_1");
+
+        let mut interpreter = Interpreter::from_transaction(&tx, 0, ScriptFlags::default()).unwrap();
+        assert!(matches!(interpreter.run(), Err(InterpreterError::VerifyFailed)));
+
+        let failure = interpreter.failure().unwrap();
+        assert_eq!(failure.phase, ExecutionPhase::Locking);
+        assert_eq!(failure.script_index, 2);
+        assert_eq!(failure.byte_offset, 2);
+        assert_eq!(failure.opcode, Some(OpCodes::OP_EQUALVERIFY));
+        assert_eq!(failure.stack, vec![vec![5_u8], vec![6_u8]]);
+        assert_eq!(failure.code, "VERIFY");
+    }
+
+    #[test]
+    fn failure_byte_offset_refers_to_original_script_inside_if() {
+        let script = Script::from_asm_string("OP_0 OP_IF OP_1 OP_ELSE 0101 OP_0 OP_VERIFY OP_ENDIF").unwrap();
+
+        let mut interpreter = Interpreter::from_script(&script, ScriptFlags::default());
+        assert!(interpreter.run().is_err());
+
+        let failure = interpreter.failure().unwrap();
+        assert_eq!(failure.script_index, 6);
+        // OP_0 OP_IF OP_1 OP_ELSE [02 01 01] OP_0 OP_VERIFY
+        assert_eq!(failure.byte_offset, 8);
+        assert_eq!(failure.opcode, Some(OpCodes::OP_VERIFY));
+        assert_eq!(failure.stack, vec![vec![1_u8, 1], vec![]]);
+    }
+
+    #[test]
+    fn failure_after_script_finished() {
+        let mut interpreter = Interpreter::from_script(&Script::from_asm_string("OP_1 OP_NOT").unwrap(), ScriptFlags::default());
+        assert!(!interpreter.verify().is_success());
+
+        let failure = interpreter.failure().unwrap();
+        assert_eq!(failure.script_index, 2);
+        assert_eq!(failure.byte_offset, 2);
+        assert_eq!(failure.opcode, None);
+        assert_eq!(failure.code, "EVAL_FALSE");
+    }
+
+    #[test]
+    fn stack_opcodes_keep_element_order() {
+        let run = |asm: &str| {
+            let mut interpreter = Interpreter::from_script(&Script::from_asm_string(asm).unwrap(), ScriptFlags::default());
+            interpreter.run().map(|_| interpreter.state().stack().iter().map(|x| x[0]).collect::<Vec<u8>>())
+        };
+
+        assert_eq!(run("OP_1 OP_2 OP_2DUP").unwrap(), vec![1, 2, 1, 2]);
+        assert_eq!(run("OP_1 OP_2 OP_3 OP_3DUP").unwrap(), vec![1, 2, 3, 1, 2, 3]);
+        assert_eq!(run("OP_1 OP_2 OP_3 OP_4 OP_2SWAP").unwrap(), vec![3, 4, 1, 2]);
+        assert_eq!(run("OP_1 OP_2 OP_3 OP_4 OP_2OVER").unwrap(), vec![1, 2, 3, 4, 1, 2]);
+        assert_eq!(run("OP_1 OP_2 OP_3 OP_4 OP_5 OP_6 OP_2ROT").unwrap(), vec![3, 4, 5, 6, 1, 2]);
+        assert_eq!(run("OP_1 OP_2 OP_TUCK").unwrap(), vec![2, 1, 2]);
+        assert_eq!(run("OP_1 OP_IFDUP").unwrap(), vec![1, 1]);
+        // A false value is left alone, neither duplicated nor popped
+        assert_eq!(run("OP_0 OP_IFDUP OP_DEPTH OP_NIP").unwrap(), vec![1]);
+
+        // Too few elements is a script error rather than a panic
+        for asm in [
+            "OP_1 OP_NIP",
+            "OP_1 OP_OVER",
+            "OP_1 OP_2 OP_ROT",
+            "OP_1 OP_SWAP",
+            "OP_1 OP_TUCK",
+            "OP_1 OP_2DUP",
+            "OP_1 OP_2 OP_3 OP_2OVER",
+            "OP_1 OP_2ROT",
+        ] {
+            assert!(matches!(run(asm), Err(InterpreterError::EmptyStack)), "{}", asm);
+        }
+    }
+
+    #[test]
+    fn cat_appends_the_top_element_to_the_one_below() {
+        let mut interpreter = Interpreter::from_script(&Script::from_asm_string("01 02 OP_CAT").unwrap(), ScriptFlags::default());
+        interpreter.run().unwrap();
+        assert_eq!(interpreter.stack(), &[vec![1_u8, 2]]);
+
+        let mut interpreter = Interpreter::from_script(&Script::from_asm_string("0102 0304 OP_CAT 02 OP_SPLIT").unwrap(), ScriptFlags::default());
+        interpreter.run().unwrap();
+        assert_eq!(interpreter.stack(), &[vec![1_u8, 2], vec![3, 4]]);
+    }
+
+    #[test]
+    fn failure_reports_stacks_from_before_the_failing_opcode() {
+        let tx = spending_tx("OP_2 OP_3", "OP_TOALTSTACK OP_DUP OP_CAT OP_5 OP_SPLIT");
+
+        let mut interpreter = Interpreter::from_transaction(&tx, 0, ScriptFlags::default()).unwrap();
+        assert!(matches!(interpreter.run(), Err(InterpreterError::InvalidSplitRange)));
+
+        let failure = interpreter.failure().unwrap();
+        assert_eq!(failure.script_index, 4);
+        assert_eq!(failure.stack, vec![vec![2_u8, 2], vec![5]]);
+        assert_eq!(failure.alt_stack, vec![vec![3_u8]]);
+        assert_eq!(interpreter.stack(), &[vec![2_u8, 2], vec![5]]);
+    }
+
+    #[test]
+    fn executed_opcodes_are_opt_in() {
+        let script = Script::from_asm_string("OP_1 ff OP_DROP").unwrap();
+
+        let mut interpreter = Interpreter::from_script(&script, ScriptFlags::default());
+        interpreter.run().unwrap();
+        assert!(interpreter.state().executed_opcodes.is_empty());
+
+        let mut interpreter = Interpreter::from_script(&script, ScriptFlags::default());
+        interpreter.set_record_executed_opcodes(true);
+        interpreter.run().unwrap();
+        assert_eq!(interpreter.state().executed_opcodes, vec![OpCodes::OP_1, OpCodes::OP_DATA, OpCodes::OP_DROP]);
+    }
+}
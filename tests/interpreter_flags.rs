@@ -0,0 +1,217 @@
+#[cfg(test)]
+mod interpreter_flags_tests {
+    use bsv::{Hash, Interpreter, InterpreterError, PrivateKey, Script, ScriptFlags, SigHash, Transaction, TxIn};
+    use num_bigint::BigUint;
+
+    fn p2pkh_tx(sighash: SigHash, unlocking_prefix: &str) -> Transaction {
+        let private_key = PrivateKey::from_wif("L2WAdy8C19GHNtZDSkbsVBJrBaF9XHpPLTgmnc2N5aGyguhJf7zh").unwrap();
+        let pubkey = private_key.to_public_key().unwrap();
+        let mut tx = Transaction::new(2, 0);
+
+        let locking_script = Script::from_asm_string(&format!("OP_DUP OP_HASH160 {} OP_EQUALVERIFY OP_CHECKSIG", Hash::hash_160(&pubkey.to_bytes().unwrap()).to_hex())).unwrap();
+
+        let mut txin = TxIn::default();
+        txin.set_satoshis(0);
+        txin.set_locking_script(&locking_script);
+        tx.add_input(&txin);
+
+        let signature = tx.sign(&private_key, sighash, 0, &locking_script, 0).unwrap();
+        let script = Script::from_asm_string(&format!("{} {} {}", unlocking_prefix, signature.to_hex().unwrap(), pubkey.to_hex().unwrap())).unwrap();
+
+        txin.set_unlocking_script(&script);
+        tx.set_input(0, &txin);
+        tx
+    }
+
+    /// Replaces S with N - S, which is an equally valid but high S signature.
+    fn to_high_s(sig: &[u8]) -> Vec<u8> {
+        let curve_order = BigUint::parse_bytes(b"FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEBAAEDCE6AF48A03BBFD25E8CD0364141", 16).unwrap();
+        let len_r = sig[3] as usize;
+        let r = &sig[4..4 + len_r];
+        let len_s = sig[5 + len_r] as usize;
+        let s = BigUint::from_bytes_be(&sig[6 + len_r..6 + len_r + len_s]);
+
+        let mut high_s = (curve_order - s).to_bytes_be();
+        if high_s[0] & 0x80 != 0 {
+            high_s.insert(0, 0);
+        }
+
+        let mut der = vec![0x02, r.len() as u8];
+        der.extend_from_slice(r);
+        der.extend_from_slice(&[0x02, high_s.len() as u8]);
+        der.extend_from_slice(&high_s);
+        der.insert(0, der.len() as u8);
+        der.insert(0, 0x30);
+        der.push(*sig.last().unwrap());
+        der
+    }
+
+    #[test]
+    fn default_flags_are_post_genesis() {
+        assert_eq!(ScriptFlags::default(), ScriptFlags::UTXO_AFTER_GENESIS);
+        assert!(ScriptFlags::standard(true).contains(ScriptFlags::MANDATORY | ScriptFlags::UTXO_AFTER_GENESIS));
+        assert!(!ScriptFlags::consensus(false).contains(ScriptFlags::UTXO_AFTER_GENESIS));
+    }
+
+    #[test]
+    fn minimaldata_rejects_non_minimal_push() {
+        let script = Script::from_asm_string("01").unwrap();
+
+        let mut interpreter = Interpreter::from_script(&script, ScriptFlags::default());
+        interpreter.run().unwrap();
+
+        let mut interpreter = Interpreter::from_script(&script, ScriptFlags::default() | ScriptFlags::MINIMALDATA);
+        assert!(matches!(interpreter.run(), Err(InterpreterError::MinimalData)));
+    }
+
+    #[test]
+    fn minimaldata_rejects_non_minimal_number() {
+        let script = Script::from_asm_string("0500 OP_1ADD").unwrap();
+
+        let mut interpreter = Interpreter::from_script(&script, ScriptFlags::default());
+        interpreter.run().unwrap();
+        assert_eq!(interpreter.state().stack().last().unwrap(), &vec![6_u8]);
+
+        let mut interpreter = Interpreter::from_script(&script, ScriptFlags::default() | ScriptFlags::MINIMALDATA);
+        assert!(matches!(interpreter.run(), Err(InterpreterError::MinimalData)));
+    }
+
+    #[test]
+    fn op_return_fails_pre_genesis() {
+        let script = Script::from_asm_string("OP_1 OP_RETURN").unwrap();
+
+        let mut interpreter = Interpreter::from_script(&script, ScriptFlags::NONE);
+        assert!(matches!(interpreter.run(), Err(InterpreterError::OpReturn)));
+    }
+
+    #[test]
+    fn op_return_stops_execution_post_genesis() {
+        let script = Script::from_asm_string("OP_1 OP_RETURN OP_0 OP_VERIFY").unwrap();
+
+        let mut interpreter = Interpreter::from_script(&script, ScriptFlags::UTXO_AFTER_GENESIS);
+        interpreter.run().unwrap();
+
+        assert_eq!(interpreter.state().stack(), &[vec![1_u8]]);
+    }
+
+    #[test]
+    fn discourage_upgradable_nops() {
+        let script = Script::from_asm_string("OP_NOP4 OP_1").unwrap();
+
+        let mut interpreter = Interpreter::from_script(&script, ScriptFlags::default());
+        interpreter.run().unwrap();
+
+        let mut interpreter = Interpreter::from_script(&script, ScriptFlags::default() | ScriptFlags::DISCOURAGE_UPGRADABLE_NOPS);
+        assert!(matches!(interpreter.run(), Err(InterpreterError::DiscourageUpgradableNops(_))));
+    }
+
+    #[test]
+    fn cleanstack_requires_single_element() {
+        let script = Script::from_asm_string("OP_1 OP_1").unwrap();
+
+        let mut interpreter = Interpreter::from_script(&script, ScriptFlags::default());
+        interpreter.run().unwrap();
+
+        let mut interpreter = Interpreter::from_script(&script, ScriptFlags::default() | ScriptFlags::CLEANSTACK);
+        assert!(matches!(interpreter.run(), Err(InterpreterError::CleanStack)));
+    }
+
+    #[test]
+    fn minimalif_rejects_non_minimal_condition() {
+        let script = Script::from_asm_string("OP_2 OP_IF OP_1 OP_ENDIF").unwrap();
+
+        let mut interpreter = Interpreter::from_script(&script, ScriptFlags::default());
+        interpreter.run().unwrap();
+
+        let mut interpreter = Interpreter::from_script(&script, ScriptFlags::default() | ScriptFlags::MINIMALIF);
+        assert!(matches!(interpreter.run(), Err(InterpreterError::MinimalIf)));
+    }
+
+    #[test]
+    fn sigpushonly_rejects_unlocking_opcodes() {
+        let tx = p2pkh_tx(SigHash::InputsOutputs, "OP_1 OP_DROP");
+
+        assert!(Interpreter::from_transaction(&tx, 0, ScriptFlags::NONE).is_ok());
+        assert!(matches!(Interpreter::from_transaction(&tx, 0, ScriptFlags::SIGPUSHONLY), Err(InterpreterError::SigPushOnly)));
+        // Unlocking scripts are always push only after Genesis
+        assert!(matches!(Interpreter::from_transaction(&tx, 0, ScriptFlags::UTXO_AFTER_GENESIS), Err(InterpreterError::SigPushOnly)));
+    }
+
+    #[test]
+    fn forkid_flag_requires_forkid_signature() {
+        let flags = ScriptFlags::default() | ScriptFlags::STRICTENC | ScriptFlags::SIGHASH_FORKID;
+
+        let tx = p2pkh_tx(SigHash::InputsOutputs, "");
+        let mut interpreter = Interpreter::from_transaction(&tx, 0, flags).unwrap();
+        interpreter.run().unwrap();
+        assert_eq!(interpreter.state().stack().last().unwrap(), &vec![1_u8]);
+
+        let tx = p2pkh_tx(SigHash::ALL, "");
+        let mut interpreter = Interpreter::from_transaction(&tx, 0, flags).unwrap();
+        assert!(matches!(interpreter.run(), Err(InterpreterError::MustUseForkId)));
+
+        let tx = p2pkh_tx(SigHash::InputsOutputs, "");
+        let mut interpreter = Interpreter::from_transaction(&tx, 0, ScriptFlags::default() | ScriptFlags::STRICTENC).unwrap();
+        assert!(matches!(interpreter.run(), Err(InterpreterError::IllegalForkId)));
+    }
+
+    #[test]
+    fn low_s_rejects_high_s_signature() {
+        let tx = p2pkh_tx(SigHash::InputsOutputs, "");
+        let mut txin = tx.get_input(0).unwrap();
+        let unlocking_bits = txin.get_unlocking_script().to_script_bits();
+        let (sig, pubkey) = match (&unlocking_bits[0], &unlocking_bits[1]) {
+            (bsv::ScriptBit::Push(sig), bsv::ScriptBit::Push(pubkey)) => (sig.clone(), pubkey.clone()),
+            _ => panic!("Expected signature and public key pushes"),
+        };
+
+        let mut tx = tx.clone();
+        txin.set_unlocking_script(&Script::from_asm_string(&format!("{} {}", hex::encode(to_high_s(&sig)), hex::encode(pubkey))).unwrap());
+        tx.set_input(0, &txin);
+
+        let mut interpreter = Interpreter::from_transaction(&tx, 0, ScriptFlags::default() | ScriptFlags::DERSIG).unwrap();
+        interpreter.run().unwrap();
+
+        let mut interpreter = Interpreter::from_transaction(&tx, 0, ScriptFlags::default() | ScriptFlags::LOW_S).unwrap();
+        assert!(matches!(interpreter.run(), Err(InterpreterError::SigHighS)));
+    }
+
+    #[test]
+    fn nullfail_rejects_failed_non_empty_signature() {
+        let mut tx = p2pkh_tx(SigHash::InputsOutputs, "");
+        // Changing the locktime invalidates the signature
+        tx.set_nlocktime(1);
+
+        let mut interpreter = Interpreter::from_transaction(&tx, 0, ScriptFlags::default()).unwrap();
+        interpreter.run().unwrap();
+        assert!(interpreter.state().stack().last().unwrap().is_empty());
+
+        let mut interpreter = Interpreter::from_transaction(&tx, 0, ScriptFlags::default() | ScriptFlags::NULLFAIL).unwrap();
+        assert!(matches!(interpreter.run(), Err(InterpreterError::SigNullFail)));
+    }
+
+    #[test]
+    fn nulldummy_rejects_non_empty_dummy() {
+        let mut tx = Transaction::new(2, 0);
+        let pk1 = PrivateKey::from_wif("L2WAdy8C19GHNtZDSkbsVBJrBaF9XHpPLTgmnc2N5aGyguhJf7zh").unwrap();
+
+        let locking_script = Script::from_asm_string(&format!("OP_1 {} OP_1 OP_CHECKMULTISIG", &pk1.to_public_key().unwrap().to_hex().unwrap())).unwrap();
+
+        let mut txin = TxIn::default();
+        txin.set_satoshis(0);
+        txin.set_locking_script(&locking_script);
+        tx.add_input(&txin);
+
+        let sig1 = tx.sign(&pk1, SigHash::InputsOutputs, 0, &locking_script, 0).unwrap();
+        for (dummy, is_valid) in [("OP_0", true), ("OP_1", false)] {
+            txin.set_unlocking_script(&Script::from_asm_string(&format!("{} {}", dummy, sig1.to_hex().unwrap())).unwrap());
+            tx.set_input(0, &txin);
+
+            let mut interpreter = Interpreter::from_transaction(&tx, 0, ScriptFlags::default() | ScriptFlags::NULLDUMMY).unwrap();
+            match is_valid {
+                true => assert_eq!(interpreter.run().unwrap(), ()),
+                false => assert!(matches!(interpreter.run(), Err(InterpreterError::SigNullDummy))),
+            }
+        }
+    }
+}
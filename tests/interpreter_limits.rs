@@ -0,0 +1,123 @@
+mod common;
+
+#[cfg(test)]
+mod interpreter_limits_tests {
+    use crate::common::{self, run_interpreter};
+    use bsv::{Interpreter, InterpreterError, Script, ScriptFlags, ScriptLimits, Transaction, TxIn};
+
+    fn run(asm: &str, flags: ScriptFlags, limits: ScriptLimits) -> Result<Vec<Vec<u8>>, InterpreterError> {
+        let mut interpreter = common::interpreter(asm, flags);
+        interpreter.set_limits(limits);
+        run_interpreter(interpreter)
+    }
+
+    #[test]
+    fn cat_loop_exceeds_stack_memory() {
+        let limits = ScriptLimits {
+            max_stack_memory: 1000,
+            ..ScriptLimits::default()
+        };
+        let asm = format!("ff{}", " OP_DUP OP_CAT".repeat(10));
+
+        assert!(matches!(run(&asm, ScriptFlags::default(), limits), Err(InterpreterError::StackMemory)));
+        run(&asm, ScriptFlags::default(), ScriptLimits::default()).unwrap();
+    }
+
+    #[test]
+    fn stack_memory_is_released_by_pops_and_counts_the_alt_stack() {
+        // Three one byte elements with their overhead fit, a fourth does not
+        let limits = ScriptLimits {
+            max_stack_memory: 100,
+            ..ScriptLimits::default()
+        };
+
+        run(&"ff OP_DROP ".repeat(50), ScriptFlags::default(), limits).unwrap();
+        run("ff OP_TOALTSTACK ff ff", ScriptFlags::default(), limits).unwrap();
+        assert!(matches!(run("ff OP_TOALTSTACK ff ff ff", ScriptFlags::default(), limits), Err(InterpreterError::StackMemory)));
+        assert!(matches!(run("ff OP_DUP OP_2DUP", ScriptFlags::default(), limits), Err(InterpreterError::StackMemory)));
+    }
+
+    #[test]
+    fn num2bin_size_is_checked_against_stack_memory_before_allocating() {
+        let limits = ScriptLimits {
+            max_stack_memory: 100,
+            ..ScriptLimits::default()
+        };
+
+        // 68 bytes with their overhead fit exactly, one more does not
+        assert_eq!(run("OP_1 44 OP_NUM2BIN", ScriptFlags::default(), limits).unwrap()[0].len(), 68);
+        assert!(matches!(run("OP_1 45 OP_NUM2BIN", ScriptFlags::default(), limits), Err(InterpreterError::StackMemory)));
+
+        // 2,000,000,000 bytes is rejected without being allocated
+        assert!(matches!(
+            run("OP_1 00943577 OP_NUM2BIN", ScriptFlags::default(), ScriptLimits::default()),
+            Err(InterpreterError::StackMemory)
+        ));
+    }
+
+    #[test]
+    fn ops_are_counted_in_unexecuted_branches() {
+        let within_limit = format!("OP_0 OP_IF{} OP_ENDIF", " OP_NOP".repeat(498));
+        run(&within_limit, ScriptFlags::NONE, ScriptLimits::default()).unwrap();
+
+        let over_limit = format!("OP_0 OP_IF{} OP_ENDIF", " OP_NOP".repeat(499));
+        assert!(matches!(run(&over_limit, ScriptFlags::NONE, ScriptLimits::default()), Err(InterpreterError::OpCount)));
+        // Post-Genesis has no limit by default
+        run(&over_limit, ScriptFlags::default(), ScriptLimits::default()).unwrap();
+    }
+
+    #[test]
+    fn element_size_before_genesis() {
+        let push = "ab".repeat(521);
+        assert!(matches!(
+            run(&format!("OP_0 OP_IF {} OP_ENDIF", push), ScriptFlags::NONE, ScriptLimits::default()),
+            Err(InterpreterError::PushSize)
+        ));
+        run(&format!("OP_0 OP_IF {} OP_ENDIF", push), ScriptFlags::default(), ScriptLimits::default()).unwrap();
+
+        // Computed elements are limited too
+        let half = "ab".repeat(300);
+        assert!(matches!(
+            run(&format!("{} OP_DUP OP_CAT", half), ScriptFlags::NONE, ScriptLimits::default()),
+            Err(InterpreterError::PushSize)
+        ));
+    }
+
+    #[test]
+    fn stack_elements_before_genesis() {
+        let asm = format!("OP_1 OP_TOALTSTACK{}", " OP_1".repeat(1000));
+        let error = run(&asm, ScriptFlags::NONE, ScriptLimits::default()).unwrap_err();
+        assert!(matches!(error, InterpreterError::StackSize));
+        assert_eq!(error.code(), "STACK_SIZE");
+    }
+
+    #[test]
+    fn script_size_is_policy_after_genesis() {
+        let limits = ScriptLimits {
+            max_script_size: 3,
+            ..ScriptLimits::default()
+        };
+
+        assert!(matches!(run("OP_1 OP_1 OP_1 OP_1", ScriptFlags::default(), limits), Err(InterpreterError::ScriptSize)));
+        run("OP_1 OP_1 OP_1", ScriptFlags::default(), limits).unwrap();
+        // Pre-Genesis limits are consensus and cannot be changed
+        run("OP_1 OP_1 OP_1 OP_1", ScriptFlags::NONE, limits).unwrap();
+    }
+
+    #[test]
+    fn multisig_pubkey_count() {
+        let mut tx = Transaction::new(2, 0);
+        let mut txin = TxIn::default();
+        txin.set_satoshis(0);
+        txin.set_unlocking_script(&Script::from_asm_string("OP_0 OP_0").unwrap());
+        txin.set_locking_script(&Script::from_asm_string("OP_1 OP_1 OP_2 OP_CHECKMULTISIG").unwrap());
+        tx.add_input(&txin);
+
+        let mut interpreter = Interpreter::from_transaction(&tx, 0, ScriptFlags::default()).unwrap();
+        interpreter.set_limits(ScriptLimits {
+            max_pubkeys_per_multisig: 1,
+            ..ScriptLimits::default()
+        });
+        assert!(matches!(interpreter.run(), Err(InterpreterError::PubKeyCount)));
+    }
+}
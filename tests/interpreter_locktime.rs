@@ -0,0 +1,83 @@
+#[cfg(test)]
+mod interpreter_locktime_tests {
+    use bsv::{InterpreterError, Script, ScriptFlags, ScriptVerdict, SyntheticSpend};
+
+    const CLTV_FLAGS: ScriptFlags = ScriptFlags::CHECKLOCKTIMEVERIFY;
+    const CSV_FLAGS: ScriptFlags = ScriptFlags::CHECKSEQUENCEVERIFY;
+
+    /// Spends `locking_script` with an OP_1 unlocking script from a transaction with the given locktime and input sequence.
+    fn spend(locking_script: &str, version: u32, lock_time: u32, sequence: u32, flags: ScriptFlags) -> ScriptVerdict {
+        let mut spend = SyntheticSpend::new(&Script::from_asm_string(locking_script).unwrap(), 1000).unwrap();
+        spend.tx_mut().set_version(version);
+        spend.tx_mut().set_nlocktime(lock_time);
+        let mut txin = spend.tx().get_input(SyntheticSpend::INPUT_INDEX).unwrap();
+        txin.set_sequence(sequence);
+        txin.set_unlocking_script(&Script::from_asm_string("OP_1").unwrap());
+        spend.tx_mut().set_input(SyntheticSpend::INPUT_INDEX, &txin);
+        spend.verify(flags)
+    }
+
+    #[test]
+    fn checklocktimeverify_before_genesis() {
+        // 600000 as a script number
+        let locking_script = "c02709 OP_CHECKLOCKTIMEVERIFY OP_DROP";
+
+        assert!(spend(locking_script, 1, 600_000, 0, CLTV_FLAGS).is_success());
+        assert!(spend(locking_script, 1, 700_000, 0, CLTV_FLAGS).is_success());
+        assert!(matches!(
+            spend(locking_script, 1, 599_999, 0, CLTV_FLAGS),
+            ScriptVerdict::Failure(InterpreterError::UnsatisfiedLockTime)
+        ));
+        // A final input does not enforce the transaction's locktime
+        assert!(matches!(
+            spend(locking_script, 1, 600_000, u32::MAX, CLTV_FLAGS),
+            ScriptVerdict::Failure(InterpreterError::UnsatisfiedLockTime)
+        ));
+
+        assert!(matches!(
+            spend("OP_1NEGATE OP_CHECKLOCKTIMEVERIFY", 1, 0, 0, CLTV_FLAGS),
+            ScriptVerdict::Failure(InterpreterError::NegativeLockTime)
+        ));
+        assert!(matches!(
+            spend("OP_DROP OP_CHECKLOCKTIMEVERIFY", 1, 0, 0, CLTV_FLAGS),
+            ScriptVerdict::Failure(InterpreterError::EmptyStack)
+        ));
+        // Operands may be up to 5 bytes long
+        assert!(spend("ffffffff00 OP_CHECKLOCKTIMEVERIFY OP_DROP", 1, u32::MAX, 0, CLTV_FLAGS).is_success());
+        assert!(matches!(
+            spend("ffffffff0000 OP_CHECKLOCKTIMEVERIFY OP_DROP", 1, u32::MAX, 0, CLTV_FLAGS),
+            ScriptVerdict::Failure(InterpreterError::NumberOutOfRange)
+        ));
+    }
+
+    #[test]
+    fn checksequenceverify_before_genesis() {
+        let locking_script = "OP_10 OP_CHECKSEQUENCEVERIFY OP_DROP";
+
+        assert!(spend(locking_script, 2, 0, 10, CSV_FLAGS).is_success());
+        assert!(matches!(spend(locking_script, 2, 0, 9, CSV_FLAGS), ScriptVerdict::Failure(InterpreterError::UnsatisfiedLockTime)));
+        // Relative locktimes need version 2 transactions
+        assert!(matches!(spend(locking_script, 1, 0, 10, CSV_FLAGS), ScriptVerdict::Failure(InterpreterError::UnsatisfiedLockTime)));
+        // Blocks cannot satisfy a time based relative locktime
+        assert!(matches!(
+            spend(locking_script, 2, 0, 10 | (1 << 22), CSV_FLAGS),
+            ScriptVerdict::Failure(InterpreterError::UnsatisfiedLockTime)
+        ));
+
+        // Operands with the disable flag always pass
+        assert!(spend("0000008000 OP_CHECKSEQUENCEVERIFY OP_DROP", 1, 0, 0, CSV_FLAGS).is_success());
+    }
+
+    #[test]
+    fn lock_time_opcodes_are_nops_unless_enabled() {
+        for locking_script in ["c02709 OP_CHECKLOCKTIMEVERIFY OP_DROP", "OP_10 OP_CHECKSEQUENCEVERIFY OP_DROP"] {
+            assert!(spend(locking_script, 2, 0, 0, ScriptFlags::NONE).is_success());
+            // Always NOPs after Genesis
+            assert!(spend(locking_script, 2, 0, 0, CLTV_FLAGS | CSV_FLAGS | ScriptFlags::UTXO_AFTER_GENESIS).is_success());
+            assert!(matches!(
+                spend(locking_script, 2, 0, 0, ScriptFlags::DISCOURAGE_UPGRADABLE_NOPS | ScriptFlags::UTXO_AFTER_GENESIS),
+                ScriptVerdict::Failure(InterpreterError::DiscourageUpgradableNops(_))
+            ));
+        }
+    }
+}
@@ -0,0 +1,102 @@
+#[cfg(test)]
+mod interpreter_observer_tests {
+    use std::sync::{Arc, Mutex};
+
+    use bsv::{ExecutionObserver, Hash, Interpreter, OpCodes, PrivateKey, Script, ScriptFlags, SigHash, State, StepEvent, TraceCollector, TraceEvent, Transaction, TxIn};
+
+    #[derive(Default)]
+    struct StepCounter {
+        before: usize,
+        after: usize,
+    }
+
+    impl ExecutionObserver for StepCounter {
+        fn before_opcode(&mut self, _step: &StepEvent, _state: &State) {
+            self.before += 1;
+        }
+
+        fn after_opcode(&mut self, _step: &StepEvent, _state: &State) {
+            self.after += 1;
+        }
+    }
+
+    #[test]
+    fn observer_receives_every_step() {
+        let counter = Arc::new(Mutex::new(StepCounter::default()));
+
+        let mut interpreter = Interpreter::from_script(&Script::from_asm_string("OP_1 OP_2 OP_ADD OP_3 OP_EQUALVERIFY").unwrap(), ScriptFlags::default());
+        interpreter.set_observer(counter.clone());
+        interpreter.run().unwrap();
+
+        let counter = counter.lock().unwrap();
+        assert_eq!(counter.before, 5);
+        assert_eq!(counter.after, 5);
+    }
+
+    #[test]
+    fn trace_records_signature_checks() {
+        let private_key = PrivateKey::from_wif("L2WAdy8C19GHNtZDSkbsVBJrBaF9XHpPLTgmnc2N5aGyguhJf7zh").unwrap();
+        let pubkey = private_key.to_public_key().unwrap();
+        let mut tx = Transaction::new(2, 0);
+
+        let locking_script = Script::from_asm_string(&format!("OP_DUP OP_HASH160 {} OP_EQUALVERIFY OP_CHECKSIG", Hash::hash_160(&pubkey.to_bytes().unwrap()).to_hex())).unwrap();
+
+        let mut txin = TxIn::default();
+        txin.set_satoshis(0);
+        txin.set_locking_script(&locking_script);
+        tx.add_input(&txin);
+
+        let signature = tx.sign(&private_key, SigHash::InputsOutputs, 0, &locking_script, 0).unwrap();
+        txin.set_unlocking_script(&Script::from_asm_string(&format!("{} {}", signature.to_hex().unwrap(), pubkey.to_hex().unwrap())).unwrap());
+        tx.set_input(0, &txin);
+
+        let trace = Arc::new(Mutex::new(TraceCollector::new()));
+        let mut interpreter = Interpreter::from_transaction(&tx, 0, ScriptFlags::default()).unwrap();
+        interpreter.set_observer(trace.clone());
+        assert!(interpreter.verify().is_success());
+
+        let trace = trace.lock().unwrap();
+        let checks: Vec<_> = trace
+            .events()
+            .iter()
+            .filter_map(|x| match x {
+                TraceEvent::SignatureCheck(v) => Some(v),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(checks.len(), 1);
+        assert!(checks[0].is_valid);
+        assert_eq!(checks[0].public_key, pubkey.to_bytes().unwrap());
+        assert_eq!(checks[0].sighash_type, SigHash::InputsOutputs as u8);
+        assert!(!checks[0].preimage.is_empty());
+
+        let json = trace.to_json().unwrap();
+        let events = json["events"].as_array().unwrap();
+        // 2 unlocking and 5 locking ScriptBits, each with a before and after event, plus the signature check
+        assert_eq!(events.len(), 15);
+        assert_eq!(events[0]["event"], "before_opcode");
+        assert_eq!(events[1]["stack"][0], signature.to_hex().unwrap());
+    }
+
+    #[test]
+    fn trace_records_branches_and_failures() {
+        let trace = Arc::new(Mutex::new(TraceCollector::new()));
+
+        let mut interpreter = Interpreter::from_script(&Script::from_asm_string("OP_0 OP_IF OP_1 OP_ELSE OP_0 OP_ENDIF OP_VERIFY").unwrap(), ScriptFlags::default());
+        interpreter.set_observer(trace.clone());
+        assert!(interpreter.run().is_err());
+
+        let trace = trace.lock().unwrap();
+        let branch = trace.events().iter().find_map(|x| match x {
+            TraceEvent::Branch(v) => Some(v),
+            _ => None,
+        });
+        assert_eq!(branch.unwrap().opcode, OpCodes::OP_IF);
+        assert!(!branch.unwrap().executed);
+
+        match trace.events().last() {
+            Some(TraceEvent::Failure(failure)) => assert_eq!(failure.code, "VERIFY"),
+            _ => panic!("Expected the trace to end with the failure"),
+        }
+    }
+}
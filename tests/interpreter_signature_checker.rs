@@ -0,0 +1,95 @@
+mod common;
+
+#[cfg(test)]
+mod interpreter_signature_checker_tests {
+    use crate::common::{self, run_interpreter};
+    use bsv::{Hash, Interpreter, InterpreterError, MockSignatureChecker, PrecomputedSighashChecker, PrivateKey, Script, ScriptFlags, SigHash, SignatureChecker, Transaction, TxIn};
+
+    fn run(asm: &str, checker: Option<MockSignatureChecker>) -> Result<Vec<Vec<u8>>, InterpreterError> {
+        let mut interpreter = common::interpreter(asm, ScriptFlags::default());
+        if let Some(checker) = checker {
+            interpreter.set_signature_checker(checker);
+        }
+        run_interpreter(interpreter)
+    }
+
+    #[test]
+    fn mock_checker_without_transaction() {
+        assert_eq!(run("aa41 bb OP_CHECKSIG", Some(MockSignatureChecker::always_valid())).unwrap(), vec![vec![1_u8]]);
+        assert_eq!(run("aa41 bb OP_CHECKSIG", Some(MockSignatureChecker::always_invalid())).unwrap(), vec![Vec::<u8>::new()]);
+        assert!(matches!(run("aa41 bb OP_CHECKSIG", None), Err(InterpreterError::RequiresTransaction(_))));
+
+        // Empty signatures are never valid
+        assert_eq!(run("OP_0 bb OP_CHECKSIG", Some(MockSignatureChecker::always_valid())).unwrap(), vec![Vec::<u8>::new()]);
+
+        let multisig = "OP_0 aa41 aa41 OP_2 bb cc dd OP_3 OP_CHECKMULTISIG";
+        assert_eq!(run(multisig, Some(MockSignatureChecker::always_valid())).unwrap(), vec![vec![1_u8]]);
+        assert_eq!(run(multisig, Some(MockSignatureChecker::always_invalid())).unwrap(), vec![Vec::<u8>::new()]);
+    }
+
+    #[test]
+    fn precomputed_sighash_checker() {
+        let private_key = PrivateKey::from_wif("L2WAdy8C19GHNtZDSkbsVBJrBaF9XHpPLTgmnc2N5aGyguhJf7zh").unwrap();
+        let pubkey = private_key.to_public_key().unwrap();
+        let locking_script = Script::from_asm_string(&format!("{} OP_CHECKSIG", pubkey.to_hex().unwrap())).unwrap();
+
+        let mut tx = Transaction::new(2, 0);
+        let mut txin = TxIn::default();
+        txin.set_satoshis(1000);
+        tx.add_input(&txin);
+
+        let signature = tx.sign(&private_key, SigHash::InputsOutputs, 0, &locking_script, 1000).unwrap();
+        let preimage = tx.sighash_preimage(SigHash::InputsOutputs, 0, &locking_script, 1000).unwrap();
+        let sighash: [u8; 32] = Hash::sha_256d(&preimage).to_bytes().try_into().unwrap();
+
+        let script = Script::from_asm_string(&format!("{} {} OP_CHECKSIG", signature.to_hex().unwrap(), pubkey.to_hex().unwrap())).unwrap();
+
+        let mut checker = PrecomputedSighashChecker::new();
+        checker.add_sighash(SigHash::InputsOutputs as u8, sighash);
+        let mut interpreter = Interpreter::from_script(&script, ScriptFlags::default());
+        interpreter.set_signature_checker(checker);
+        assert!(interpreter.verify().is_success());
+
+        // No sighash for the signature's sighash type
+        let mut checker = PrecomputedSighashChecker::new();
+        checker.add_sighash(SigHash::ALL as u8, sighash);
+        let mut interpreter = Interpreter::from_script(&script, ScriptFlags::default());
+        interpreter.set_signature_checker(checker);
+        assert!(!interpreter.verify().is_success());
+
+        // Only the byte order the hash is output in is accepted
+        let mut reversed = sighash;
+        reversed.reverse();
+        let mut checker = PrecomputedSighashChecker::new();
+        checker.add_sighash(SigHash::InputsOutputs as u8, reversed);
+        let mut interpreter = Interpreter::from_script(&script, ScriptFlags::default());
+        interpreter.set_signature_checker(checker);
+        assert!(!interpreter.verify().is_success());
+    }
+
+    #[test]
+    fn transaction_checker_lock_times() {
+        let mut tx = Transaction::new(2, 600_000);
+        let mut txin = TxIn::default();
+        txin.set_sequence(10);
+        txin.set_locking_script(&Script::default());
+        tx.add_input(&txin);
+
+        let checker = Interpreter::from_transaction(&tx, 0, ScriptFlags::default()).unwrap().tx_script().unwrap();
+        assert!(checker.check_lock_time(600_000));
+        assert!(checker.check_lock_time(500_000));
+        assert!(!checker.check_lock_time(600_001));
+        // Timestamps cannot be compared to block heights
+        assert!(!checker.check_lock_time(500_000_000));
+
+        assert!(checker.check_sequence(10));
+        assert!(!checker.check_sequence(11));
+        assert!(!checker.check_sequence(10 | (1 << 22)));
+
+        // Final inputs ignore the transaction's locktime
+        txin.set_sequence(u32::MAX);
+        tx.set_input(0, &txin);
+        let checker = Interpreter::from_transaction(&tx, 0, ScriptFlags::default()).unwrap().tx_script().unwrap();
+        assert!(!checker.check_lock_time(500_000));
+    }
+}
@@ -1,7 +1,35 @@
 #[cfg(test)]
 mod interpreter_signature_tests {
-    use bsv::Interpreter;
-    use bsv::{Hash, PrivateKey, Script, SigHash, Transaction, TxIn};
+    use bsv::{Hash, PrivateKey, Script, ScriptFlags, SigHash, Transaction, TxIn};
+    use bsv::{Interpreter, InterpreterError, ScriptVerdict, SyntheticSpend};
+
+    const WIFS: [&str; 3] = [
+        "L2WAdy8C19GHNtZDSkbsVBJrBaF9XHpPLTgmnc2N5aGyguhJf7zh",
+        "Kz859spUJBWUBTYqesPMbW1kmFZ7BisBSJckSVYthvvFZ8cRnaPd",
+        "KxQZuMUEecRFubLb52hmfzK4q1Mq4Wi2FfaEs7ZXHkF2cuJqjK16",
+    ];
+
+    /// Spends a `sig_count`-of-3 multisig output, signing with the keys at the given indices in that order.
+    fn multisig_tx(dummy: &str, sig_count: u8, signers: &[usize]) -> Transaction {
+        let keys: Vec<PrivateKey> = WIFS.iter().map(|x| PrivateKey::from_wif(x).unwrap()).collect();
+        let pubkeys: Vec<String> = keys.iter().map(|x| x.to_public_key().unwrap().to_hex().unwrap()).collect();
+        let locking_script = Script::from_asm_string(&format!("OP_{} {} OP_3 OP_CHECKMULTISIG", sig_count, pubkeys.join(" "))).unwrap();
+
+        let mut spend = SyntheticSpend::new(&locking_script, 0).unwrap();
+        spend
+            .unlock(|spend| {
+                let sigs: Vec<String> = signers.iter().map(|x| spend.sign(&keys[*x], SigHash::ALL).unwrap().to_hex().unwrap()).collect();
+                Script::from_asm_string(format!("{} {}", dummy, sigs.join(" ")).trim())
+            })
+            .unwrap();
+        spend.tx().clone()
+    }
+
+    fn run_multisig(tx: &Transaction, flags: ScriptFlags) -> Result<Vec<u8>, InterpreterError> {
+        let mut interpreter = Interpreter::from_transaction(tx, 0, flags)?;
+        interpreter.run()?;
+        Ok(interpreter.state().stack().last().unwrap().clone())
+    }
 
     #[test]
     fn simple_p2pkh_signature_test() {
@@ -28,7 +56,7 @@ mod interpreter_signature_tests {
         txin.set_unlocking_script(&script);
         tx.set_input(0, &txin);
 
-        let mut interpreter = Interpreter::from_transaction(&tx, 0).unwrap();
+        let mut interpreter = Interpreter::from_transaction(&tx, 0, ScriptFlags::default()).unwrap();
 
         println!("Loaded P2PKH script {:?}", interpreter.script_bits());
         interpreter.run().unwrap();
@@ -72,7 +100,7 @@ mod interpreter_signature_tests {
         txin.set_unlocking_script(&script);
         tx.set_input(0, &txin);
 
-        let mut interpreter = Interpreter::from_transaction(&tx, 0).unwrap();
+        let mut interpreter = Interpreter::from_transaction(&tx, 0, ScriptFlags::default()).unwrap();
 
         interpreter.run().unwrap();
 
@@ -111,7 +139,7 @@ mod interpreter_signature_tests {
         txin.set_unlocking_script(&script);
         tx.set_input(0, &txin);
 
-        let mut interpreter = Interpreter::from_transaction(&tx, 0).unwrap();
+        let mut interpreter = Interpreter::from_transaction(&tx, 0, ScriptFlags::default()).unwrap();
 
         println!("Loaded MULTISIG script {:?}", interpreter.script_bits());
         interpreter.run().unwrap();
@@ -147,7 +175,7 @@ mod interpreter_signature_tests {
         txin.set_unlocking_script(&script);
         tx.set_input(0, &txin);
 
-        let mut interpreter = Interpreter::from_transaction(&tx, 0).unwrap();
+        let mut interpreter = Interpreter::from_transaction(&tx, 0, ScriptFlags::default()).unwrap();
 
         println!("Loaded MULTISIG script {:?}", interpreter.script_bits());
         assert!(interpreter.run().is_err());
@@ -181,11 +209,134 @@ mod interpreter_signature_tests {
         txin.set_unlocking_script(&script);
         tx.set_input(0, &txin);
 
-        let mut interpreter = Interpreter::from_transaction(&tx, 0).unwrap();
+        let mut interpreter = Interpreter::from_transaction(&tx, 0, ScriptFlags::default()).unwrap();
 
         println!("Loaded MULTISIG script {:?}", interpreter.script_bits());
         interpreter.run().unwrap();
 
         assert_eq!(interpreter.state().stack().last().unwrap(), &vec![1_u8]);
     }
+
+    #[test]
+    fn checksig_with_codeseparator_inside_if_test() {
+        let private_key = PrivateKey::from_wif("L2WAdy8C19GHNtZDSkbsVBJrBaF9XHpPLTgmnc2N5aGyguhJf7zh").unwrap();
+        let pubkey = private_key.to_public_key().unwrap();
+        let mut tx = Transaction::new(2, 0);
+
+        let locking_script = Script::from_asm_string(&format!("OP_IF OP_CODESEPARATOR {} OP_CHECKSIG OP_ELSE OP_0 OP_ENDIF", pubkey.to_hex().unwrap())).unwrap();
+        // Everything after the OP_CODESEPARATOR in the serialised locking script, including the rest of the IF statement
+        let script_code = Script::from_asm_string(&format!("{} OP_CHECKSIG OP_ELSE OP_0 OP_ENDIF", pubkey.to_hex().unwrap())).unwrap();
+
+        let mut txin = TxIn::default();
+        txin.set_satoshis(0);
+        txin.set_locking_script(&locking_script);
+        tx.add_input(&txin);
+
+        let signature = tx.sign(&private_key, SigHash::InputsOutputs, 0, &script_code, 0).unwrap();
+        txin.set_unlocking_script(&Script::from_asm_string(&format!("{} OP_1", signature.to_hex().unwrap())).unwrap());
+        tx.set_input(0, &txin);
+
+        let mut interpreter = Interpreter::from_transaction(&tx, 0, ScriptFlags::default()).unwrap();
+        interpreter.run().unwrap();
+
+        assert_eq!(interpreter.state().stack().last().unwrap(), &vec![1_u8]);
+    }
+
+    #[test]
+    fn multisig_signatures_must_follow_pubkey_order() {
+        assert_eq!(run_multisig(&multisig_tx("OP_0", 2, &[0, 2]), ScriptFlags::default()).unwrap(), vec![1_u8]);
+        assert_eq!(run_multisig(&multisig_tx("OP_0", 2, &[1, 2]), ScriptFlags::default()).unwrap(), vec![1_u8]);
+        assert!(run_multisig(&multisig_tx("OP_0", 2, &[2, 0]), ScriptFlags::default()).unwrap().is_empty());
+
+        // Failed signatures must be empty with NULLFAIL
+        assert!(matches!(
+            run_multisig(&multisig_tx("OP_0", 2, &[2, 0]), ScriptFlags::default() | ScriptFlags::NULLFAIL),
+            Err(InterpreterError::SigNullFail)
+        ));
+    }
+
+    #[test]
+    fn multisig_counts() {
+        // Zero signatures always succeed
+        assert_eq!(run_multisig(&multisig_tx("OP_0", 0, &[]), ScriptFlags::default()).unwrap(), vec![1_u8]);
+
+        let mut tx = multisig_tx("OP_0", 0, &[]);
+        let mut txin = tx.get_input(0).unwrap();
+        txin.set_unlocking_script(&Script::from_asm_string("OP_0 OP_0 OP_0 OP_0 OP_0").unwrap());
+        txin.set_locking_script(&Script::from_asm_string("OP_4 OP_1 OP_1 OP_1 OP_3 OP_CHECKMULTISIG").unwrap());
+        tx.set_input(0, &txin);
+        assert!(matches!(run_multisig(&tx, ScriptFlags::default()), Err(InterpreterError::SigCount)));
+
+        txin.set_locking_script(&Script::from_asm_string("OP_1 OP_1 OP_1 OP_1 OP_1NEGATE OP_CHECKMULTISIG").unwrap());
+        tx.set_input(0, &txin);
+        assert!(matches!(run_multisig(&tx, ScriptFlags::default()), Err(InterpreterError::PubKeyCount)));
+
+        // Only the dummy element is missing
+        txin.set_unlocking_script(&Script::default());
+        txin.set_locking_script(&Script::from_asm_string("OP_0 OP_0 OP_CHECKMULTISIG").unwrap());
+        tx.set_input(0, &txin);
+        assert!(matches!(run_multisig(&tx, ScriptFlags::default()), Err(InterpreterError::InvalidStackOperation(_))));
+    }
+
+    #[test]
+    fn multisig_nulldummy() {
+        let tx = multisig_tx("OP_1", 2, &[0, 1]);
+        assert_eq!(run_multisig(&tx, ScriptFlags::default()).unwrap(), vec![1_u8]);
+        assert!(matches!(run_multisig(&tx, ScriptFlags::default() | ScriptFlags::NULLDUMMY), Err(InterpreterError::SigNullDummy)));
+    }
+
+    #[test]
+    fn multisig_pubkeys_count_as_ops() {
+        let mut tx = multisig_tx("OP_0", 0, &[]);
+        let mut txin = tx.get_input(0).unwrap();
+        // 479 OP_NOPs, OP_ADD and OP_CHECKMULTISIG are 481 ops, the 20 public keys take it over the limit of 500
+        let keys = " OP_1".repeat(20);
+        txin.set_locking_script(&Script::from_asm_string(format!("{} OP_0{} OP_16 OP_4 OP_ADD OP_CHECKMULTISIG", " OP_NOP".repeat(479), keys).trim()).unwrap());
+        tx.set_input(0, &txin);
+        assert!(matches!(run_multisig(&tx, ScriptFlags::NONE), Err(InterpreterError::OpCount)));
+    }
+
+    #[test]
+    fn synthetic_spend_of_p2pkh() {
+        let private_key = PrivateKey::from_wif(WIFS[0]).unwrap();
+        let pubkey = private_key.to_public_key().unwrap();
+        let locking_script = Script::from_asm_string(&format!("OP_DUP OP_HASH160 {} OP_EQUALVERIFY OP_CHECKSIG", Hash::hash_160(&pubkey.to_bytes().unwrap()).to_hex())).unwrap();
+
+        let verdict = SyntheticSpend::verify_unlock(&locking_script, 1000, ScriptFlags::default(), |spend| {
+            let signature = spend.sign(&private_key, SigHash::InputsOutputs)?;
+            Script::from_asm_string(&format!("{} {}", signature.to_hex()?, pubkey.to_hex()?))
+        });
+        assert!(matches!(verdict, ScriptVerdict::Success));
+
+        // Signed by another key
+        let other_key = PrivateKey::from_wif(WIFS[1]).unwrap();
+        let verdict = SyntheticSpend::verify_unlock(&locking_script, 1000, ScriptFlags::default(), |spend| {
+            let signature = spend.sign(&other_key, SigHash::InputsOutputs)?;
+            Script::from_asm_string(&format!("{} {}", signature.to_hex()?, pubkey.to_hex()?))
+        });
+        assert!(matches!(verdict, ScriptVerdict::Failure(InterpreterError::EvalFalse)));
+    }
+
+    #[test]
+    fn synthetic_spend_commits_to_funding_output() {
+        let private_key = PrivateKey::from_wif(WIFS[0]).unwrap();
+        let locking_script = Script::from_asm_string(&format!("{} OP_CHECKSIG", private_key.to_public_key().unwrap().to_hex().unwrap())).unwrap();
+
+        let mut spend = SyntheticSpend::new(&locking_script, 5000).unwrap();
+        let input = spend.tx().get_input(SyntheticSpend::INPUT_INDEX).unwrap();
+        assert_eq!(input.get_prev_tx_id_hex(None), spend.funding_tx().get_id_hex().unwrap());
+        assert_eq!(spend.funding_tx().get_output(input.get_vout() as usize).unwrap().get_satoshis(), 5000);
+
+        // Signatures commit to the satoshis being spent
+        let mut other = SyntheticSpend::new(&locking_script, 4000).unwrap();
+        let signature = other.sign(&private_key, SigHash::InputsOutputs).unwrap();
+        other.set_unlocking_script(&Script::from_asm_string(&signature.to_hex().unwrap()).unwrap());
+        assert!(other.verify(ScriptFlags::default()).is_success());
+
+        spend.set_unlocking_script(&Script::from_asm_string(&signature.to_hex().unwrap()).unwrap());
+        assert!(!spend.verify(ScriptFlags::default()).is_success());
+        let mut interpreter = spend.interpreter(ScriptFlags::default()).unwrap();
+        interpreter.run().unwrap();
+        assert!(interpreter.state().stack().last().unwrap().is_empty());
+    }
 }
@@ -1,7 +1,7 @@
 #[cfg(test)]
 mod interpreter_utility_tests {
     use bsv::Interpreter;
-    use bsv::Script;
+    use bsv::{InterpreterError, Script, ScriptFlags};
 
     #[test]
     fn simple_op_num2bin_test() {
@@ -12,7 +12,7 @@ mod interpreter_utility_tests {
         )
         .unwrap();
 
-        let mut interpreter = Interpreter::from_script(&script);
+        let mut interpreter = Interpreter::from_script(&script, ScriptFlags::default());
         interpreter.run().unwrap();
 
         assert_eq!(interpreter.state().stack().last().unwrap(), &vec![1_u8]);
@@ -27,7 +27,7 @@ mod interpreter_utility_tests {
         )
         .unwrap();
 
-        let mut interpreter = Interpreter::from_script(&script);
+        let mut interpreter = Interpreter::from_script(&script, ScriptFlags::default());
         interpreter.run().unwrap();
 
         assert_eq!(hex::encode(interpreter.state().stack().last().unwrap()), "abcdef4243");
@@ -42,7 +42,7 @@ mod interpreter_utility_tests {
         )
         .unwrap();
 
-        let mut interpreter = Interpreter::from_script(&script);
+        let mut interpreter = Interpreter::from_script(&script, ScriptFlags::default());
         assert!(interpreter.run().is_err());
     }
 
@@ -55,7 +55,7 @@ mod interpreter_utility_tests {
         )
         .unwrap();
 
-        let mut interpreter = Interpreter::from_script(&script);
+        let mut interpreter = Interpreter::from_script(&script, ScriptFlags::default());
         interpreter.run().unwrap();
 
         assert_eq!(hex::encode(interpreter.state().stack().last().unwrap()), "abcdef42430000000000000000000000");
@@ -69,7 +69,7 @@ mod interpreter_utility_tests {
         )
         .unwrap();
 
-        let mut interpreter = Interpreter::from_script(&script);
+        let mut interpreter = Interpreter::from_script(&script, ScriptFlags::default());
         interpreter.run().unwrap();
 
         assert_eq!(hex::encode(interpreter.state().stack().last().unwrap()), "2168");
@@ -83,9 +83,30 @@ mod interpreter_utility_tests {
         )
         .unwrap();
 
-        let mut interpreter = Interpreter::from_script(&script);
+        let mut interpreter = Interpreter::from_script(&script, ScriptFlags::default());
         interpreter.run().unwrap();
 
         assert_eq!(hex::encode(interpreter.state().stack().last().unwrap()), "21680000000000000000000000000000");
     }
+
+    #[test]
+    fn num2bin_pads_zero_and_moves_the_sign_bit() {
+        let run = |asm: &str| {
+            let mut interpreter = Interpreter::from_script(&Script::from_asm_string(asm).unwrap(), ScriptFlags::default());
+            interpreter.run().map(|_| hex::encode(interpreter.state().stack().last().unwrap()))
+        };
+
+        assert_eq!(run("OP_0 OP_4 OP_NUM2BIN").unwrap(), "00000000");
+        assert_eq!(run("OP_0 OP_0 OP_NUM2BIN").unwrap(), "");
+        // Negative zero is minimally encoded as zero
+        assert_eq!(run("80 OP_0 OP_NUM2BIN").unwrap(), "");
+        // -127 keeps its length when it already fits, otherwise the sign bit moves to the last byte
+        assert_eq!(run("ff OP_1 OP_NUM2BIN").unwrap(), "ff");
+        assert_eq!(run("ff OP_2 OP_NUM2BIN").unwrap(), "7f80");
+        assert_eq!(run("ff00 OP_3 OP_NUM2BIN").unwrap(), "ff0000");
+        // Non-minimal input is shortened first
+        assert_eq!(run("010000 OP_2 OP_NUM2BIN").unwrap(), "0100");
+
+        assert!(matches!(run("0102 OP_1 OP_NUM2BIN"), Err(InterpreterError::ImpossibleEncoding)));
+    }
 }
@@ -1,6 +1,6 @@
 #[cfg(test)]
 mod script_tests {
-    use bsv::{Hash, OpCodes, P2PKHAddress, Script, ScriptBit};
+    use bsv::{BSVErrors, Hash, OpCodes, P2PKHAddress, Script, ScriptBit, ScriptParseError, ScriptParseErrorKind, Transaction, TxOut};
     // #[test]
     // #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
     // fn to_hex_string() {
@@ -496,7 +496,8 @@ mod script_tests {
 
     #[test]
     fn scrypt_stateful_contract() {
-        let script = Script::from_hex("6a00010100010001000100010001000100010001001400000000").unwrap();
+        // The last push claims 20 bytes but only 4 follow, so it is kept as a raw tail
+        let script = Script::from_bytes_lenient(&hex::decode("6a00010100010001000100010001000100010001001400000000").unwrap()).script;
 
         assert_eq!(
             &script.to_script_bits(),
@@ -513,10 +514,122 @@ mod script_tests {
                 ScriptBit::Push(hex::decode("00").unwrap()),
                 ScriptBit::Push(hex::decode("00").unwrap()),
                 ScriptBit::Push(hex::decode("00").unwrap()),
-                ScriptBit::Push(hex::decode("00000000").unwrap()),
+                ScriptBit::Unparsed(hex::decode("1400000000").unwrap()),
             ]
         );
 
-        assert_eq!(&script.to_asm_string(), "OP_RETURN 0 01 0 00 00 00 00 00 00 00 00 00000000")
+        assert_eq!(&script.to_asm_string(), "OP_RETURN 0 01 0 00 00 00 00 00 00 00 00 1400000000");
+        assert_eq!(&script.to_hex(), "6a00010100010001000100010001000100010001001400000000")
+    }
+
+    #[test]
+    fn count_sigops() {
+        let p2pkh = Script::from_asm_string("OP_DUP OP_HASH160 05186ff0710ed004229e644c0653b2985c648a23 OP_EQUALVERIFY OP_CHECKSIG").unwrap();
+        assert_eq!(p2pkh.count_sigops(true), 1);
+
+        let multisig =
+            Script::from_asm_string("OP_2 02e46dcd7991e5a4bd642739249b0158312e1aee56a60fd1bf622172ffe65bd789 02e46dcd7991e5a4bd642739249b0158312e1aee56a60fd1bf622172ffe65bd789 OP_2 OP_CHECKMULTISIG")
+                .unwrap();
+        assert_eq!(multisig.count_sigops(true), 2);
+        assert_eq!(multisig.count_sigops(false), 20);
+
+        // Unexecuted branches count, a multisig without OP_1 - OP_16 before it counts as 20
+        let script = Script::from_asm_string("OP_IF OP_CHECKSIGVERIFY OP_ELSE ff OP_CHECKMULTISIGVERIFY OP_ENDIF OP_CHECKSIG").unwrap();
+        assert_eq!(script.count_sigops(true), 22);
+
+        // Counting stops at a push running past the end of the script
+        let script = Script::from_script_bits(vec![
+            ScriptBit::OpCode(OpCodes::OP_CHECKSIG),
+            ScriptBit::Coinbase(vec![0x4c, 0x05, 0xac]),
+            ScriptBit::OpCode(OpCodes::OP_CHECKSIG),
+        ]);
+        assert_eq!(script.count_sigops(true), 1);
+    }
+
+    #[test]
+    fn lenient_parsing_keeps_unparsed_bytes() {
+        let parsed = Script::from_bytes_lenient(&hex::decode("006a0568656c6c6fbb0102").unwrap());
+        assert_eq!(
+            parsed.script.to_script_bits(),
+            vec![
+                ScriptBit::OpCode(OpCodes::OP_0),
+                ScriptBit::OpCode(OpCodes::OP_RETURN),
+                ScriptBit::Push(b"hello".to_vec()),
+                ScriptBit::Unparsed(hex::decode("bb0102").unwrap()),
+            ]
+        );
+        assert_eq!(
+            parsed.errors,
+            vec![ScriptParseError {
+                offset: 8,
+                kind: ScriptParseErrorKind::UnknownOpcode(0xbb)
+            }]
+        );
+        assert!(!parsed.is_truncated());
+
+        // Unbalanced conditionals are left flat
+        let parsed = Script::from_bytes_lenient(&hex::decode("6a635168").unwrap());
+        assert_eq!(parsed.script.to_asm_string(), "OP_RETURN OP_IF OP_1 OP_ENDIF");
+        assert!(parsed.errors.is_empty());
+        let parsed = Script::from_bytes_lenient(&hex::decode("6a6351").unwrap());
+        assert_eq!(
+            parsed.script.to_script_bits(),
+            vec![ScriptBit::OpCode(OpCodes::OP_RETURN), ScriptBit::OpCode(OpCodes::OP_IF), ScriptBit::OpCode(OpCodes::OP_1)]
+        );
+        assert_eq!(
+            parsed.errors,
+            vec![ScriptParseError {
+                offset: 1,
+                kind: ScriptParseErrorKind::UnbalancedConditional(OpCodes::OP_IF)
+            }]
+        );
+
+        let parsed = Script::from_bytes_lenient(&hex::decode("6a4d0100").unwrap());
+        assert!(parsed.is_truncated());
+        assert_eq!(parsed.script.to_hex(), "6a4d0100");
+    }
+
+    #[test]
+    fn lenient_parsing_round_trips_every_script() {
+        let mut scripts: Vec<Vec<u8>> = (0..=0xffff_u32).map(|x| x.to_be_bytes()[2..].to_vec()).collect();
+        scripts.extend((0..=0xff).map(|x| vec![x]));
+        scripts.extend([hex::decode("4c02aabb").unwrap(), hex::decode("4e0100000000").unwrap(), hex::decode("63516768ff").unwrap()]);
+
+        for bytes in scripts {
+            assert_eq!(Script::from_bytes_lenient(&bytes).script.to_bytes(), bytes, "{}", hex::encode(&bytes));
+        }
+    }
+
+    #[test]
+    fn strict_parsing_reports_every_error() {
+        let errors = match Script::from_bytes(&hex::decode("51bb68bc4c").unwrap()) {
+            Err(BSVErrors::ParseScript(errors)) => errors,
+            other => panic!("{:?}", other),
+        };
+
+        let kinds: Vec<(usize, ScriptParseErrorKind)> = errors.iter().map(|x| (x.offset, x.kind)).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (1, ScriptParseErrorKind::UnknownOpcode(0xbb)),
+                (2, ScriptParseErrorKind::UnbalancedConditional(OpCodes::OP_ENDIF)),
+                (3, ScriptParseErrorKind::UnknownOpcode(0xbc)),
+                (4, ScriptParseErrorKind::TruncatedPush),
+            ]
+        );
+        assert_eq!(errors[0].to_string(), "unknown opcode 0xbb at byte 1");
+
+        assert!(Script::from_hex("0568656c6c").is_err());
+        assert!(Script::from_hex("63516768").is_ok());
+    }
+
+    #[test]
+    fn transactions_keep_unparsable_outputs() {
+        let script_bytes = hex::decode("006a02ffbe").unwrap();
+        let mut tx = Transaction::new(1, 0);
+        tx.add_output(&TxOut::new(0, &Script::from_bytes_lenient(&script_bytes).script));
+
+        let tx = Transaction::from_bytes(&tx.to_bytes().unwrap()).unwrap();
+        assert_eq!(tx.get_output(0).unwrap().get_script_pub_key().to_bytes(), script_bytes);
     }
 }
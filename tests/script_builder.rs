@@ -0,0 +1,120 @@
+#[cfg(test)]
+mod script_builder_tests {
+    use bsv::{Breakpoint, DebuggerStop, ExecutionPhase, Interpreter, LabelPosition, OpCodes, Script, ScriptBit, ScriptBuilder, ScriptDebugger, ScriptFlags};
+
+    fn single_bit(builder: ScriptBuilder) -> ScriptBit {
+        let bits = builder.build().to_script_bits();
+        assert_eq!(bits.len(), 1);
+        bits[0].clone()
+    }
+
+    #[test]
+    fn push_data_is_minimal() {
+        let push = |data: &[u8]| single_bit(ScriptBuilder::new().push_data(data));
+
+        assert_eq!(push(&[]), ScriptBit::OpCode(OpCodes::OP_0));
+        assert_eq!(push(&[1]), ScriptBit::OpCode(OpCodes::OP_1));
+        assert_eq!(push(&[16]), ScriptBit::OpCode(OpCodes::OP_16));
+        assert_eq!(push(&[0x81]), ScriptBit::OpCode(OpCodes::OP_1NEGATE));
+        assert_eq!(push(&[0]), ScriptBit::Push(vec![0]));
+        assert_eq!(push(&[17]), ScriptBit::Push(vec![17]));
+        assert_eq!(push(&[0; 75]), ScriptBit::Push(vec![0; 75]));
+        assert_eq!(push(&[0; 76]), ScriptBit::PushData(OpCodes::OP_PUSHDATA1, vec![0; 76]));
+        assert_eq!(push(&[0; 256]), ScriptBit::PushData(OpCodes::OP_PUSHDATA2, vec![0; 256]));
+        assert_eq!(push(&[0; 65536]), ScriptBit::PushData(OpCodes::OP_PUSHDATA4, vec![0; 65536]));
+
+        // Every push passes MINIMALDATA
+        for length in [0, 1, 75, 76, 255, 256, 65535, 65536] {
+            let script = ScriptBuilder::new().push_data(&vec![0xab; length]).build();
+            let mut interpreter = Interpreter::from_script(&script, ScriptFlags::default() | ScriptFlags::MINIMALDATA);
+            interpreter.run().unwrap();
+            assert_eq!(interpreter.stack(), &[vec![0xab; length]]);
+        }
+    }
+
+    #[test]
+    fn push_int_encodes_script_numbers() {
+        let push = |number: i64| single_bit(ScriptBuilder::new().push_int(number));
+
+        assert_eq!(push(0), ScriptBit::OpCode(OpCodes::OP_0));
+        assert_eq!(push(-1), ScriptBit::OpCode(OpCodes::OP_1NEGATE));
+        assert_eq!(push(16), ScriptBit::OpCode(OpCodes::OP_16));
+        assert_eq!(push(17), ScriptBit::Push(vec![0x11]));
+        assert_eq!(push(-2), ScriptBit::Push(vec![0x82]));
+        assert_eq!(push(127), ScriptBit::Push(vec![0x7f]));
+        assert_eq!(push(128), ScriptBit::Push(vec![0x80, 0x00]));
+        assert_eq!(push(-128), ScriptBit::Push(vec![0x80, 0x80]));
+        assert_eq!(push(255), ScriptBit::Push(vec![0xff, 0x00]));
+        assert_eq!(push(256), ScriptBit::Push(vec![0x00, 0x01]));
+        assert_eq!(push(i64::MAX), ScriptBit::Push(vec![0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x7f]));
+        assert_eq!(push(i64::MIN), ScriptBit::Push(vec![0, 0, 0, 0, 0, 0, 0, 0x80, 0x80]));
+
+        assert_eq!(single_bit(ScriptBuilder::new().push_bool(true)), ScriptBit::OpCode(OpCodes::OP_1));
+        assert_eq!(single_bit(ScriptBuilder::new().push_bool(false)), ScriptBit::OpCode(OpCodes::OP_0));
+
+        // The interpreter reads back the same number
+        for number in [-65536, -129, -128, -17, -1, 0, 1, 16, 17, 127, 128, 32767, 32768, 1 << 40] {
+            let script = ScriptBuilder::new()
+                .push_int(number - 1)
+                .push_opcode(OpCodes::OP_1ADD)
+                .push_int(number)
+                .push_opcode(OpCodes::OP_NUMEQUAL)
+                .build();
+            let mut interpreter = Interpreter::from_script(&script, ScriptFlags::default() | ScriptFlags::MINIMALDATA);
+            interpreter.run().unwrap();
+            assert_eq!(interpreter.stack(), &[vec![1_u8]], "{}", number);
+        }
+    }
+
+    #[test]
+    fn conditionals_and_appended_scripts() {
+        let p2pkh_tail = Script::from_asm_string("OP_EQUALVERIFY OP_CHECKSIG").unwrap();
+        let script = ScriptBuilder::new()
+            .push_opcode(OpCodes::OP_DUP)
+            .if_else(
+                |then| then.push_int(1000).push_opcode(OpCodes::OP_CHECKSEQUENCEVERIFY),
+                |otherwise| otherwise.push_opcode(OpCodes::OP_DROP),
+            )
+            .if_then(|then| then.push_bool(true))
+            .append(&p2pkh_tail)
+            .build();
+
+        assert_eq!(
+            script.to_asm_string(),
+            "OP_DUP OP_IF e803 OP_CHECKSEQUENCEVERIFY OP_ELSE OP_DROP OP_ENDIF OP_IF OP_1 OP_ENDIF OP_EQUALVERIFY OP_CHECKSIG"
+        );
+        assert_eq!(Script::from_bytes(&script.to_bytes()).unwrap(), script);
+        assert_eq!(Script::from(ScriptBuilder::new().append(&script)), script);
+    }
+
+    #[test]
+    fn labels_point_into_the_built_script() {
+        let builder = ScriptBuilder::new()
+            .label("start")
+            .push_data(&[0xab; 80])
+            .push_opcode(OpCodes::OP_1)
+            .if_else(|then| then.push_int(2).label("then"), |otherwise| otherwise.label("else").push_int(300))
+            .append(&Script::from_asm_string("OP_1 OP_IF OP_NOP OP_ENDIF").unwrap())
+            .label("end")
+            .push_int(4);
+
+        let position = |index, byte_offset| Some(LabelPosition { index, byte_offset });
+        assert_eq!(builder.get_label("start"), position(0, 0));
+        // OP_PUSHDATA1 <80> OP_1 OP_IF OP_2 [OP_ELSE] [<2c01>] ...
+        assert_eq!(builder.get_label("then"), position(4, 85));
+        assert_eq!(builder.get_label("else"), position(5, 86));
+        // ... OP_ENDIF OP_1 OP_IF OP_NOP OP_ENDIF [OP_4]
+        assert_eq!(builder.get_label("end"), position(11, 94));
+        assert_eq!(builder.get_label("missing"), None);
+        assert_eq!(builder.labels().len(), 4);
+        assert_eq!(builder.build().to_bytes()[94], OpCodes::OP_4 as u8);
+
+        let mut debugger = ScriptDebugger::new(Interpreter::from_script(&builder.build(), ScriptFlags::default()));
+        let id = debugger.add_breakpoint(Breakpoint::Index {
+            phase: ExecutionPhase::Locking,
+            index: builder.get_label("end").unwrap().index,
+        });
+        assert_eq!(debugger.resume().unwrap(), DebuggerStop::Breakpoint(id));
+        assert_eq!(debugger.position().unwrap().byte_offset, 94);
+    }
+}
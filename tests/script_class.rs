@@ -0,0 +1,164 @@
+#[cfg(test)]
+mod script_class_tests {
+    use bsv::{OpCodes, Script, ScriptBuilder, ScriptClass};
+
+    const PUBKEY: &str = "02e46dcd7991e5a4bd642739249b0158312e1aee56a60fd1bf622172ffe65bd789";
+    const PUBKEY_HASH: &str = "05186ff0710ed004229e644c0653b2985c648a23";
+
+    fn classify(asm: &str) -> ScriptClass {
+        Script::from_asm_string(asm).unwrap().classify()
+    }
+
+    fn bytes(hex: &str) -> Vec<u8> {
+        hex::decode(hex).unwrap()
+    }
+
+    #[test]
+    fn key_and_hash_locks() {
+        assert_eq!(
+            classify(&format!("OP_DUP OP_HASH160 {} OP_EQUALVERIFY OP_CHECKSIG", PUBKEY_HASH)),
+            ScriptClass::P2PKH { pubkey_hash: bytes(PUBKEY_HASH) }
+        );
+        assert_eq!(classify(&format!("{} OP_CHECKSIG", PUBKEY)), ScriptClass::P2PK { public_key: bytes(PUBKEY) });
+        assert_eq!(classify(&format!("OP_HASH160 {} OP_EQUAL", PUBKEY_HASH)), ScriptClass::P2SH { script_hash: bytes(PUBKEY_HASH) });
+
+        // Not a valid public key
+        assert_eq!(classify(&format!("{} OP_CHECKSIG", PUBKEY_HASH)), ScriptClass::Nonstandard);
+        assert_eq!(classify(&format!("OP_DUP OP_HASH160 {} OP_EQUAL OP_CHECKSIG", PUBKEY_HASH)), ScriptClass::Nonstandard);
+    }
+
+    #[test]
+    fn multisig() {
+        assert_eq!(
+            classify(&format!("OP_2 {0} {0} {0} OP_3 OP_CHECKMULTISIG", PUBKEY)),
+            ScriptClass::Multisig {
+                required: 2,
+                public_keys: vec![bytes(PUBKEY); 3]
+            }
+        );
+        assert_eq!(
+            classify(&format!("OP_1 {} OP_1 OP_CHECKMULTISIG", PUBKEY)),
+            ScriptClass::Multisig {
+                required: 1,
+                public_keys: vec![bytes(PUBKEY)]
+            }
+        );
+
+        // More signatures than keys, and a key count that does not match
+        assert_eq!(classify(&format!("OP_3 {0} {0} OP_2 OP_CHECKMULTISIG", PUBKEY)), ScriptClass::Nonstandard);
+        assert_eq!(classify(&format!("OP_1 {0} {0} OP_3 OP_CHECKMULTISIG", PUBKEY)), ScriptClass::Nonstandard);
+    }
+
+    #[test]
+    fn data() {
+        assert_eq!(
+            classify("0 OP_RETURN 6d02 68656c6c6f"),
+            ScriptClass::FalseReturn {
+                pushes: vec![bytes("6d02"), bytes("68656c6c6f")],
+                payload: bytes("026d020568656c6c6f")
+            }
+        );
+        assert_eq!(
+            classify("OP_RETURN 21e8"),
+            ScriptClass::OpReturn {
+                pushes: vec![bytes("21e8")],
+                payload: bytes("0221e8")
+            }
+        );
+        assert_eq!(classify("OP_RETURN"), ScriptClass::OpReturn { pushes: vec![], payload: vec![] });
+        assert_eq!(classify("OP_RETURN 21e8 OP_DROP"), ScriptClass::Nonstandard);
+
+        let long = ScriptBuilder::new().push_opcode(OpCodes::OP_0).push_opcode(OpCodes::OP_RETURN).push_data(&[0xab; 300]).build();
+        match long.classify() {
+            ScriptClass::FalseReturn { pushes, .. } => assert_eq!(pushes, vec![vec![0xab; 300]]),
+            v => panic!("{:?}", v),
+        }
+    }
+
+    #[test]
+    fn data_with_small_numbers_and_unparsed_tails() {
+        let parse = |hex: &str| Script::from_bytes_lenient(&bytes(hex)).script.classify();
+
+        assert_eq!(
+            parse("006a0568656c6c6fbb"),
+            ScriptClass::FalseReturn {
+                pushes: vec![b"hello".to_vec()],
+                payload: bytes("0568656c6c6fbb")
+            }
+        );
+        assert_eq!(
+            parse("006a0568656c6c6f51"),
+            ScriptClass::FalseReturn {
+                pushes: vec![b"hello".to_vec(), vec![1]],
+                payload: bytes("0568656c6c6f51")
+            }
+        );
+        assert_eq!(
+            parse("6a0568656c6c6f0051bb"),
+            ScriptClass::OpReturn {
+                pushes: vec![b"hello".to_vec(), vec![], vec![1]],
+                payload: bytes("0568656c6c6f0051bb")
+            }
+        );
+        // A push running past the end of the script is kept in the payload
+        assert_eq!(
+            parse("006a05686568"),
+            ScriptClass::FalseReturn {
+                pushes: vec![],
+                payload: bytes("05686568")
+            }
+        );
+
+        // Anything may follow OP_FALSE OP_RETURN, only the pushes before the first other opcode are extracted
+        assert_eq!(
+            parse("006a01ab7501cd"),
+            ScriptClass::FalseReturn {
+                pushes: vec![vec![0xab]],
+                payload: bytes("01ab7501cd")
+            }
+        );
+    }
+
+    #[test]
+    fn inscription() {
+        let envelope = "OP_0 OP_IF 6f7264 OP_1 746578742f706c61696e OP_0 68656c6c6f OP_ENDIF";
+        let p2pkh = format!("OP_DUP OP_HASH160 {} OP_EQUALVERIFY OP_CHECKSIG", PUBKEY_HASH);
+        let expected = ScriptClass::Inscription {
+            content_type: b"text/plain".to_vec(),
+            content: b"hello".to_vec(),
+            lock: Box::new(ScriptClass::P2PKH { pubkey_hash: bytes(PUBKEY_HASH) }),
+        };
+
+        assert_eq!(classify(&format!("{} {}", p2pkh, envelope)), expected);
+        assert_eq!(classify(&format!("{} {}", envelope, p2pkh)), expected);
+
+        // Parsed from bytes the same way
+        let script = Script::from_asm_string(&format!("{} {}", p2pkh, envelope)).unwrap();
+        assert_eq!(Script::from_bytes(&script.to_bytes()).unwrap().classify(), expected);
+
+        match classify(envelope) {
+            ScriptClass::Inscription { lock, .. } => assert_eq!(*lock, ScriptClass::Nonstandard),
+            v => panic!("{:?}", v),
+        }
+        assert_eq!(classify("OP_0 OP_IF 6f7264 OP_1 746578742f706c61696e OP_ENDIF"), ScriptClass::Nonstandard);
+        assert_eq!(classify("OP_1 OP_IF 6f7264 OP_1 746578742f706c61696e OP_0 68656c6c6f OP_ENDIF"), ScriptClass::Nonstandard);
+    }
+
+    #[test]
+    fn r_puzzle() {
+        let prefix = "OP_OVER OP_3 OP_SPLIT OP_NIP OP_1 OP_SPLIT OP_SWAP OP_SPLIT OP_DROP";
+
+        assert_eq!(
+            classify(&format!("{} OP_HASH160 {} OP_EQUALVERIFY OP_CHECKSIG", prefix, PUBKEY_HASH)),
+            ScriptClass::RPuzzle {
+                hash: Some(OpCodes::OP_HASH160),
+                value: bytes(PUBKEY_HASH)
+            }
+        );
+        assert_eq!(
+            classify(&format!("{} {} OP_EQUALVERIFY OP_CHECKSIG", prefix, PUBKEY)),
+            ScriptClass::RPuzzle { hash: None, value: bytes(PUBKEY) }
+        );
+        assert_eq!(classify(&format!("{} OP_CHECKSIG {} OP_EQUALVERIFY OP_CHECKSIG", prefix, PUBKEY_HASH)), ScriptClass::Nonstandard);
+    }
+}
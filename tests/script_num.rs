@@ -0,0 +1,117 @@
+#[cfg(test)]
+mod script_num_tests {
+    use bsv::{Interpreter, OpCodes, Script, ScriptBit, ScriptBuilder, ScriptFlags, ScriptNum, ScriptNumError};
+    use num_bigint::BigInt;
+
+    #[test]
+    fn encoding() {
+        let cases: &[(i64, &[u8])] = &[
+            (0, &[]),
+            (1, &[0x01]),
+            (-1, &[0x81]),
+            (127, &[0x7f]),
+            (-127, &[0xff]),
+            (128, &[0x80, 0x00]),
+            (-128, &[0x80, 0x80]),
+            (255, &[0xff, 0x00]),
+            (256, &[0x00, 0x01]),
+            (-32768, &[0x00, 0x80, 0x80]),
+            (i64::MIN, &[0, 0, 0, 0, 0, 0, 0, 0x80, 0x80]),
+        ];
+
+        for (number, bytes) in cases {
+            let num = ScriptNum::from(*number);
+            assert_eq!(num.to_bytes(), *bytes, "{}", number);
+            assert_eq!(num.encoded_len(), bytes.len(), "{}", number);
+            assert_eq!(ScriptNum::from_bytes(bytes, true, 9).unwrap(), num);
+            assert_eq!(num.to_i64(), Some(*number));
+        }
+
+        let big = BigInt::from(u64::MAX) * 4_u8;
+        assert_eq!(ScriptNum::from(big.clone()).to_i64(), None);
+        assert_eq!(BigInt::from(ScriptNum::from(big.clone())), big);
+        assert_eq!(ScriptNum::from(big).to_bytes(), vec![0xfc, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x03]);
+    }
+
+    #[test]
+    fn reading_checks_length_and_minimal_encoding() {
+        assert_eq!(ScriptNum::from_bytes(&[1, 2, 3, 4, 5], true, 4), Err(ScriptNumError::TooLong { length: 5, max_length: 4 }));
+        assert_eq!(ScriptNum::from_bytes(&[0x01, 0x00], true, 4), Err(ScriptNumError::NotMinimal));
+        assert_eq!(ScriptNum::from_bytes(&[0x80], true, 4), Err(ScriptNumError::NotMinimal));
+        assert_eq!(ScriptNum::from_bytes(&[0x01, 0x00], false, 4).unwrap().to_i64(), Some(1));
+
+        // Negative zero is zero
+        assert!(ScriptNum::from_bytes(&[0x80], false, 4).unwrap().is_zero());
+
+        assert!(ScriptNum::is_minimal(&[]));
+        assert!(ScriptNum::is_minimal(&[0xff, 0x00]));
+        assert!(ScriptNum::is_minimal(&[0xff, 0x80]));
+        assert!(!ScriptNum::is_minimal(&[0x7f, 0x80]));
+        assert!(!ScriptNum::is_minimal(&[0x00]));
+
+        assert_eq!(ScriptNum::from_bytes_lenient(&[0x05, 0x00, 0x00, 0x80]).to_bytes(), vec![0x85]);
+    }
+
+    #[test]
+    fn checked_arithmetic() {
+        let num = |x: i64| ScriptNum::from(x);
+
+        assert_eq!(num(0x7fff_ffff).checked_add(&num(0), 4), Some(num(0x7fff_ffff)));
+        assert_eq!(num(0x7fff_ffff).checked_add(&num(1), 4), None);
+        assert_eq!(num(0x7fff_ffff).checked_add(&num(1), 5), Some(num(0x8000_0000)));
+        assert_eq!(num(-0x7fff_ffff).checked_sub(&num(1), 4), None);
+        assert_eq!(num(0x10000).checked_mul(&num(0x100), 4), Some(num(0x1000000)));
+        assert_eq!(num(0x10000).checked_mul(&num(0x10000), 4), None);
+
+        // Rounds towards zero, the remainder takes the sign of the dividend
+        assert_eq!(num(-7).checked_div(&num(2), 4), Some(num(-3)));
+        assert_eq!(num(-7).checked_rem(&num(2), 4), Some(num(-1)));
+        assert_eq!(num(7).checked_rem(&num(-2), 4), Some(num(1)));
+        assert_eq!(num(7).checked_div(&num(0), 4), None);
+        assert_eq!(num(7).checked_rem(&num(0), 4), None);
+
+        assert_eq!(num(2) + num(3), num(5));
+        assert_eq!(num(2) - num(3), -num(1));
+        assert_eq!(num(2) * num(-3), num(-6));
+        assert_eq!("-6".parse::<ScriptNum>().unwrap(), num(-6));
+        assert_eq!(num(-6).to_string(), "-6");
+    }
+
+    #[test]
+    fn matches_interpreter() {
+        let flags = ScriptFlags::default() | ScriptFlags::MINIMALDATA;
+
+        for number in [-1 << 40, -70000, -129, -128, -2, -1, 0, 1, 2, 16, 17, 127, 128, 255, 70000, 1 << 40] {
+            // The interpreter's result of an operation is the same as the encoding of ScriptNum
+            let script = ScriptBuilder::new().push_int(number - 3).push_int(3).push_opcode(OpCodes::OP_ADD).build();
+            let mut interpreter = Interpreter::from_script(&script, flags);
+            interpreter.run().unwrap();
+            assert_eq!(interpreter.stack(), &[ScriptNum::from(number).to_bytes()], "{}", number);
+        }
+    }
+
+    #[test]
+    fn asm_decimal_numbers() {
+        let script = Script::from_asm_string("-1 16 +16 -5 +100 -1000 +12345 1000 17").unwrap();
+        assert_eq!(
+            script.to_script_bits(),
+            vec![
+                ScriptBit::OpCode(OpCodes::OP_1NEGATE),
+                ScriptBit::OpCode(OpCodes::OP_16),
+                ScriptBit::OpCode(OpCodes::OP_16),
+                ScriptBit::Push(vec![0x85]),
+                ScriptBit::Push(vec![0x64]),
+                ScriptBit::Push(vec![0xe8, 0x83]),
+                ScriptBit::Push(vec![0x39, 0x30]),
+                // Unsigned numbers above 16 are always hex
+                ScriptBit::Push(vec![0x10, 0x00]),
+                ScriptBit::Push(vec![0x17]),
+            ]
+        );
+
+        assert!(Script::from_asm_string("100").is_err());
+        assert!(Script::from_asm_string("12a").is_err());
+        assert!(Script::from_asm_string("-").is_err());
+        assert!(Script::from_asm_string("+1a").is_err());
+    }
+}
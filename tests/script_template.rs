@@ -1,6 +1,6 @@
 #[cfg(test)]
 mod script_template_tests {
-    use bsv::{MatchDataTypes, Script, ScriptTemplate};
+    use bsv::{MatchDataTypes, Script, ScriptTemplate, ScriptTemplateErrors, MAX_TEMPLATE_REPEAT};
 
     #[test]
     fn empty_script_does_not_match_template() {
@@ -347,4 +347,107 @@ mod script_template_tests {
 
         assert_eq!(matching_inputs.len(), 1);
     }
+
+    const PUBKEY: &str = "02e46dcd7991e5a4bd642739249b0158312e1aee56a60fd1bf622172ffe65bd789";
+
+    #[test]
+    fn repetition_matches_any_multisig() {
+        let script_template = ScriptTemplate::from_asm_string("(OP_1:m|OP_2:m|OP_3:m) OP_PUBKEY:keys{1,3} (OP_1:n|OP_2:n|OP_3:n) OP_CHECKMULTISIG").unwrap();
+
+        let script = Script::from_asm_string(&format!("OP_2 {0} {0} {0} OP_3 OP_CHECKMULTISIG", PUBKEY)).unwrap();
+        let matched = script.match_template(&script_template).unwrap();
+        assert_eq!(matched.get("m"), Some(&[0x52][..]));
+        assert_eq!(matched.get("n"), Some(&[0x53][..]));
+        assert_eq!(matched.get_all("keys").len(), 3);
+        assert_eq!(matched.data.len(), 3);
+        assert!(matches!(matched.data[0], (MatchDataTypes::PublicKey, _)));
+
+        assert!(Script::from_asm_string(&format!("OP_1 {} OP_1 OP_CHECKMULTISIG", PUBKEY)).unwrap().is_match(&script_template));
+        assert!(!Script::from_asm_string(&format!("OP_1 {0} {0} {0} {0} OP_1 OP_CHECKMULTISIG", PUBKEY))
+            .unwrap()
+            .is_match(&script_template));
+        assert!(!Script::from_asm_string("OP_1 OP_1 OP_CHECKMULTISIG").unwrap().is_match(&script_template));
+    }
+
+    #[test]
+    fn optional_groups_and_wildcard_tail() {
+        let script_template = ScriptTemplate::from_asm_string("OP_DUP OP_HASH160 OP_PUBKEYHASH:owner OP_EQUALVERIFY OP_CHECKSIG (OP_RETURN ...:tail)?").unwrap();
+        let p2pkh = "OP_DUP OP_HASH160 05186ff0711831d110ca96ddfc47816b5a31900d OP_EQUALVERIFY OP_CHECKSIG";
+
+        let matched = Script::from_asm_string(p2pkh).unwrap().match_template(&script_template).unwrap();
+        assert_eq!(matched.get("owner"), Some(hex::decode("05186ff0711831d110ca96ddfc47816b5a31900d").unwrap().as_slice()));
+        assert_eq!(matched.get("tail"), None);
+
+        // Anything at all may follow OP_RETURN, including bytes that cannot be parsed
+        let script = Script::from_asm_string(&format!("{} OP_RETURN 21e8 OP_DROP", p2pkh)).unwrap();
+        assert_eq!(script.match_template(&script_template).unwrap().get("tail"), Some(&[0x02, 0x21, 0xe8, 0x75][..]));
+
+        let mut bytes = Script::from_asm_string(&format!("{} OP_RETURN", p2pkh)).unwrap().to_bytes();
+        bytes.extend_from_slice(&[0xbb, 0x4c]);
+        let script = Script::from_bytes_lenient(&bytes).script;
+        assert_eq!(script.match_template(&script_template).unwrap().get("tail"), Some(&[0xbb, 0x4c][..]));
+
+        assert!(!Script::from_asm_string(&format!("{} OP_DROP", p2pkh)).unwrap().is_match(&script_template));
+    }
+
+    #[test]
+    fn alternation_and_backtracking() {
+        let script_template = ScriptTemplate::from_asm_string("OP_PUBKEY (OP_CHECKSIG:check | OP_CHECKSIGVERIFY:verify OP_1)").unwrap();
+        let matched = Script::from_asm_string(&format!("{} OP_CHECKSIGVERIFY OP_1", PUBKEY))
+            .unwrap()
+            .match_template(&script_template)
+            .unwrap();
+        assert_eq!(matched.get("verify"), Some(&[0xad][..]));
+        assert_eq!(matched.get("check"), None);
+        assert!(Script::from_asm_string(&format!("{} OP_CHECKSIG", PUBKEY)).unwrap().is_match(&script_template));
+        assert!(!Script::from_asm_string(&format!("{} OP_CHECKSIGVERIFY", PUBKEY)).unwrap().is_match(&script_template));
+
+        // Repetition is greedy but gives back what the rest of the template needs
+        let script = Script::from_asm_string("01 02 03 OP_DROP").unwrap();
+        let matched = script.match_template(&ScriptTemplate::from_asm_string("OP_DATA*:first OP_DATA:last OP_DROP").unwrap()).unwrap();
+        assert_eq!(matched.get_all("first"), &[vec![1], vec![2]]);
+        assert_eq!(matched.get("last"), Some(&[3][..]));
+
+        assert!(script.is_match(&ScriptTemplate::from_asm_string("OP_DATA{3} OP_DROP").unwrap()));
+        assert!(script.is_match(&ScriptTemplate::from_asm_string("OP_DATA{2,} OP_DROP").unwrap()));
+        assert!(!script.is_match(&ScriptTemplate::from_asm_string("OP_DATA{1,2} OP_DROP").unwrap()));
+        assert!(script.is_match(&ScriptTemplate::from_asm_string("OP_DATA:x{1,2} OP_DATA? OP_DROP").unwrap()));
+
+        // Nested repetition does not take exponential time
+        let script = Script::from_asm_string(&vec!["01"; 200].join(" ")).unwrap();
+        assert!(!script.is_match(&ScriptTemplate::from_asm_string("(OP_DATA* OP_DATA*)* OP_DROP").unwrap()));
+    }
+
+    #[test]
+    fn reports_furthest_mismatch() {
+        let script_template = ScriptTemplate::from_asm_string("OP_DATA+ (OP_DROP OP_DROP|OP_DROP OP_1)").unwrap();
+
+        match Script::from_asm_string("01 02 OP_DROP OP_2").unwrap().match_template(&script_template) {
+            Err(ScriptTemplateErrors::MatchFailure(index, _, _, _)) => assert_eq!(index, 3),
+            v => panic!("{:?}", v),
+        }
+        assert!(matches!(
+            Script::from_asm_string("01 02 OP_DROP").unwrap().match_template(&script_template),
+            Err(ScriptTemplateErrors::LengthsDiffer)
+        ));
+    }
+
+    #[test]
+    fn invalid_syntax() {
+        for template in ["(OP_DUP", "OP_DUP)", "OP_DATA{3,1}", "OP_DATA{x}", "OP_DATA:", "OP_DATA??", "(OP_1|OP_2):m", "...+"] {
+            assert!(matches!(ScriptTemplate::from_asm_string(template), Err(ScriptTemplateErrors::Syntax(_))), "{}", template);
+        }
+    }
+
+    #[test]
+    fn repeat_counts_are_bounded() {
+        ScriptTemplate::from_asm_string(&format!("OP_DATA{{{}}}", MAX_TEMPLATE_REPEAT)).unwrap();
+
+        for template in ["OP_DATA{3000000}", "OP_DATA{1,3000000}", "OP_DATA{3000000,}", "(OP_DATA OP_DROP){1001}"] {
+            assert!(matches!(ScriptTemplate::from_asm_string(template), Err(ScriptTemplateErrors::Syntax(_))), "{}", template);
+        }
+
+        // Each count is allowed, but nested they would expand to a million instructions
+        assert!(matches!(ScriptTemplate::from_asm_string("((OP_DATA OP_DROP){1000}){1000}"), Err(ScriptTemplateErrors::Syntax(_))));
+    }
 }
@@ -127,4 +127,50 @@ mod sighash_tests {
 
         assert!(tx.verify(&PublicKey::from_private_key(&priv_key), &sig));
     }
+
+    #[test]
+    fn sighash_otda_uses_original_algorithm() {
+        let priv_key = PrivateKey::from_wif("L31JUXCGspUREe9Gya8F2WWjeoRz3bb8AQzJjAP8ntGYp37oYdSx").unwrap();
+        let signing_script = Script::from_asm_string("OP_0 OP_RETURN").unwrap();
+        let mut tx = Transaction::from_hex("01000000029e8d016a7b0dc49a325922d05da1f916d1e4d4f0cb840c9727f3d22ce8d1363f000000008c493046022100e9318720bee5425378b4763b0427158b1051eec8b08442ce3fbfbf7b30202a44022100d4172239ebd701dae2fbaaccd9f038e7ca166707333427e3fb2a2865b19a7f27014104510c67f46d2cbb29476d1f0b794be4cb549ea59ab9cc1e731969a7bf5be95f7ad5e7f904e5ccf50a9dc1714df00fbeb794aa27aaff33260c1032d931a75c56f2ffffffffa3195e7a1ab665473ff717814f6881485dc8759bebe97e31c301ffe7933a656f020000008b48304502201c282f35f3e02a1f32d2089265ad4b561f07ea3c288169dedcf2f785e6065efa022100e8db18aadacb382eed13ee04708f00ba0a9c40e3b21cf91da8859d0f7d99e0c50141042b409e1ebbb43875be5edde9c452c82c01e3903d38fa4fd89f3887a52cb8aea9dc8aec7e2c9d5b3609c03eb16259a2537135a1bf0f9c5fbbcbdbaf83ba402442ffffffff02206b1000000000001976a91420bb5c3bfaef0231dc05190e7f1c8e22e098991e88acf0ca0100000000001976a9149e3e2d23973a04ec1b02be97c30ab9f2f27c3b2c88ac00000000").unwrap();
+
+        assert_eq!(SigHash::try_from(SigHash::InputsOutput | SigHash::CHRONICLE).unwrap(), SigHash::OTDA_InputsOutput);
+        assert!(SigHash::OTDA_InputsOutput.uses_otda());
+        assert!(SigHash::SINGLE.uses_otda());
+        assert!(!SigHash::InputsOutput.uses_otda());
+
+        // Same preimage as SIGHASH_SINGLE without FORKID, other than the sighash type on the end
+        let sighash_buffer = tx.sighash_preimage(SigHash::OTDA_InputsOutput, 0, &signing_script, 0).unwrap();
+        assert_eq!(
+            sighash_buffer.to_hex(),
+            "01000000029e8d016a7b0dc49a325922d05da1f916d1e4d4f0cb840c9727f3d22ce8d1363f0000000002006affffffffa3195e7a1ab665473ff717814f6881485dc8759bebe97e31c301ffe7933a656f02000000000000000001206b1000000000001976a91420bb5c3bfaef0231dc05190e7f1c8e22e098991e88ac0000000063000000"
+        );
+
+        let sig = tx.sign(&priv_key, SigHash::OTDA_InputsOutput, 0, &signing_script, 0).unwrap();
+        assert_eq!(sig.to_bytes().unwrap().last(), Some(&0x63));
+        assert!(tx.verify(&PublicKey::from_private_key(&priv_key), &sig));
+    }
+
+    #[test]
+    fn sighash_single_blanks_the_outputs_before_the_input() {
+        let priv_key = PrivateKey::from_wif("L31JUXCGspUREe9Gya8F2WWjeoRz3bb8AQzJjAP8ntGYp37oYdSx").unwrap();
+        let signing_script = Script::from_asm_string("OP_0 OP_RETURN").unwrap();
+        let mut tx = Transaction::from_hex("01000000029e8d016a7b0dc49a325922d05da1f916d1e4d4f0cb840c9727f3d22ce8d1363f000000008c493046022100e9318720bee5425378b4763b0427158b1051eec8b08442ce3fbfbf7b30202a44022100d4172239ebd701dae2fbaaccd9f038e7ca166707333427e3fb2a2865b19a7f27014104510c67f46d2cbb29476d1f0b794be4cb549ea59ab9cc1e731969a7bf5be95f7ad5e7f904e5ccf50a9dc1714df00fbeb794aa27aaff33260c1032d931a75c56f2ffffffffa3195e7a1ab665473ff717814f6881485dc8759bebe97e31c301ffe7933a656f020000008b48304502201c282f35f3e02a1f32d2089265ad4b561f07ea3c288169dedcf2f785e6065efa022100e8db18aadacb382eed13ee04708f00ba0a9c40e3b21cf91da8859d0f7d99e0c50141042b409e1ebbb43875be5edde9c452c82c01e3903d38fa4fd89f3887a52cb8aea9dc8aec7e2c9d5b3609c03eb16259a2537135a1bf0f9c5fbbcbdbaf83ba402442ffffffff02206b1000000000001976a91420bb5c3bfaef0231dc05190e7f1c8e22e098991e88acf0ca0100000000001976a9149e3e2d23973a04ec1b02be97c30ab9f2f27c3b2c88ac00000000").unwrap();
+
+        // Signing the second input keeps a blank first output, then the output at the input's index
+        let sighash_buffer = tx.sighash_preimage(SigHash::OTDA_InputsOutput, 1, &signing_script, 0).unwrap();
+        assert_eq!(
+            sighash_buffer.to_hex(),
+            "01000000029e8d016a7b0dc49a325922d05da1f916d1e4d4f0cb840c9727f3d22ce8d1363f000000000000000000a3195e7a1ab665473ff717814f6881485dc8759bebe97e31c301ffe7933a656f0200000002006affffffff02ffffffffffffffff00f0ca0100000000001976a9149e3e2d23973a04ec1b02be97c30ab9f2f27c3b2c88ac0000000063000000"
+        );
+
+        let preimage = Transaction::from_bytes(&sighash_buffer).unwrap();
+        assert_eq!(preimage.get_noutputs(), 2);
+        assert_eq!(preimage.get_output(0).unwrap(), TxOut::new(0xffffffffffffffff, &Script::default()));
+
+        for sighash in [SigHash::OTDA_InputsOutput, SigHash::SINGLE] {
+            let sig = tx.sign(&priv_key, sighash, 1, &signing_script, 0).unwrap();
+            assert!(tx.verify(&PublicKey::from_private_key(&priv_key), &sig));
+        }
+    }
 }
@@ -0,0 +1,132 @@
+#[cfg(test)]
+mod stack_analysis_tests {
+    use bsv::{OpCodes, Script, ScriptBit, ScriptFlags, StackArity, StackIssue};
+
+    fn script(asm: &str) -> Script {
+        Script::from_asm_string(asm).unwrap()
+    }
+
+    fn flat_script(opcodes: &[OpCodes]) -> Script {
+        Script::from_script_bits(opcodes.iter().map(|x| ScriptBit::OpCode(*x)).collect())
+    }
+
+    fn depths(asm: &str) -> Vec<Option<(usize, usize)>> {
+        let analysis = script(asm).analyze_stack(ScriptFlags::default());
+        analysis.steps.iter().map(|x| x.min_depth.zip(x.max_depth)).collect()
+    }
+
+    #[test]
+    fn opcode_metadata_depends_on_era() {
+        let pre_chronicle = ScriptFlags::default();
+        let chronicle = ScriptFlags::UTXO_AFTER_GENESIS | ScriptFlags::UTXO_AFTER_CHRONICLE;
+
+        assert_eq!(OpCodes::OP_2ROT.stack_arity(pre_chronicle), Some(StackArity { inputs: 6, outputs: 6 }));
+        assert_eq!(OpCodes::OP_PICK.stack_arity(pre_chronicle), None);
+
+        assert!(OpCodes::OP_2MUL.is_disabled(pre_chronicle));
+        assert!(!OpCodes::OP_2MUL.is_disabled(chronicle));
+        assert!(OpCodes::OP_VER.is_invalid(pre_chronicle));
+        assert!(!OpCodes::OP_VER.is_disabled(pre_chronicle));
+        assert!(OpCodes::OP_RETURN.is_invalid(ScriptFlags::NONE));
+        assert!(!OpCodes::OP_RETURN.is_invalid(pre_chronicle));
+
+        // NOPs until Chronicle
+        assert_eq!(OpCodes::OP_SUBSTR.stack_arity(pre_chronicle), Some(StackArity { inputs: 0, outputs: 0 }));
+        assert_eq!(OpCodes::OP_SUBSTR.stack_arity(chronicle), Some(StackArity { inputs: 3, outputs: 1 }));
+    }
+
+    #[test]
+    fn p2pkh() {
+        let analysis = script("OP_DUP OP_HASH160 05186ff0710ed004229e644c0653b2985c648a23 OP_EQUALVERIFY OP_CHECKSIG").analyze_stack(ScriptFlags::default());
+
+        assert_eq!(analysis.required_depth, 2);
+        assert_eq!(analysis.max_depth, 4);
+        assert_eq!(analysis.steps[3].arity, Some(StackArity { inputs: 2, outputs: 0 }));
+        assert_eq!(analysis.steps[2].opcode, None);
+        assert!(analysis.issues.is_empty());
+        assert_eq!(analysis.unreachable_from, None);
+        assert_eq!(
+            depths("OP_DUP OP_HASH160 05186ff0710ed004229e644c0653b2985c648a23 OP_EQUALVERIFY OP_CHECKSIG"),
+            vec![Some((3, 3)), Some((3, 3)), Some((4, 4)), Some((2, 2)), Some((1, 1))]
+        );
+    }
+
+    #[test]
+    fn branches() {
+        let analysis = script("OP_IF OP_1 OP_2 OP_3 OP_ELSE OP_DROP OP_ENDIF").analyze_stack(ScriptFlags::default());
+        assert_eq!(analysis.required_depth, 2);
+        assert_eq!(analysis.max_depth, 4);
+        assert_eq!(analysis.branches.len(), 1);
+        assert_eq!((analysis.branches[0].then_max_depth, analysis.branches[0].else_max_depth), (Some(4), Some(1)));
+        assert_eq!(analysis.steps[6].min_depth.zip(analysis.steps[6].max_depth), Some((0, 4)));
+
+        // Conditions pushed as constants only take one side
+        let analysis = script("OP_0 OP_IF OP_2DUP OP_ELSE OP_1 OP_ENDIF").analyze_stack(ScriptFlags::default());
+        assert_eq!(analysis.required_depth, 0);
+        assert_eq!((analysis.branches[0].then_max_depth, analysis.branches[0].else_max_depth), (None, Some(1)));
+        assert!(!analysis.steps[2].reachable);
+        assert!(analysis.steps[5].reachable);
+
+        // Values are followed through stack rearrangements
+        assert_eq!(depths("OP_1 OP_0 OP_SWAP OP_NOTIF OP_DUP OP_ENDIF")[4], None);
+    }
+
+    #[test]
+    fn unreachable_after_op_return() {
+        let analysis = script("OP_1 OP_RETURN OP_DUP OP_DROP").analyze_stack(ScriptFlags::default());
+        assert_eq!(analysis.unreachable_from, Some(2));
+        assert_eq!(analysis.max_depth, 1);
+        assert!(analysis.steps[1].reachable);
+        assert_eq!(analysis.steps[1].max_depth, None);
+
+        // Only some paths return inside a conditional
+        let analysis = script("OP_IF OP_RETURN OP_ENDIF OP_DUP").analyze_stack(ScriptFlags::default());
+        assert_eq!(analysis.unreachable_from, None);
+        assert_eq!(analysis.required_depth, 2);
+        assert_eq!(analysis.branches[0].then_max_depth, Some(1));
+    }
+
+    #[test]
+    fn counts_taken_from_the_stack() {
+        let multisig = "OP_2 02e46dcd7991e5a4bd642739249b0158312e1aee56a60fd1bf622172ffe65bd789 02e46dcd7991e5a4bd642739249b0158312e1aee56a60fd1bf622172ffe65bd789 02e46dcd7991e5a4bd642739249b0158312e1aee56a60fd1bf622172ffe65bd789 OP_3 OP_CHECKMULTISIG";
+        let analysis = script(multisig).analyze_stack(ScriptFlags::default());
+        assert_eq!(analysis.required_depth, 3);
+        assert_eq!(analysis.max_depth, 8);
+        assert_eq!(analysis.steps[5].arity, None);
+        assert_eq!(analysis.steps[5].max_depth, Some(1));
+
+        assert_eq!(script("OP_3 OP_PICK").analyze_stack(ScriptFlags::default()).required_depth, 4);
+        assert_eq!(depths("OP_IFDUP"), vec![Some((1, 2))]);
+
+        let analysis = script("OP_ROLL").analyze_stack(ScriptFlags::default());
+        assert_eq!(analysis.issues, vec![StackIssue::UnknownStackEffect { index: 0, opcode: OpCodes::OP_ROLL }]);
+    }
+
+    #[test]
+    fn issues() {
+        let analysis = script("OP_1 OP_IF OP_ELSE OP_2MUL OP_ENDIF OP_1").analyze_stack(ScriptFlags::default());
+        assert_eq!(analysis.issues, vec![StackIssue::DisabledOpcode { index: 3, opcode: OpCodes::OP_2MUL }]);
+        assert_eq!(analysis.unreachable_from, Some(4));
+
+        let chronicle = ScriptFlags::UTXO_AFTER_GENESIS | ScriptFlags::UTXO_AFTER_CHRONICLE;
+        assert!(script("OP_1 OP_IF OP_ELSE OP_2MUL OP_ENDIF OP_1").analyze_stack(chronicle).issues.is_empty());
+
+        let issues = |opcodes: &[OpCodes]| flat_script(opcodes).analyze_stack(ScriptFlags::default()).issues;
+        let double_else = [OpCodes::OP_IF, OpCodes::OP_ELSE, OpCodes::OP_ELSE, OpCodes::OP_ENDIF];
+        assert_eq!(issues(&double_else), vec![StackIssue::UnbalancedConditional { index: 2 }]);
+        assert!(flat_script(&double_else).analyze_stack(ScriptFlags::NONE).issues.is_empty());
+        assert_eq!(issues(&[OpCodes::OP_IF, OpCodes::OP_1]), vec![StackIssue::UnbalancedConditional { index: 2 }]);
+        assert_eq!(issues(&[OpCodes::OP_ENDIF]), vec![StackIssue::UnbalancedConditional { index: 0 }]);
+        assert_eq!(
+            issues(&[OpCodes::OP_1, OpCodes::OP_TOALTSTACK, OpCodes::OP_FROMALTSTACK, OpCodes::OP_FROMALTSTACK]),
+            vec![StackIssue::EmptyAltStack { index: 3 }]
+        );
+        assert_eq!(
+            issues(&[OpCodes::OP_RESERVED]),
+            vec![StackIssue::InvalidOpcode {
+                index: 0,
+                opcode: OpCodes::OP_RESERVED
+            }]
+        );
+    }
+}
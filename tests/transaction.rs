@@ -478,6 +478,18 @@ mod transaction_tests {
         assert_eq!(&txin.get_outpoint_hex(Some(true)), outpoint)
     }
 
+    #[test]
+    fn count_sigops() {
+        let mut tx = Transaction::new(1, 0);
+        let mut txin = TxIn::new(&[1; 32], 0, &Script::from_asm_string("OP_0 00 OP_CHECKSIG").unwrap(), None);
+        tx.add_input(&txin);
+        assert_eq!(tx.count_sigops(), 1);
+
+        txin.set_locking_script(&Script::from_asm_string("OP_1 02e46dcd7991e5a4bd642739249b0158312e1aee56a60fd1bf622172ffe65bd789 OP_1 OP_CHECKMULTISIGVERIFY OP_CHECKSIG").unwrap());
+        tx.add_input(&txin);
+        assert_eq!(tx.count_sigops(), 1 + 3);
+    }
+
     #[test]
     fn txin_from_outpoint_slice_too_short_should_error() {
         let txin = TxIn::from_outpoint_bytes(&[]);